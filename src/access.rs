@@ -0,0 +1,131 @@
+use frankenstein::types::{ChatType, Message};
+
+use crate::config::{AccessConfig, AccessRule};
+use crate::context::Context;
+use crate::helper::bot_actions;
+use crate::helper::message_utils::{get_command, get_sender_id};
+
+impl AccessRule {
+    pub fn check(&self, chat_id: i64, sender_id: Option<i64>) -> bool {
+        if sender_id.is_some_and(|id| self.denied_users.contains(&id)) || self.denied_chats.contains(&chat_id) {
+            return false;
+        }
+        if self.allowed_users.is_none() && self.allowed_chats.is_none() {
+            return true;
+        }
+        let user_allowed = self.allowed_users.as_ref()
+            .is_some_and(|users| sender_id.is_some_and(|id| users.contains(&id)));
+        let chat_allowed = self.allowed_chats.as_ref()
+            .is_some_and(|chats| chats.contains(&chat_id));
+        user_allowed || chat_allowed
+    }
+}
+
+impl AccessConfig {
+    pub fn is_admin(&self, sender_id: Option<i64>) -> bool {
+        sender_id.is_some_and(|id| self.admins.contains(&id))
+    }
+
+    /// Check the global rule, and the rule of the module if given
+    pub fn is_allowed(&self, module: Option<&str>, chat_id: i64, sender_id: Option<i64>) -> bool {
+        if self.is_admin(sender_id) {
+            return true;
+        }
+        if !self.global.check(chat_id, sender_id) {
+            return false;
+        }
+        match module.and_then(|module| self.modules.get(module)) {
+            Some(rule) => rule.check(chat_id, sender_id),
+            None => true
+        }
+    }
+}
+
+pub fn message_allowed(ctx: &Context, module: Option<&str>, msg: &Message) -> bool {
+    let allowed = ctx.config.access.is_allowed(module, msg.chat.id, get_sender_id(msg));
+    if !allowed {
+        log::debug!(
+            target: "access",
+            "Denied message from {:?} at {} (module: {:?})", get_sender_id(msg), msg.chat.id, module
+        );
+    }
+    allowed
+}
+
+/// Reply the deny message to commands and private messages, other messages are ignored silently
+pub async fn reply_denied(ctx: &Context, msg: &Message) -> anyhow::Result<()> {
+    let Some(deny_message) = ctx.config.access.deny_message.as_ref() else {
+        return Ok(());
+    };
+    if msg.chat.type_field != ChatType::Private && get_command(msg).is_none() {
+        return Ok(());
+    }
+    bot_actions::send_reply_message(&ctx.bot, msg.chat.id, deny_message, msg.message_id, None).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn access(value: serde_json::Value) -> AccessConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn allows_everyone_by_default() {
+        let access = AccessConfig::default();
+        assert!(access.is_allowed(None, -100, Some(1)));
+        assert!(access.is_allowed(Some("pixiv"), -100, None));
+    }
+
+    #[test]
+    fn deny_lists() {
+        let access = access(json!({ "denied_users": [1], "denied_chats": [-100] }));
+        assert!(!access.is_allowed(None, 5, Some(1)));
+        assert!(!access.is_allowed(None, -100, Some(2)));
+        assert!(access.is_allowed(None, -200, Some(2)));
+    }
+
+    #[test]
+    fn allow_lists_match_user_or_chat() {
+        let access = access(json!({ "allowed_users": [1], "allowed_chats": [-100] }));
+        assert!(access.is_allowed(None, 1, Some(1)));
+        assert!(access.is_allowed(None, -100, Some(2)));
+        assert!(!access.is_allowed(None, -200, Some(2)));
+        // Channel posts have no sender
+        assert!(!access.is_allowed(None, -200, None));
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let access = access(json!({ "allowed_chats": [-100], "denied_users": [1] }));
+        assert!(!access.is_allowed(None, -100, Some(1)));
+    }
+
+    #[test]
+    fn module_rules_after_global() {
+        let access = access(json!({
+            "denied_users": [3],
+            "modules": { "kemono": { "allowed_users": [1] } },
+        }));
+        assert!(access.is_allowed(Some("kemono"), 1, Some(1)));
+        assert!(!access.is_allowed(Some("kemono"), 2, Some(2)));
+        assert!(access.is_allowed(Some("pixiv"), 2, Some(2)));
+        assert!(!access.is_allowed(Some("pixiv"), 3, Some(3)));
+    }
+
+    #[test]
+    fn admins_bypass_rules() {
+        let access = access(json!({
+            "admins": [9],
+            "denied_users": [9],
+            "modules": { "kemono": { "allowed_users": [1] } },
+        }));
+        assert!(access.is_admin(Some(9)));
+        assert!(!access.is_admin(None));
+        assert!(access.is_allowed(Some("kemono"), 9, Some(9)));
+    }
+}
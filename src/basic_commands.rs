@@ -1,17 +1,36 @@
 use std::sync::Arc;
 
 use frankenstein::types::Message;
+use futures::future::BoxFuture;
 
-use crate::handler::HandlerResult;
+use crate::handler::{Handler, HandlerResult, UpdateKind};
 use crate::helper::bot_actions;
 use crate::helper::message_utils::{get_chat_sender, get_command};
 use crate::context::Context;
+use crate::i18n::message_locale;
 
 pub const COMMAND_LIST: &[(&'static str, &'static str)] = &[
-    ("help", "顯示幫助信息"),
-    ("exit", "退出當前的功能"),
+    ("help", "cmd.help"),
+    ("exit", "cmd.exit"),
 ];
 
+pub struct BasicHandler;
+
+impl Handler for BasicHandler {
+    fn name(&self) -> &'static str { "basic" }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] { COMMAND_LIST }
+
+    fn help(&self) -> Option<&'static str> { Some("help.basic") }
+
+    // Handled before modal states, so /exit is always available
+    fn priority(&self) -> i32 { -10 }
+
+    fn handle_message(&self, ctx: Arc<Context>, msg: Arc<Message>, _kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        Box::pin(basic_command_handler(ctx, msg))
+    }
+}
+
 pub async fn basic_command_handler(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerResult {
     let command = get_command(&msg);
     if let Some(command) = command {
@@ -21,7 +40,7 @@ pub async fn basic_command_handler(ctx: Arc<Context>, msg: Arc<Message>) -> Hand
                 return Ok(std::ops::ControlFlow::Break(()))
             }
             "help" => {
-                bot_actions::send_message(&ctx.bot, msg.chat.id, HELP_MSG).await?;
+                bot_actions::send_message(&ctx.bot, msg.chat.id, ctx.handlers.help_message(message_locale(&ctx, &msg))).await?;
                 return Ok(std::ops::ControlFlow::Break(()))
             }
             _ => {
@@ -31,11 +50,3 @@ pub async fn basic_command_handler(ctx: Arc<Context>, msg: Arc<Message>) -> Hand
     }
     return Ok(std::ops::ControlFlow::Continue(()))
 }
-
-const HELP_MSG : &'static str = 
-"這裡是薄荷茶～ 目前支持這些功能\n\
-- /help : 顯示幫助信息\n\
-\n\
-貼紙轉換和貼紙下載\n\
-/sticker_convert : 轉換貼紙、圖片和動圖\n\
-/sticker_set_download : 下載貼紙包";
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use frankenstein::AsyncTelegramApi;
+use frankenstein::methods::{AnswerCallbackQueryParams, EditMessageReplyMarkupParams};
+use frankenstein::types::{CallbackQuery, InlineKeyboardButton, MaybeInaccessibleMessage, Message};
+
+use crate::context::Context;
+use crate::error::log_incident;
+use crate::helper::param_builders;
+use crate::i18n::callback_locale;
+
+/// Callback data in `<namespace>:<action>[:<arg>...]` form, e.g. `mon:rm:<uuid>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallbackData {
+    pub namespace: String,
+    pub action: String,
+    pub args: Vec<String>,
+}
+
+impl CallbackData {
+    pub fn parse(data: &str) -> Option<CallbackData> {
+        let mut parts = data.split(':');
+        let namespace = parts.next().filter(|s| !s.is_empty())?.to_string();
+        let action = parts.next().filter(|s| !s.is_empty())?.to_string();
+        let args = parts.map(|s| s.to_string()).collect();
+        Some(CallbackData { namespace, action, args })
+    }
+
+    pub fn encode(namespace: &str, action: &str, args: &[&str]) -> String {
+        let mut data = format!("{namespace}:{action}");
+        for arg in args {
+            data.push(':');
+            data.push_str(arg);
+        }
+        data
+    }
+
+    pub fn arg(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(|s| s.as_str())
+    }
+}
+
+/// The acknowledgement shown to the user who pressed the button
+#[derive(Debug, Clone)]
+pub enum CallbackAnswer {
+    Silent,
+    Notice(String),
+    Alert(String),
+}
+
+pub fn callback_button(text: impl Into<String>, namespace: &str, action: &str, args: &[&str]) -> InlineKeyboardButton {
+    InlineKeyboardButton::builder()
+        .text(text)
+        .callback_data(CallbackData::encode(namespace, action, args))
+        .build()
+}
+
+/// The message the button is attached to, None if it is too old or inline
+pub fn callback_message(query: &CallbackQuery) -> Option<&Message> {
+    match query.message.as_ref()? {
+        MaybeInaccessibleMessage::Message(msg) => Some(msg),
+        MaybeInaccessibleMessage::InaccessibleMessage(_) => None,
+    }
+}
+
+/// Remove the pressed button from the message, rows left empty are removed too
+pub async fn remove_callback_button(ctx: &Context, msg: &Message, callback_data: &str) -> anyhow::Result<()> {
+    let Some(markup) = msg.reply_markup.as_ref() else {
+        return Ok(());
+    };
+    let rows: Vec<Vec<InlineKeyboardButton>> = markup.inline_keyboard.iter()
+        .map(|row| row.iter()
+            .filter(|button| button.callback_data.as_deref() != Some(callback_data))
+            .cloned()
+            .collect::<Vec<_>>()
+        )
+        .filter(|row| !row.is_empty())
+        .collect();
+    let param = EditMessageReplyMarkupParams::builder()
+        .chat_id(msg.chat.id)
+        .message_id(msg.message_id)
+        .reply_markup(param_builders::inline_keyboard(rows))
+        .build();
+    ctx.bot.edit_message_reply_markup(&param).await?;
+    Ok(())
+}
+
+pub async fn answer_callback_query(ctx: &Context, query: &CallbackQuery, answer: CallbackAnswer) -> anyhow::Result<()> {
+    let (text, show_alert) = match answer {
+        CallbackAnswer::Silent => (None, false),
+        CallbackAnswer::Notice(text) => (Some(text), false),
+        CallbackAnswer::Alert(text) => (Some(text), true),
+    };
+    let param = AnswerCallbackQueryParams::builder()
+        .callback_query_id(&query.id)
+        .maybe_text(text)
+        .show_alert(show_alert)
+        .build();
+    ctx.bot.answer_callback_query(&param).await?;
+    Ok(())
+}
+
+/// Route the callback query to the module owning the namespace, and always answer it
+pub async fn handle_callback_query(ctx: Arc<Context>, query: Arc<CallbackQuery>) {
+    let data = query.data.as_deref().and_then(CallbackData::parse);
+
+    let answer = match data {
+        Some(data) => {
+            log::debug!(
+                target: "callback_query",
+                "Callback query from {}: {:?}", query.from.id, data
+            );
+            let chat_id = callback_message(&query).map(|msg| msg.chat.id).unwrap_or(query.from.id as i64);
+            let sender_id = Some(query.from.id as i64);
+            match ctx.handlers.callback_owner(&data.namespace) {
+                Some(handler) if !ctx.config.access.is_allowed(Some(handler.name()), chat_id, sender_id) => {
+                    match ctx.config.access.deny_message.as_ref() {
+                        Some(deny_message) => CallbackAnswer::Alert(deny_message.clone()),
+                        None => CallbackAnswer::Silent
+                    }
+                }
+                Some(handler) => match handler.handle_callback(ctx.clone(), query.clone(), data).await {
+                    Ok(answer) => answer,
+                    Err(e) => CallbackAnswer::Alert(log_incident("callback_query", &e, callback_locale(&ctx, &query)))
+                }
+                None => {
+                    log::debug!(target: "callback_query", "No handler for callback namespace {}", data.namespace);
+                    CallbackAnswer::Silent
+                }
+            }
+        }
+        None => {
+            log::debug!(target: "callback_query", "Ignoring callback query with invalid data {:?}", query.data);
+            CallbackAnswer::Silent
+        }
+    };
+
+    if let Err(e) = answer_callback_query(&ctx, &query, answer).await {
+        log::warn!(target: "callback_query", "Failed to answer callback query: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_with_args() {
+        let data = CallbackData::parse("mon:rm:123e4567").unwrap();
+        assert_eq!(data.namespace, "mon");
+        assert_eq!(data.action, "rm");
+        assert_eq!(data.arg(0), Some("123e4567"));
+        assert_eq!(data.arg(1), None);
+    }
+
+    #[test]
+    fn parse_without_args() {
+        let data = CallbackData::parse("job:list").unwrap();
+        assert!(data.args.is_empty());
+    }
+
+    #[test]
+    fn parse_keeps_empty_args() {
+        let data = CallbackData::parse("set:lang::ja").unwrap();
+        assert_eq!(data.args, vec!["".to_string(), "ja".to_string()]);
+    }
+
+    #[test]
+    fn parse_rejects_missing_namespace_or_action() {
+        for data in ["", "mon", "mon:", ":rm", ":"] {
+            assert_eq!(CallbackData::parse(data), None, "{data}");
+        }
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let encoded = CallbackData::encode("job", "cancel", &["42"]);
+        assert_eq!(encoded, "job:cancel:42");
+        let data = CallbackData::parse(&encoded).unwrap();
+        assert_eq!(data, CallbackData {
+            namespace: "job".to_string(),
+            action: "cancel".to_string(),
+            args: vec!["42".to_string()],
+        });
+        assert_eq!(CallbackData::encode("job", "list", &[]), "job:list");
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::error::Error;
+use std::path::PathBuf;
 
 use serde::Deserialize;
 
+use crate::i18n::Locale;
 use crate::kemono::config::KemonoConfig;
 use crate::pixiv::config::PixivConfig;
 use crate::sticker::config::StickerConfig;
@@ -47,6 +50,297 @@ pub struct BotConfig {
     pub sticker: StickerConfig,
     pub pixiv: PixivConfig,
     pub kemono: KemonoConfig,
+    /// Seconds to wait for running jobs before exiting
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout: u64,
+    /// Enable or disable modules by name, e.g. `{"kemono": false}`, modules are enabled if absent
+    #[serde(default)]
+    pub modules: HashMap<String, bool>,
+    #[serde(default)]
+    pub modal: ModalConfig,
+    #[serde(default)]
+    pub access: AccessConfig,
+    /// Locale for users without a supported language and chats without an override
+    #[serde(default)]
+    pub default_locale: Locale,
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    #[serde(default)]
+    pub download: DownloadConfig,
+    #[serde(default)]
+    pub progress: ProgressConfig,
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+    #[serde(default)]
+    pub upload_cache: UploadCacheConfig,
+    #[serde(default)]
+    pub temp: TempConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+}
+
+fn default_shutdown_timeout() -> u64 { 30 }
+
+/// User and chat restrictions, all users and chats are allowed by default
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccessConfig {
+    /// Bot admins, never restricted
+    #[serde(default)]
+    pub admins: Vec<i64>,
+    /// Restrictions for all modules
+    #[serde(flatten)]
+    pub global: AccessRule,
+    /// Restrictions for modules by name, checked after the global ones
+    #[serde(default)]
+    pub modules: HashMap<String, AccessRule>,
+    /// Reply to denied commands, denied users are ignored silently if not set
+    pub deny_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccessRule {
+    /// If any allow list is set, only the listed users or chats are allowed
+    pub allowed_users: Option<Vec<i64>>,
+    pub allowed_chats: Option<Vec<i64>>,
+    #[serde(default)]
+    pub denied_users: Vec<i64>,
+    #[serde(default)]
+    pub denied_chats: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModalConfig {
+    /// Seconds of inactivity before a modal state expires
+    #[serde(default = "default_modal_ttl")]
+    pub ttl: u64,
+    /// Seconds between two sweeps of expired modal states
+    #[serde(default = "default_modal_sweep_interval")]
+    pub sweep_interval: u64,
+    /// Tell the user when the modal state is expired
+    #[serde(default = "default_modal_expire_notice")]
+    pub expire_notice: bool,
+    /// Save modal states in data directory, so they survive restarts
+    #[serde(default = "default_modal_persist")]
+    pub persist: bool,
+}
+
+impl Default for ModalConfig {
+    fn default() -> Self {
+        ModalConfig {
+            ttl: default_modal_ttl(),
+            sweep_interval: default_modal_sweep_interval(),
+            expire_notice: default_modal_expire_notice(),
+            persist: default_modal_persist(),
+        }
+    }
+}
+
+fn default_modal_ttl() -> u64 { 600 }
+fn default_modal_sweep_interval() -> u64 { 60 }
+fn default_modal_expire_notice() -> bool { true }
+fn default_modal_persist() -> bool { true }
+
+/// Concurrency limits of download jobs, jobs over the limits wait in a FIFO queue
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobsConfig {
+    /// Jobs running at the same time
+    #[serde(default = "default_jobs_max_running")]
+    pub max_running: usize,
+    /// Jobs of the same user running at the same time
+    #[serde(default = "default_jobs_max_per_user")]
+    pub max_per_user: usize,
+    /// Limits by job kind (`pixiv`, `ugoira`, `kemono`, `sticker_set`), e.g. `{"ugoira": 1}`
+    #[serde(default)]
+    pub kind_limits: HashMap<String, usize>,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        JobsConfig {
+            max_running: default_jobs_max_running(),
+            max_per_user: default_jobs_max_per_user(),
+            kind_limits: HashMap::new(),
+        }
+    }
+}
+
+fn default_jobs_max_running() -> usize { 3 }
+fn default_jobs_max_per_user() -> usize { 1 }
+
+/// The download engine shared by pixiv, kemono and sticker sets
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadConfig {
+    /// Concurrent connections to the same host, across all jobs
+    #[serde(default = "default_download_per_host_limit")]
+    pub per_host_limit: usize,
+    /// Overrides of `per_host_limit` by host name, e.g. `{"kemono.cr": 2}`
+    #[serde(default)]
+    pub host_limits: HashMap<String, usize>,
+    /// Retries of a failed file, resumed from the downloaded part if the server supports it
+    #[serde(default = "default_download_max_retries")]
+    pub max_retries: u32,
+    /// Upper bound of the backoff between retries, in seconds
+    #[serde(default = "default_download_max_backoff")]
+    pub max_backoff: u64,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        DownloadConfig {
+            per_host_limit: default_download_per_host_limit(),
+            host_limits: HashMap::new(),
+            max_retries: default_download_max_retries(),
+            max_backoff: default_download_max_backoff(),
+        }
+    }
+}
+
+fn default_download_per_host_limit() -> usize { 4 }
+fn default_download_max_retries() -> u32 { 3 }
+fn default_download_max_backoff() -> u64 { 30 }
+
+/// Progress of download jobs, edits of the progress message are slowed down as the job goes on
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProgressConfig {
+    /// Show the status as reactions on the request instead of a progress message, can be overridden by chats
+    #[serde(default)]
+    pub reactions: bool,
+    /// Seconds before showing the progress, jobs finished earlier show nothing
+    #[serde(default = "default_progress_delay")]
+    pub delay: u64,
+    /// Seconds between edits at the start of a job
+    #[serde(default = "default_progress_min_interval")]
+    pub min_interval: u64,
+    /// Seconds between edits of long jobs
+    #[serde(default = "default_progress_max_interval")]
+    pub max_interval: u64,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        ProgressConfig {
+            reactions: false,
+            delay: default_progress_delay(),
+            min_interval: default_progress_min_interval(),
+            max_interval: default_progress_max_interval(),
+        }
+    }
+}
+
+fn default_progress_delay() -> u64 { 2 }
+fn default_progress_min_interval() -> u64 { 2 }
+fn default_progress_max_interval() -> u64 { 10 }
+
+/// Archives larger than the upload limit are split into several zips
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ArchiveConfig {
+    /// Size limit of each zip in MB, 1 MB under the upload limit of the Bot API server if absent
+    pub part_size: Option<u64>,
+}
+
+impl ArchiveConfig {
+    pub fn part_size_bytes(&self, telegram: &TelegramConfig) -> u64 {
+        let upload_limit = telegram.upload_limit();
+        match self.part_size {
+            Some(part_size) => (part_size * 1_000_000).min(upload_limit),
+            None => upload_limit - 1_000_000,
+        }
+    }
+}
+
+/// File IDs of uploaded pixiv and kemono media are kept to resend the same works without downloading
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadCacheConfig {
+    #[serde(default = "default_upload_cache_enabled")]
+    pub enabled: bool,
+    /// The least recently used entries are dropped beyond this
+    #[serde(default = "default_upload_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for UploadCacheConfig {
+    fn default() -> Self {
+        UploadCacheConfig {
+            enabled: default_upload_cache_enabled(),
+            max_entries: default_upload_cache_max_entries(),
+        }
+    }
+}
+
+fn default_upload_cache_enabled() -> bool { true }
+fn default_upload_cache_max_entries() -> usize { 20000 }
+
+/// The temp directory is cleared at startup, leftovers of crashed jobs are removed periodically
+#[derive(Debug, Clone, Deserialize)]
+pub struct TempConfig {
+    /// Seconds before entries not owned by a running job are removed
+    #[serde(default = "default_temp_max_age")]
+    pub max_age: u64,
+    /// Seconds between sweeps
+    #[serde(default = "default_temp_sweep_interval")]
+    pub sweep_interval: u64,
+    /// Size limit of the temp directory in MB, new jobs are refused beyond it
+    pub quota: Option<u64>,
+}
+
+impl Default for TempConfig {
+    fn default() -> Self {
+        TempConfig {
+            max_age: default_temp_max_age(),
+            sweep_interval: default_temp_sweep_interval(),
+            quota: None,
+        }
+    }
+}
+
+fn default_temp_max_age() -> u64 { 3600 }
+fn default_temp_sweep_interval() -> u64 { 600 }
+
+/// Named HTTP client profiles, e.g. a proxy for pixiv if it is blocked on the network of the server
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HttpConfig {
+    /// Profiles by name, the `default` profile is used by modules without one
+    #[serde(default)]
+    pub profiles: HashMap<String, HttpProfile>,
+    /// Profile names by module (`pixiv`, `kemono`, `fanbox`, `telegraph`, `telegram_files`), e.g. `{"pixiv": "proxy"}`
+    #[serde(default)]
+    pub modules: HashMap<String, String>,
+}
+
+/// Options not set keep the defaults of the module, e.g. `client_user_agent` of pixiv and kemono
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HttpProfile {
+    /// `http://`, `https://` or `socks5://` proxy URL, credentials go in the URL
+    pub proxy: Option<String>,
+    pub user_agent: Option<String>,
+    /// Seconds of a whole request, including the body of downloads
+    pub timeout: Option<u64>,
+    /// Seconds to establish the connection
+    pub connect_timeout: Option<u64>,
+    /// Seconds without receiving any data, which unlike `timeout` does not cut off long downloads
+    pub read_timeout: Option<u64>,
+    /// Headers sent with every request
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Cookies by URL, e.g. `{"https://www.pixiv.net": "a=1; b=2"}`. A cookie header set by the module,
+    /// such as `php_sessid` of pixiv, replaces them
+    #[serde(default)]
+    pub cookies: HashMap<String, String>,
+    /// PEM files of extra root certificates, e.g. of an intercepting proxy
+    #[serde(default)]
+    pub root_certificates: Vec<PathBuf>,
+    /// Skip certificate verification, only for debugging
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    pub min_tls_version: Option<TlsVersion>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum TlsVersion {
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
 }
 
 impl BotConfig {
@@ -55,6 +349,10 @@ impl BotConfig {
         let config: BotConfig = serde_json::from_reader(file)?;
         Ok(config)
     }
+
+    pub fn module_enabled(&self, name: &str) -> bool {
+        self.modules.get(name).copied().unwrap_or(true)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -62,9 +360,136 @@ pub struct TelegramConfig {
     pub token: String,
     #[serde(default = "default_api_server")]
     pub bot_api_server: String,
+    #[serde(default = "default_polling_timeout")]
+    pub polling_timeout: u32,
+    /// Maximum queued updates of a single chat sender, receiving is held back when full
+    #[serde(default = "default_update_queue_size")]
+    pub update_queue_size: usize,
+    /// Receive updates from webhook instead of long polling if present
+    pub webhook: Option<WebhookConfig>,
+    /// How updates queued during downtime are handled on startup
+    #[serde(default)]
+    pub backlog: BacklogConfig,
+    /// Extra update kinds to be processed besides new messages
+    #[serde(default)]
+    pub updates: UpdateKindsConfig,
+    /// Retry and flood control of API calls
+    #[serde(default)]
+    pub api_call: ApiCallConfig,
+    /// `bot_api_server` is a local Bot API server (`--local`) sharing the file system,
+    /// files are read from the path returned by getFile and the size limits rise to 2000 MB
+    #[serde(default)]
+    pub local_mode: bool,
+}
+
+/// Size limits of the cloud Bot API
+const CLOUD_UPLOAD_LIMIT: u64 = 50_000_000;
+const CLOUD_DOWNLOAD_LIMIT: u64 = 20_000_000;
+/// Size limit of a local Bot API server, both ways
+const LOCAL_FILE_LIMIT: u64 = 2_000_000_000;
+
+impl TelegramConfig {
+    /// Largest file the bot can send, in bytes
+    pub fn upload_limit(&self) -> u64 {
+        if self.local_mode { LOCAL_FILE_LIMIT } else { CLOUD_UPLOAD_LIMIT }
+    }
+
+    /// Largest file the bot can get with getFile, in bytes
+    pub fn download_limit(&self) -> u64 {
+        if self.local_mode { LOCAL_FILE_LIMIT } else { CLOUD_DOWNLOAD_LIMIT }
+    }
 }
 
 pub fn default_api_server() -> String { "https://api.telegram.org".to_string() }
+fn default_polling_timeout() -> u32 { 15 }
+fn default_update_queue_size() -> usize { 16 }
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiCallConfig {
+    /// Retries of a call failed by flood control or transient errors
+    #[serde(default = "default_api_max_retries")]
+    pub max_retries: u32,
+    /// Upper bound of the backoff between retries, in seconds
+    #[serde(default = "default_api_max_backoff")]
+    pub max_backoff: u64,
+    /// Minimum milliseconds between two sending calls to the same private chat
+    #[serde(default = "default_api_private_chat_interval")]
+    pub private_chat_interval: u64,
+    /// Minimum milliseconds between two sending calls to the same group or channel,
+    /// Telegram allows about 20 messages per minute in a group
+    #[serde(default = "default_api_group_chat_interval")]
+    pub group_chat_interval: u64,
+    /// Minimum milliseconds between two sending calls to any chat
+    #[serde(default = "default_api_global_interval")]
+    pub global_interval: u64,
+}
+
+impl Default for ApiCallConfig {
+    fn default() -> Self {
+        ApiCallConfig {
+            max_retries: default_api_max_retries(),
+            max_backoff: default_api_max_backoff(),
+            private_chat_interval: default_api_private_chat_interval(),
+            group_chat_interval: default_api_group_chat_interval(),
+            global_interval: default_api_global_interval(),
+        }
+    }
+}
+
+fn default_api_max_retries() -> u32 { 5 }
+fn default_api_max_backoff() -> u64 { 30 }
+fn default_api_private_chat_interval() -> u64 { 1000 }
+fn default_api_group_chat_interval() -> u64 { 3000 }
+fn default_api_global_interval() -> u64 { 35 }
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    /// Public URL registered by setWebhook, usually served by a reverse proxy
+    pub url: String,
+    /// Local address for the HTTP listener
+    #[serde(default = "default_webhook_listen")]
+    pub listen: String,
+    /// Path of the local HTTP listener that accepts updates
+    #[serde(default = "default_webhook_path")]
+    pub path: String,
+    pub secret_token: Option<String>,
+    pub max_connections: Option<u32>,
+    #[serde(default)]
+    pub drop_pending_updates: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BacklogMode {
+    /// Process updates queued during downtime
+    #[default]
+    Replay,
+    /// Drop updates queued during downtime
+    Skip,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BacklogConfig {
+    #[serde(default)]
+    pub mode: BacklogMode,
+    /// Updates older than this (in seconds) are ignored
+    pub max_age: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateKindsConfig {
+    /// Run monitor and link detection on edited messages
+    #[serde(default)]
+    pub edited_message: bool,
+    /// Run monitor and link detection on channel posts
+    #[serde(default)]
+    pub channel_post: bool,
+    #[serde(default)]
+    pub edited_channel_post: bool,
+}
+
+fn default_webhook_listen() -> String { "127.0.0.1:8080".to_string() }
+fn default_webhook_path() -> String { "/".to_string() }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct TelegraphConfig {
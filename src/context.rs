@@ -1,71 +1,264 @@
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use dashmap::DashMap;
-use frankenstein::client_reqwest::Bot;
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Duration};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 
-use crate::config::BotConfig;
+use crate::config::{BotConfig, ModalConfig};
+use crate::handler::HandlerRegistry;
+use crate::helper::bot_actions;
+use crate::helper::download::DownloadEngine;
+use crate::helper::handled_links::HandledLinks;
+use crate::helper::http_client::HttpClients;
+use crate::helper::telegram_client::TelegramClient;
+use crate::helper::upload_cache::UploadCache;
+use crate::i18n::{chat_locale, tr};
+use crate::jobs::in_flight::InFlightRequests;
+use crate::jobs::scheduler::JobScheduler;
 use crate::monitor::MonitorModalState;
 use crate::monitor::context::MonitorContext;
 use crate::pixiv::context::PixivContext;
+use crate::settings::store::SettingsStore;
+use crate::storage::{Document, Migration, Storage, unchanged};
 use crate::sticker::StickerModalState;
 use crate::types::ChatSender;
 
-#[derive(Debug, Clone)]
+struct ModalStatesDocument;
+
+impl Document for ModalStatesDocument {
+    type Data = Vec<SavedModalState>;
+
+    const NAME: &'static str = "modal_states.json";
+    const MIGRATIONS: &'static [Migration] = &[unchanged];
+    const LEGACY_FILE: Option<&'static str> = Some("modal_states.json");
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ModalState {
     Sticker(StickerModalState),
     Monitor(MonitorModalState),
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ModalEntry {
+    state: ModalState,
+    /// Unix timestamp in seconds, extended on every access
+    expires_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedModalState {
+    chat_sender: ChatSender,
+    #[serde(flatten)]
+    entry: ModalEntry,
+}
+
 #[derive(Debug)]
 pub struct ModalStateStorage {
-    map: DashMap<ChatSender, ModalState>,
+    map: DashMap<ChatSender, ModalEntry>,
+    ttl: i64,
+    /// Modal states are saved to the store if set
+    storage: Option<Arc<Storage>>,
+    dirty: AtomicBool,
 }
 
 impl ModalStateStorage {
+    /// Create the storage, and restore the saved states if persistence is enabled
+    pub fn from_config(config: &ModalConfig, storage: Arc<Storage>) -> ModalStateStorage {
+        let states = ModalStateStorage {
+            map: DashMap::new(),
+            ttl: config.ttl as i64,
+            storage: config.persist.then_some(storage),
+            dirty: AtomicBool::new(false),
+        };
+        if let Some(storage) = states.storage.as_ref() {
+            match storage.load::<ModalStatesDocument>() {
+                Ok(saved) => {
+                    let now = chrono::Utc::now().timestamp();
+                    for SavedModalState { chat_sender, entry } in saved.unwrap_or_default() {
+                        if entry.expires_at > now {
+                            states.map.insert(chat_sender, entry);
+                        }
+                    }
+                }
+                Err(e) => log::warn!(target: "modal_state", "Failed to load saved modal states: {e}"),
+            }
+        }
+        states
+    }
+
     pub async fn set_state<T: Into<ChatSender>>(&self, chat_sender: T, state: ModalState) {
-        self.map.insert(chat_sender.into(), state);
+        let expires_at = chrono::Utc::now().timestamp() + self.ttl;
+        self.map.insert(chat_sender.into(), ModalEntry { state, expires_at });
+        self.dirty.store(true, Ordering::Relaxed);
     }
 
+    /// Returns None if the state is expired, otherwise extends its expiry
     pub async fn get_state<T: Into<ChatSender>>(&self, chat_sender: T) -> Option<ModalState> {
-        self.map.get(&chat_sender.into()).map(|lock| lock.value().clone())
+        let now = chrono::Utc::now().timestamp();
+        let mut entry = self.map.get_mut(&chat_sender.into())?;
+        if entry.expires_at <= now {
+            return None;
+        }
+        entry.expires_at = now + self.ttl;
+        self.dirty.store(true, Ordering::Relaxed);
+        Some(entry.state.clone())
     }
 
     pub async fn release_state<T: Into<ChatSender>>(&self, chat_sender: T) -> Option<ModalState> {
-        self.map.remove(&chat_sender.into()).map(|lock| lock.1)
+        let removed = self.map.remove(&chat_sender.into()).map(|(_, entry)| entry.state);
+        if removed.is_some() {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Remove and return the expired states
+    pub fn sweep_expired(&self) -> Vec<(ChatSender, ModalState)> {
+        let now = chrono::Utc::now().timestamp();
+        let expired_keys: Vec<ChatSender> = self.map.iter()
+            .filter(|entry| entry.expires_at <= now)
+            .map(|entry| *entry.key())
+            .collect();
+        let expired: Vec<(ChatSender, ModalState)> = expired_keys.into_iter()
+            // Checked again, the state may be set again in between
+            .filter_map(|key| self.map.remove_if(&key, |_, entry| entry.expires_at <= now))
+            .map(|(key, entry)| (key, entry.state))
+            .collect();
+        if !expired.is_empty() {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        expired
+    }
+
+    /// Write the states to the store if changed and persistence is enabled
+    pub async fn save(&self) {
+        let Some(storage) = self.storage.as_ref() else {
+            return;
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let snapshot = || self.map.iter()
+            .map(|entry| SavedModalState { chat_sender: *entry.key(), entry: entry.value().clone() })
+            .collect();
+        if let Err(e) = storage.save::<ModalStatesDocument>(snapshot).await {
+            log::warn!(target: "modal_state", "Failed to save modal states: {e}");
+            self.dirty.store(true, Ordering::Relaxed);
+        }
     }
 }
 
 impl Default for ModalStateStorage {
     fn default() -> Self {
         ModalStateStorage {
-            map: DashMap::new()
+            map: DashMap::new(),
+            ttl: ModalConfig::default().ttl as i64,
+            storage: None,
+            dirty: AtomicBool::new(false),
         }
     }
 }
 
+/// Remove expired modal states periodically, and save the states if changed
+pub async fn modal_state_sweeper(ctx: Arc<Context>) {
+    let interval = Duration::from_secs(ctx.config.modal.sweep_interval.max(1));
+    loop {
+        tokio::select! {
+            _ = sleep(interval) => {},
+            _ = ctx.shutdown.cancelled() => break,
+        }
+
+        for (chat_sender, state) in ctx.modal_states.sweep_expired() {
+            log::info!(
+                target: "modal_state",
+                "Modal state of {}:{} expired: {:?}", chat_sender.chat_id, chat_sender.sender_id, state
+            );
+            if ctx.config.modal.expire_notice {
+                let result = bot_actions::send_message(
+                    &ctx.bot, chat_sender.chat_id,
+                    tr(chat_locale(&ctx, chat_sender.chat_id), "modal.expired")
+                ).await;
+                if let Err(e) = result {
+                    log::warn!(target: "modal_state", "Failed to send expire notice: {e}");
+                }
+            }
+        }
+
+        ctx.modal_states.save().await;
+    }
+}
+
 #[derive(Debug)]
 pub struct Context {
-    pub bot: Bot,
+    pub bot: TelegramClient,
     pub config: BotConfig,
     pub temp_root_path: PathBuf,
+    #[allow(unused)]
     pub data_root_path: PathBuf,
+    /// Versioned documents in the data directory
+    pub storage: Arc<Storage>,
     pub modal_states: ModalStateStorage,
+    /// Enabled modules, drives dispatch, command list and /help
+    pub handlers: HandlerRegistry,
+    /// Per-chat overrides of config.json
+    pub settings: SettingsStore,
+    /// Limits concurrent download jobs
+    pub jobs: JobScheduler,
+    /// Identical requests wait for the first one
+    pub in_flight: InFlightRequests,
+    /// Links detected in messages, edits only react to new ones
+    pub handled_links: HandledLinks,
+    pub downloader: DownloadEngine,
+    /// File IDs of uploaded media, to resend without downloading
+    pub upload_cache: UploadCache,
+    /// HTTP clients of the modules, built from the profiles in config
+    pub http: HttpClients,
     pub pixiv: PixivContext,
     pub monitor: MonitorContext,
+    /// Tracks update handlers and background writes, waited on shutdown
+    pub tasks: TaskTracker,
+    /// Cancelled when the bot is requested to shut down
+    pub shutdown: CancellationToken,
 }
 
 impl Context {
-    pub fn _new(bot: Bot, config: BotConfig, temp_root_path: PathBuf, data_root_path: PathBuf) -> Context {
-        let pixiv =  PixivContext::from_config(&config.pixiv).expect("Failed to create Pixiv Context");
+    pub fn _new(bot: TelegramClient, config: BotConfig, temp_root_path: PathBuf, data_root_path: PathBuf) -> Context {
+        let pixiv =  PixivContext::from_config(&config).expect("Failed to create Pixiv Context");
         let monitor = MonitorContext::default();
+        let storage = Arc::new(Storage::new(&data_root_path));
+        let settings = SettingsStore::load(storage.clone());
+        let jobs = JobScheduler::new(config.jobs.clone());
+        let downloader = DownloadEngine::new(config.download.clone());
+        let upload_cache = UploadCache::from_config(&config.upload_cache, storage.clone());
+        let http = HttpClients::from_config(&config).expect("Failed to create HTTP clients");
         Context {
             bot,
             config,
             temp_root_path,
             data_root_path,
+            storage,
             modal_states: ModalStateStorage::default(),
+            handlers: HandlerRegistry::default(),
+            settings,
+            jobs,
+            in_flight: InFlightRequests::default(),
+            handled_links: HandledLinks::default(),
+            downloader,
+            upload_cache,
+            http,
             pixiv,
-            monitor
+            monitor,
+            tasks: TaskTracker::new(),
+            shutdown: CancellationToken::new(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,152 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use frankenstein::types::Message;
+use reqwest::StatusCode;
+use uuid::Uuid;
+
+use crate::context::Context;
+use crate::helper::bot_actions;
+use crate::helper::download::DownloadError;
+use crate::helper::log::LogOp;
+use crate::i18n::{Locale, message_locale, tr, tr_args};
+use crate::jobs::scheduler::JobCancelled;
+
+/// What went wrong, from the view of users
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Failed to reach the upstream site or Telegram
+    Network,
+    /// The upstream site has no such content
+    UpstreamNotFound,
+    /// The upstream site refused the request
+    UpstreamBlocked,
+    /// The content is too large to be sent
+    TooLarge,
+    /// Telegram refused the request, e.g. an invalid media group
+    TelegramRejected,
+    Internal,
+}
+
+impl ErrorKind {
+    /// Message key of the reply, filled with the incident ID
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            ErrorKind::Network => "error.network",
+            ErrorKind::UpstreamNotFound => "error.upstream_not_found",
+            ErrorKind::UpstreamBlocked => "error.upstream_blocked",
+            ErrorKind::TooLarge => "error.too_large",
+            ErrorKind::TelegramRejected => "error.telegram_rejected",
+            ErrorKind::Internal => "error.internal",
+        }
+    }
+
+    fn from_status(status: StatusCode) -> ErrorKind {
+        match status {
+            StatusCode::NOT_FOUND | StatusCode::GONE => ErrorKind::UpstreamNotFound,
+            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED | StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS => ErrorKind::UpstreamBlocked,
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorKind::TooLarge,
+            status if status.is_server_error() => ErrorKind::Network,
+            _ => ErrorKind::Internal,
+        }
+    }
+
+    fn from_reqwest(error: &reqwest::Error) -> ErrorKind {
+        if let Some(status) = error.status() {
+            ErrorKind::from_status(status)
+        } else if error.is_decode() {
+            // The upstream returned something unexpected
+            ErrorKind::Internal
+        } else {
+            ErrorKind::Network
+        }
+    }
+
+    fn from_telegram(error: &frankenstein::Error) -> ErrorKind {
+        match error {
+            frankenstein::Error::Api(response) if response.error_code == 413 => ErrorKind::TooLarge,
+            frankenstein::Error::Api(_) => ErrorKind::TelegramRejected,
+            frankenstein::Error::HttpReqwest(error) => ErrorKind::from_reqwest(error),
+            _ => ErrorKind::Internal,
+        }
+    }
+
+    /// Classify by the first known error in the chain
+    pub fn classify(error: &anyhow::Error) -> ErrorKind {
+        for cause in error.chain() {
+            if let Some(error) = cause.downcast_ref::<BotError>() {
+                return error.kind;
+            }
+            if let Some(error) = cause.downcast_ref::<frankenstein::Error>() {
+                return ErrorKind::from_telegram(error);
+            }
+            if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+                return ErrorKind::from_reqwest(error);
+            }
+            if let Some(error) = cause.downcast_ref::<DownloadError>() {
+                return match error {
+                    DownloadError::ReqwestError(error) => ErrorKind::from_reqwest(error),
+                    DownloadError::Unsuccess(status) => ErrorKind::from_status(*status),
+                    DownloadError::IoError(_) => ErrorKind::Internal,
+                    // Corrupted in transfer
+                    DownloadError::ChecksumMismatch { .. } => ErrorKind::Network,
+                };
+            }
+        }
+        ErrorKind::Internal
+    }
+}
+
+/// An error raised by handlers when the kind is known,
+/// e.g. `return Err(BotError::new(ErrorKind::TooLarge, "archive is 60 MB").into())`
+#[derive(Debug)]
+pub struct BotError {
+    pub kind: ErrorKind,
+    pub detail: String,
+}
+
+impl BotError {
+    pub fn new(kind: ErrorKind, detail: impl Into<String>) -> BotError {
+        BotError { kind, detail: detail.into() }
+    }
+}
+
+impl Display for BotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.detail)
+    }
+}
+
+impl Error for BotError {}
+
+/// Short ID shown to users and written to the log, to find the log of a reported failure
+pub fn new_incident_id() -> String {
+    let mut id = Uuid::new_v4().simple().to_string();
+    id.truncate(8);
+    id
+}
+
+/// Log the error with a new incident ID, returns the localized text for users
+pub fn log_incident(target: &str, error: &anyhow::Error, locale: Locale) -> String {
+    let kind = ErrorKind::classify(error);
+    let incident_id = new_incident_id();
+    log::error!(
+        target: target,
+        "[Incident {incident_id}] {kind:?}: {error}, detail: {error:?}"
+    );
+    tr_args(locale, kind.message_key(), &[&incident_id])
+}
+
+/// Log the error of a handler and reply to the message triggering it
+pub async fn report_error(ctx: &Context, msg: &Message, error: &anyhow::Error) {
+    // Cancelled by the user, not a failure
+    let text = if error.chain().any(|cause| cause.is::<JobCancelled>()) {
+        log::info!(target: "handler_error", "{} Job cancelled", LogOp(msg));
+        tr(message_locale(ctx, msg), "jobs.cancelled").to_string()
+    } else {
+        log_incident("handler_error", error, message_locale(ctx, msg))
+    };
+    if let Err(e) = bot_actions::send_reply_message(&ctx.bot, msg.chat.id, text, msg.message_id, None).await {
+        log::warn!(target: "handler_error", "Failed to reply error message: {e}");
+    }
+}
@@ -1,2 +1,138 @@
+use std::sync::Arc;
+
+use frankenstein::inline_mode::InlineQuery;
+use frankenstein::types::{BotCommand, CallbackQuery, Message};
+use futures::future::BoxFuture;
+
+use crate::callback::{CallbackAnswer, CallbackData};
+use crate::config::BotConfig;
+use crate::context::{Context, ModalState};
+use crate::i18n::{Locale, tr};
+
 pub type HandlerResult = anyhow::Result<std::ops::ControlFlow<(), ()>>;
-pub type ModalHandlerResult = anyhow::Result<()>;
\ No newline at end of file
+pub type ModalHandlerResult = anyhow::Result<()>;
+pub type CallbackHandlerResult = anyhow::Result<crate::callback::CallbackAnswer>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateKind {
+    Message,
+    EditedMessage,
+    ChannelPost,
+    EditedChannelPost,
+    InlineQuery,
+}
+
+/// A feature module, registered into [`HandlerRegistry`] on startup
+pub trait Handler: Send + Sync {
+    /// Module name, used as the key for enabling or disabling in `BotConfig.modules`
+    fn name(&self) -> &'static str;
+
+    /// Commands with the message keys of their descriptions, registered by `set_my_commands` and listed in /help
+    fn commands(&self) -> &'static [(&'static str, &'static str)] { &[] }
+
+    /// Message key of the section title in /help, the module is hidden from /help if None
+    fn help(&self) -> Option<&'static str> { None }
+
+    /// Handlers with lower priority run first,
+    /// negative priority runs before the message is routed to modal states
+    fn priority(&self) -> i32 { 0 }
+
+    /// Update kinds dispatched to this handler
+    fn update_kinds(&self) -> &'static [UpdateKind] { &[UpdateKind::Message] }
+
+    /// Whether `BotConfig.access` limits the messages dispatched to this handler,
+    /// false for handlers watching every message rather than serving the sender
+    fn access_controlled(&self) -> bool { true }
+
+    fn handle_message(&self, _ctx: Arc<Context>, _msg: Arc<Message>, _kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        Box::pin(async { Ok(std::ops::ControlFlow::Continue(())) })
+    }
+
+    /// Whether the modal state is owned by this handler
+    fn owns_modal_state(&self, _state: &ModalState) -> bool { false }
+
+    fn handle_modal(&self, _ctx: Arc<Context>, _msg: Arc<Message>, _state: ModalState) -> BoxFuture<'static, ModalHandlerResult> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Namespace of callback data handled by this handler
+    fn callback_namespace(&self) -> Option<&'static str> { None }
+
+    fn handle_callback(&self, _ctx: Arc<Context>, _query: Arc<CallbackQuery>, _data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+        Box::pin(async { Ok(CallbackAnswer::Silent) })
+    }
+
+    fn handle_inline_query(&self, _ctx: Arc<Context>, _query: Arc<InlineQuery>) -> BoxFuture<'static, anyhow::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Enabled handlers, sorted by priority
+#[derive(Default)]
+pub struct HandlerRegistry {
+    handlers: Vec<Arc<dyn Handler>>,
+}
+
+impl HandlerRegistry {
+    pub fn new(handlers: Vec<Arc<dyn Handler>>, config: &BotConfig) -> HandlerRegistry {
+        let mut handlers: Vec<Arc<dyn Handler>> = handlers.into_iter()
+            .filter(|handler| {
+                let enabled = config.module_enabled(handler.name());
+                if !enabled {
+                    log::info!(target: "handler_registry", "Module {} is disabled", handler.name());
+                }
+                enabled
+            })
+            .collect();
+        // Stable sort, handlers with the same priority keep the registration order
+        handlers.sort_by_key(|handler| handler.priority());
+        HandlerRegistry { handlers }
+    }
+
+    /// Handlers accepting the update kind, in priority order
+    pub fn for_kind(&self, kind: UpdateKind) -> impl Iterator<Item = &Arc<dyn Handler>> {
+        self.handlers.iter().filter(move |handler| handler.update_kinds().contains(&kind))
+    }
+
+    pub fn modal_owner(&self, state: &ModalState) -> Option<&Arc<dyn Handler>> {
+        self.handlers.iter().find(|handler| handler.owns_modal_state(state))
+    }
+
+    pub fn callback_owner(&self, namespace: &str) -> Option<&Arc<dyn Handler>> {
+        self.handlers.iter().find(|handler| handler.callback_namespace() == Some(namespace))
+    }
+
+    pub fn bot_commands(&self, locale: Locale) -> Vec<BotCommand> {
+        self.handlers.iter()
+            .flat_map(|handler| handler.commands())
+            .map(|(command, desc)|
+                BotCommand::builder()
+                .command(*command)
+                .description(tr(locale, desc))
+                .build()
+            )
+            .collect()
+    }
+
+    pub fn help_message(&self, locale: Locale) -> String {
+        let mut help = format!("{}\n", tr(locale, "help.header"));
+        for handler in &self.handlers {
+            let Some(title) = handler.help() else {
+                continue;
+            };
+            help.push_str(&format!("\n{}\n", tr(locale, title)));
+            for (command, desc) in handler.commands() {
+                help.push_str(&format!("/{command} : {}\n", tr(locale, desc)));
+            }
+        }
+        help
+    }
+}
+
+impl std::fmt::Debug for HandlerRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.handlers.iter().map(|handler| handler.name()))
+            .finish()
+    }
+}
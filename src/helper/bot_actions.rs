@@ -1,14 +1,14 @@
 use frankenstein::response::{MessageOrBool, MethodResponse};
 use frankenstein::AsyncTelegramApi;
-use frankenstein::types::{ChatAction, Message};
-use frankenstein::methods::{DeleteMessageParams, EditMessageTextParams, SendChatActionParams, SendMessageParams};
-use frankenstein::client_reqwest::Bot;
+use frankenstein::types::{ChatAction, InlineKeyboardMarkup, Message, ReactionType, ReactionTypeEmoji, ReplyMarkup};
+use frankenstein::methods::{DeleteMessageParams, EditMessageTextParams, SendChatActionParams, SendMessageParams, SetMessageReactionParams};
 
 use crate::helper::param_builders;
+use crate::helper::telegram_client::TelegramClient;
 
 // This module is designed to reduce builder codes
 
-pub async fn send_message(bot: &Bot, chat_id: i64, text: impl Into<String>) -> Result<Message, frankenstein::Error> {
+pub async fn send_message(bot: &TelegramClient, chat_id: i64, text: impl Into<String>) -> Result<Message, frankenstein::Error> {
     let send_message_param = SendMessageParams::builder()
         .chat_id(chat_id)
         .text(text)
@@ -16,7 +16,7 @@ pub async fn send_message(bot: &Bot, chat_id: i64, text: impl Into<String>) -> R
     Ok(bot.send_message(&send_message_param).await?.result)
 }
 
-pub async fn send_html_message(bot: &Bot, chat_id: i64, text: impl Into<String>) -> Result<Message, frankenstein::Error> {
+pub async fn send_html_message(bot: &TelegramClient, chat_id: i64, text: impl Into<String>) -> Result<Message, frankenstein::Error> {
     let send_message_param = SendMessageParams::builder()
         .chat_id(chat_id)
         .parse_mode(frankenstein::ParseMode::Html)
@@ -25,8 +25,22 @@ pub async fn send_html_message(bot: &Bot, chat_id: i64, text: impl Into<String>)
     Ok(bot.send_message(&send_message_param).await?.result)
 }
 
+pub async fn send_message_with_markup(
+    bot: &TelegramClient, 
+    chat_id: i64, 
+    text: impl Into<String>, 
+    markup: ReplyMarkup
+) -> Result<Message, frankenstein::Error> {
+    let send_message_param = SendMessageParams::builder()
+        .chat_id(chat_id)
+        .text(text)
+        .reply_markup(markup)
+        .build();
+    Ok(bot.send_message(&send_message_param).await?.result)
+}
+
 pub async fn send_reply_message(
-    bot: &Bot, 
+    bot: &TelegramClient, 
     chat_id: i64, 
     text: impl Into<String>, 
     reply_message_id: i32,
@@ -42,16 +56,42 @@ pub async fn send_reply_message(
     Ok(bot.send_message(&send_message_param).await?.result)
 }
 
-pub async fn edit_message_text(bot: &Bot, chat_id: i64, message_id: i32, text: impl Into<String>) -> Result<MessageOrBool, frankenstein::Error> {
+pub async fn send_reply_message_with_markup(
+    bot: &TelegramClient, 
+    chat_id: i64, 
+    text: impl Into<String>, 
+    reply_message_id: i32,
+    markup: ReplyMarkup
+) -> Result<Message, frankenstein::Error> {
+    let reply_param = param_builders::reply_parameters(reply_message_id, None);
+
+    let send_message_param = SendMessageParams::builder()
+        .chat_id(chat_id)
+        .reply_parameters(reply_param)
+        .text(text)
+        .reply_markup(markup)
+        .build();
+    Ok(bot.send_message(&send_message_param).await?.result)
+}
+
+/// Edit the text and keep the inline keyboard, which is removed if not given
+pub async fn edit_message_text_with_markup(
+    bot: &TelegramClient, 
+    chat_id: i64, 
+    message_id: i32, 
+    text: impl Into<String>,
+    markup: InlineKeyboardMarkup
+) -> Result<MessageOrBool, frankenstein::Error> {
     let edit_message_text_param = EditMessageTextParams::builder()
         .chat_id(chat_id)
         .message_id(message_id)
         .text(text)
+        .reply_markup(markup)
         .build();
     Ok(bot.edit_message_text(&edit_message_text_param).await?.result)
 }
 
-pub async fn delete_message(bot: &Bot, chat_id: i64, message_id: i32) -> Result<MethodResponse<bool>, frankenstein::Error> {
+pub async fn delete_message(bot: &TelegramClient, chat_id: i64, message_id: i32) -> Result<MethodResponse<bool>, frankenstein::Error> {
     let param = DeleteMessageParams::builder()
         .chat_id(chat_id)
         .message_id(message_id)
@@ -59,7 +99,21 @@ pub async fn delete_message(bot: &Bot, chat_id: i64, message_id: i32) -> Result<
     Ok(bot.delete_message(&param).await?)
 }
 
-pub async fn sent_chat_action(bot: &Bot, chat_id: i64, action: ChatAction) -> Result<MethodResponse<bool>, frankenstein::Error>  {
+/// Replace the reactions of the bot on the message, or remove them if None
+pub async fn set_reaction(bot: &TelegramClient, chat_id: i64, message_id: i32, emoji: Option<&str>) -> Result<MethodResponse<bool>, frankenstein::Error> {
+    let reaction = emoji
+        .map(|emoji| ReactionType::Emoji(ReactionTypeEmoji::builder().emoji(emoji).build()))
+        .into_iter()
+        .collect();
+    let param = SetMessageReactionParams::builder()
+        .chat_id(chat_id)
+        .message_id(message_id)
+        .reaction(reaction)
+        .build();
+    bot.set_message_reaction(&param).await
+}
+
+pub async fn sent_chat_action(bot: &TelegramClient, chat_id: i64, action: ChatAction) -> Result<MethodResponse<bool>, frankenstein::Error>  {
     let param = SendChatActionParams::builder()
         .chat_id(chat_id)
         .action(action)
@@ -1,14 +1,26 @@
 use std::error::Error;
 use std::fmt::Display;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
 
-use frankenstein::client_reqwest::Bot;
+use dashmap::DashMap;
 use frankenstein::methods::GetFileParams;
 use frankenstein::{reqwest, AsyncTelegramApi};
+use futures::StreamExt;
+use reqwest::header::{ETAG, HeaderMap, HeaderName, HeaderValue, IF_RANGE, LAST_MODIFIED, RANGE};
 use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
 use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 
+use crate::config::DownloadConfig;
 use crate::context::Context;
+use crate::helper::telegram_client::TelegramClient;
+
 #[derive(Debug, Clone, Default)]
 pub struct TelegramFileInfo {
     pub file_path: String,
@@ -42,7 +54,7 @@ fn path_to_filename(path: impl Into<String>) -> String {
 }
 
 pub async fn get_telegram_file_info(
-    bot: &Bot,
+    bot: &TelegramClient,
     file_id: &str,
 ) -> anyhow::Result<Option<TelegramFileInfo>> {
     let result = bot.get_file(&GetFileParams::builder().file_id(file_id).build()).await?.result;
@@ -58,6 +70,22 @@ pub enum DownloadError {
     ReqwestError(reqwest::Error),
     IoError(std::io::Error),
     Unsuccess(StatusCode),
+    /// The SHA-256 digest of the downloaded file is not the expected one
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+impl DownloadError {
+    /// Whether another attempt may succeed
+    fn is_transient(&self) -> bool {
+        match self {
+            DownloadError::ReqwestError(error) => !error.is_builder() && !error.is_redirect(),
+            DownloadError::IoError(_) => false,
+            DownloadError::Unsuccess(status) => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS || *status == StatusCode::REQUEST_TIMEOUT
+            }
+            DownloadError::ChecksumMismatch { .. } => true,
+        }
+    }
 }
 
 impl Display for DownloadError {
@@ -66,13 +94,15 @@ impl Display for DownloadError {
             DownloadError::ReqwestError(error) => write!(f, "ReqwestError: {:?}", error),
             DownloadError::IoError(error) => write!(f, "IoError: {:?}", error),
             DownloadError::Unsuccess(status_code) => write!(f, "Unsuccess Request: {:?}", status_code),
+            DownloadError::ChecksumMismatch { expected, actual } => write!(f, "Checksum Mismatch: expected {expected}, got {actual}"),
         }
     }
 }
 
 impl From<reqwest::Error> for DownloadError {
     fn from(value: reqwest::Error) -> Self {
-        Self::ReqwestError(value)
+        // URLs of Telegram files contain the bot token, so they are never kept in errors
+        Self::ReqwestError(value.without_url())
     }
 }
 
@@ -107,42 +137,371 @@ pub async fn download_url_to_memory(
     }
 }
 
-pub async fn download_url_to_file(
-    client: Option<Client>,
-    url: &str,
-    file: &mut tokio::fs::File
-) -> Result<(), DownloadError> {
+/// Where a [`DownloadTask`] gets the file from
+#[derive(Debug, Clone)]
+pub enum DownloadSource {
+    Url(String),
+    /// A file on the local file system, e.g. kept by a local Bot API server
+    Local(PathBuf),
+}
 
-    let client = match client {
-        Some(client) => client,
-        // Possibly use other user agent
-        None => Client::builder().build()?
-    };
+/// A file to be downloaded by [`DownloadEngine`]
+#[derive(Debug, Clone)]
+pub struct DownloadTask {
+    pub source: DownloadSource,
+    pub save_path: PathBuf,
+    /// Extra request headers, e.g. the Referer required by pixiv
+    pub headers: HeaderMap,
+    /// Expected SHA-256 digest in hex, verified after the download
+    pub sha256: Option<String>,
+}
 
-    let mut resp = client.get(url)
-        .send().await
-        .map_err(|e| DownloadError::ReqwestError(e))?;
+impl DownloadTask {
+    pub fn new(url: impl Into<String>, save_path: impl Into<PathBuf>) -> DownloadTask {
+        DownloadTask {
+            source: DownloadSource::Url(url.into()),
+            save_path: save_path.into(),
+            headers: HeaderMap::new(),
+            sha256: None,
+        }
+    }
 
-    let status = resp.status();
+    /// Copy the local file instead of downloading
+    pub fn local(path: impl Into<PathBuf>, save_path: impl Into<PathBuf>) -> DownloadTask {
+        DownloadTask {
+            source: DownloadSource::Local(path.into()),
+            save_path: save_path.into(),
+            headers: HeaderMap::new(),
+            sha256: None,
+        }
+    }
+
+    pub fn header(mut self, name: HeaderName, value: &'static str) -> DownloadTask {
+        self.headers.insert(name, HeaderValue::from_static(value));
+        self
+    }
+
+    pub fn sha256(mut self, digest: impl Into<String>) -> DownloadTask {
+        self.sha256 = Some(digest.into());
+        self
+    }
+
+    /// Shown in logs instead of the URL, which may contain the bot token
+    pub fn label(&self) -> String {
+        self.save_path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Progress of [`DownloadEngine::download_all`], passed to the progress callback
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DownloadProgress {
+    pub total_items: usize,
+    pub completed_items: usize,
+    pub failed_items: usize,
+    /// Bytes written, including the ones of retried attempts which started over
+    pub downloaded_bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct CompletedDownload {
+    /// Index of the task in the given list
+    pub index: usize,
+    pub save_path: PathBuf,
+    pub size: u64,
+}
+
+#[derive(Debug)]
+pub struct FailedDownload {
+    /// Index of the task in the given list
+    pub index: usize,
+    pub label: String,
+    pub error: DownloadError,
+}
+
+/// Result of [`DownloadEngine::download_all`], both lists are sorted by the task index
+#[derive(Debug, Default)]
+pub struct DownloadReport {
+    pub completed: Vec<CompletedDownload>,
+    pub failed: Vec<FailedDownload>,
+    /// The remaining tasks are dropped by the cancellation token
+    pub cancelled: bool,
+}
+
+impl DownloadReport {
+    pub fn log_failures(&self, target: &str) {
+        for failure in &self.failed {
+            log::warn!(target: target, "Failed to download {}: {}", failure.label, failure.error);
+        }
+    }
+}
+
+/// Downloads of all modules go through here,
+/// limits the connections to each host, retries transient failures and resumes partial files
+#[derive(Debug)]
+pub struct DownloadEngine {
+    config: DownloadConfig,
+    hosts: DashMap<String, Arc<Semaphore>>,
+}
+
+impl DownloadEngine {
+    pub fn new(config: DownloadConfig) -> DownloadEngine {
+        DownloadEngine { config, hosts: DashMap::new() }
+    }
+
+    fn host_semaphore(&self, url: &str) -> Arc<Semaphore> {
+        let host = reqwest::Url::parse(url).ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_default();
+        let limit = self.config.host_limits.get(&host).copied().unwrap_or(self.config.per_host_limit);
+        self.hosts.entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(limit.max(1))))
+            .clone()
+    }
+
+    /// Download a single file with retries
+    pub async fn download(&self, client: &Client, task: &DownloadTask) -> Result<u64, DownloadError> {
+        self.download_with_progress(client, task, &|_| {}).await
+    }
+
+    async fn download_with_progress(
+        &self,
+        client: &Client,
+        task: &DownloadTask,
+        on_bytes: &(dyn Fn(u64) + Send + Sync)
+    ) -> Result<u64, DownloadError> {
+        let mut attempt = 0;
+        // Of the file the partial download belongs to, kept across the attempts
+        let mut validator = None;
+        loop {
+            let error = match self.try_download(client, task, &mut validator, on_bytes).await {
+                Ok(size) => return Ok(size),
+                Err(error) => error,
+            };
+            if attempt >= self.config.max_retries || !error.is_transient() {
+                return Err(error);
+            }
+            attempt += 1;
+            let delay = Duration::from_secs(2u64.saturating_pow(attempt).min(self.config.max_backoff));
+            log::warn!(
+                target: "download",
+                "Failed to download {}: {error}, retrying in {}s ({attempt}/{})",
+                task.label(), delay.as_secs(), self.config.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// One attempt, verifies the checksum if given
+    async fn try_download(
+        &self,
+        client: &Client,
+        task: &DownloadTask,
+        validator: &mut Option<HeaderValue>,
+        on_bytes: &(dyn Fn(u64) + Send + Sync)
+    ) -> Result<u64, DownloadError> {
+        let size = match &task.source {
+            DownloadSource::Url(url) => self.fetch_url(client, url, task, validator, on_bytes).await?,
+            DownloadSource::Local(path) => {
+                let size = tokio::fs::copy(path, &task.save_path).await?;
+                on_bytes(size);
+                size
+            }
+        };
+
+        if let Some(expected) = task.sha256.as_ref() {
+            let actual = sha256_file(task.save_path.clone()).await?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                // Corrupted, do not resume from it
+                tokio::fs::remove_file(&task.save_path).await?;
+                return Err(DownloadError::ChecksumMismatch { expected: expected.clone(), actual });
+            }
+        }
+        Ok(size)
+    }
+
+    /// Continues from the file left by the previous attempt if the server accepts ranges,
+    /// `validator` of the first response is sent as `If-Range` so a file changed meanwhile is downloaded anew
+    async fn fetch_url(
+        &self,
+        client: &Client,
+        url: &str,
+        task: &DownloadTask,
+        validator: &mut Option<HeaderValue>,
+        on_bytes: &(dyn Fn(u64) + Send + Sync)
+    ) -> Result<u64, DownloadError> {
+        let semaphore = self.host_semaphore(url);
+        // The semaphore is never closed
+        let _permit = semaphore.acquire().await.expect("host semaphore closed");
+
+        let mut existing = match tokio::fs::metadata(&task.save_path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0
+        };
+        let request = |existing: u64| {
+            let request = client.get(url).headers(task.headers.clone());
+            match (existing, validator.as_ref()) {
+                (0, _) => request,
+                (_, Some(validator)) => request.header(RANGE, format!("bytes={existing}-")).header(IF_RANGE, validator.clone()),
+                (_, None) => request.header(RANGE, format!("bytes={existing}-")),
+            }
+        };
+        let mut resp = request(existing).send().await?;
+
+        // The partial file is stale or already complete, start over from zero
+        if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE && existing > 0 {
+            log::debug!(target: "download", "Range of {} not satisfiable, starting over", task.label());
+            tokio::fs::remove_file(&task.save_path).await?;
+            existing = 0;
+            resp = request(existing).send().await?;
+        }
+
+        let status = resp.status();
+        let (mut file, mut size) = match status {
+            StatusCode::PARTIAL_CONTENT if existing > 0 => {
+                let file = tokio::fs::OpenOptions::new().append(true).open(&task.save_path).await?;
+                (file, existing)
+            }
+            // Ranges are not supported or the file changed, start over
+            status if status.is_success() => {
+                if existing > 0 {
+                    log::debug!(target: "download", "Server sent the whole file of {}, starting over", task.label());
+                }
+                *validator = resume_validator(resp.headers());
+                (tokio::fs::File::create(&task.save_path).await?, 0)
+            }
+            status => return Err(DownloadError::Unsuccess(status)),
+        };
 
-    if status.is_success() {
         while let Some(chunk) = resp.chunk().await? {
-            file.write_all(&chunk).await?
+            file.write_all(&chunk).await?;
+            size += chunk.len() as u64;
+            on_bytes(chunk.len() as u64);
         }
-        Ok(())
-    } else {
-        Err(DownloadError::Unsuccess(status).into())
+        file.flush().await?;
+        Ok(size)
+    }
+
+    /// Download the files with at most `workers` at the same time, stops early if cancelled.
+    /// `on_progress` is called whenever bytes are written or a file is done
+    pub async fn download_all(
+        &self,
+        client: &Client,
+        tasks: &[DownloadTask],
+        workers: usize,
+        cancel: &CancellationToken,
+        on_progress: &(dyn Fn(DownloadProgress) + Send + Sync),
+    ) -> DownloadReport {
+        let completed_items = &AtomicUsize::new(0);
+        let failed_items = &AtomicUsize::new(0);
+        let downloaded_bytes = &AtomicU64::new(0);
+        let report_progress = &move || on_progress(DownloadProgress {
+            total_items: tasks.len(),
+            completed_items: completed_items.load(Ordering::Relaxed),
+            failed_items: failed_items.load(Ordering::Relaxed),
+            downloaded_bytes: downloaded_bytes.load(Ordering::Relaxed),
+        });
+
+        let results: Vec<(usize, Option<Result<u64, DownloadError>>)> = futures::stream::iter(0..tasks.len())
+            .map(|index| {
+                let task = &tasks[index];
+                let on_bytes = move |bytes: u64| {
+                    downloaded_bytes.fetch_add(bytes, Ordering::Relaxed);
+                    report_progress();
+                };
+                async move {
+                    if cancel.is_cancelled() {
+                        return (index, None);
+                    }
+                    let result = tokio::select! {
+                        result = self.download_with_progress(client, task, &on_bytes) => result,
+                        _ = cancel.cancelled() => return (index, None),
+                    };
+                    match result {
+                        Ok(_) => completed_items.fetch_add(1, Ordering::Relaxed),
+                        Err(_) => failed_items.fetch_add(1, Ordering::Relaxed),
+                    };
+                    report_progress();
+                    (index, Some(result))
+                }
+            })
+            .buffer_unordered(workers.max(1))
+            .collect()
+            .await;
+
+        let mut report = DownloadReport::default();
+        for (index, result) in results {
+            match result {
+                Some(Ok(size)) => report.completed.push(CompletedDownload {
+                    index, save_path: tasks[index].save_path.clone(), size
+                }),
+                Some(Err(error)) => report.failed.push(FailedDownload {
+                    index, label: tasks[index].label(), error
+                }),
+                None => report.cancelled = true,
+            }
+        }
+        report.completed.sort_by_key(|download| download.index);
+        report.failed.sort_by_key(|download| download.index);
+        report
+    }
+
+    /// Sum of the Content-Length of the files by HEAD requests, files without the length are counted as 0.
+    /// Local files are counted by their metadata
+    pub async fn total_size(&self, client: &Client, tasks: &[DownloadTask], workers: usize, cancel: &CancellationToken) -> u64 {
+        futures::stream::iter(0..tasks.len())
+            .map(|index| async move {
+                let task = &tasks[index];
+                if cancel.is_cancelled() {
+                    return 0;
+                }
+                let url = match &task.source {
+                    DownloadSource::Url(url) => url,
+                    DownloadSource::Local(path) => {
+                        return tokio::fs::metadata(path).await.map(|metadata| metadata.len()).unwrap_or(0);
+                    }
+                };
+                let semaphore = self.host_semaphore(url);
+                let _permit = semaphore.acquire().await.expect("host semaphore closed");
+                let request = client.head(url).headers(task.headers.clone()).send();
+                let resp = tokio::select! {
+                    resp = request => resp,
+                    _ = cancel.cancelled() => return 0,
+                };
+                match resp {
+                    Ok(resp) => resp.headers().get("content-length")
+                        .and_then(|val| val.to_str().ok())
+                        .and_then(|val| val.parse::<u64>().ok())
+                        .unwrap_or(0),
+                    Err(e) => {
+                        log::warn!(target: "download", "Failed to check header of {} : {}", task.label(), e.without_url());
+                        0
+                    }
+                }
+            })
+            .buffer_unordered(workers.max(1))
+            .fold(0, |total, size| async move { total + size })
+            .await
     }
 }
 
-pub async fn download_url_to_path<P: AsRef<Path>>(
-    client: Option<Client>,
-    url: &str,
-    save_path: P
-) -> anyhow::Result<tokio::fs::File> {
-    let mut save_file = tokio::fs::File::create(save_path).await?;
-    download_url_to_file(client, url, &mut save_file).await?;
-    Ok(save_file)
+/// Hex SHA-256 digest of the file, hashed in a blocking thread
+async fn sha256_file(path: PathBuf) -> Result<String, DownloadError> {
+    let digest = tokio::task::spawn_blocking(move || -> std::io::Result<String> {
+        let mut file = std::fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect())
+    }).await.map_err(std::io::Error::other)??;
+    Ok(digest)
 }
 
 fn get_telegram_file_link(api_url: &str, token: &str, file_path: &str,) -> String {
@@ -158,22 +517,158 @@ pub async fn download_telegram_to_memory(
     ctx: &Context,
     file_path: &str,
 ) -> anyhow::Result<Vec<u8>> {
-    Ok(download_url_to_memory(None, &get_telegram_file_link_by_context(ctx, file_path)).await?)
+    if ctx.config.telegram.local_mode {
+        return Ok(tokio::fs::read(file_path).await?);
+    }
+    Ok(download_url_to_memory(Some(ctx.http.telegram_files.clone()), &get_telegram_file_link_by_context(ctx, file_path)).await?)
 }
 
-#[allow(unused)]
-pub async fn download_telegram_file_to_file(
-    ctx: &Context,
-    file_path: &str,
-    save_file: &mut tokio::fs::File
-) -> anyhow::Result<()> {
-    Ok(download_url_to_file(None, &get_telegram_file_link_by_context(ctx, file_path), save_file).await?)
+
+/// A task downloading the file from the Bot API server, `file_path` is the one returned by `get_file`.
+/// A local Bot API server returns the absolute path of the file, which is copied directly
+pub fn telegram_file_task(ctx: &Context, file_path: &str, save_path: impl Into<PathBuf>) -> DownloadTask {
+    if ctx.config.telegram.local_mode {
+        return DownloadTask::local(file_path, save_path);
+    }
+    DownloadTask::new(get_telegram_file_link_by_context(ctx, file_path), save_path)
 }
 
 pub async fn download_telegram_file_to_path<P: AsRef<Path>>(
     ctx: &Context,
     file_path: &str,
     save_path: P
-) -> anyhow::Result<tokio::fs::File> {
-    Ok(download_url_to_path(None, &get_telegram_file_link_by_context(ctx, file_path), save_path).await?)
-}
\ No newline at end of file
+) -> anyhow::Result<()> {
+    ctx.downloader.download(&ctx.http.telegram_files, &telegram_file_task(ctx, file_path, save_path.as_ref())).await?;
+    Ok(())
+}
+
+/// Strong ETag, or Last-Modified if there is none, weak ETags can not be used in `If-Range`
+fn resume_validator(headers: &HeaderMap) -> Option<HeaderValue> {
+    headers.get(ETAG)
+        .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+        .or_else(|| headers.get(LAST_MODIFIED))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use axum::Router;
+    use axum::body::{Body, Bytes};
+    use axum::extract::State;
+    use axum::http::{HeaderMap as AxumHeaderMap, StatusCode as AxumStatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+
+    use super::*;
+
+    const BODY: &[u8] = b"0123456789";
+    const CHANGED_BODY: &[u8] = b"abcdefghij";
+
+    /// Serves `BODY` with the ETag `"v1"`, dropping the connection halfway through the first response.
+    /// If `changes` is set the file is `CHANGED_BODY` with the ETag `"v2"` after the first request
+    #[derive(Clone, Default)]
+    struct ResumeServer {
+        changes: bool,
+        /// `If-Range` of each request
+        if_ranges: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    async fn resumable_file(State(server): State<ResumeServer>, headers: AxumHeaderMap) -> Response {
+        let if_range = headers.get("if-range").and_then(|value| value.to_str().ok()).map(str::to_string);
+        let first = {
+            let mut if_ranges = server.if_ranges.lock().unwrap();
+            if_ranges.push(if_range.clone());
+            if_ranges.len() == 1
+        };
+        let (etag, body) = match server.changes && !first {
+            true => ("\"v2\"", CHANGED_BODY),
+            false => ("\"v1\"", BODY),
+        };
+        if first {
+            // The first chunk reaches the client before the connection drops
+            let chunks = futures::stream::once(async move { Ok(Bytes::from_static(&body[..4])) })
+                .chain(futures::stream::once(async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Err(std::io::Error::other("connection dropped"))
+                }));
+            return ([("etag", etag)], Body::from_stream(chunks)).into_response();
+        }
+        let range = headers.get("range").and_then(|value| value.to_str().ok())
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+        match range {
+            Some(start) if if_range.as_deref() == Some(etag) => {
+                (AxumStatusCode::PARTIAL_CONTENT, [("etag", etag)], body[start..].to_vec()).into_response()
+            }
+            _ => (AxumStatusCode::OK, [("etag", etag)], body.to_vec()).into_response(),
+        }
+    }
+
+    async fn serve_resumable(server: ResumeServer) -> String {
+        let app = Router::new().route("/file", get(resumable_file)).with_state(server);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    fn retrying_engine() -> DownloadEngine {
+        DownloadEngine::new(DownloadConfig { max_retries: 1, max_backoff: 0, ..DownloadConfig::default() })
+    }
+
+    #[tokio::test]
+    async fn resumes_with_if_range() {
+        let server = ResumeServer::default();
+        let url = serve_resumable(server.clone()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+
+        let size = retrying_engine().download(&Client::new(), &DownloadTask::new(url, &path)).await.unwrap();
+        assert_eq!(size, BODY.len() as u64);
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+        assert_eq!(*server.if_ranges.lock().unwrap(), vec![None, Some("\"v1\"".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn starts_over_if_the_file_changed() {
+        let server = ResumeServer { changes: true, ..ResumeServer::default() };
+        let url = serve_resumable(server.clone()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+
+        let size = retrying_engine().download(&Client::new(), &DownloadTask::new(url, &path)).await.unwrap();
+        assert_eq!(size, CHANGED_BODY.len() as u64);
+        assert_eq!(std::fs::read(&path).unwrap(), CHANGED_BODY);
+        assert_eq!(*server.if_ranges.lock().unwrap(), vec![None, Some("\"v1\"".to_string())]);
+    }
+
+    /// Serve `BODY`, answering 416 to every range request like a server whose file changed
+    async fn serve_without_ranges() -> String {
+        let app = Router::new().route("/file", get(|headers: AxumHeaderMap| async move {
+            match headers.contains_key("range") {
+                true => (AxumStatusCode::RANGE_NOT_SATISFIABLE, Vec::new()),
+                false => (AxumStatusCode::OK, BODY.to_vec()),
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn restarts_stale_partial_file() {
+        let url = serve_without_ranges().await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        // Left by an earlier attempt, longer than the file now on the server
+        std::fs::write(&path, b"stale partial content").unwrap();
+
+        let engine = DownloadEngine::new(DownloadConfig { max_retries: 0, ..DownloadConfig::default() });
+        let size = engine.download(&Client::new(), &DownloadTask::new(url, &path)).await.unwrap();
+        assert_eq!(size, BODY.len() as u64);
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+    }
+}
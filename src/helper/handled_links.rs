@@ -0,0 +1,61 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+
+/// Links remembered, the oldest ones are forgotten first
+const HANDLED_LINKS_CAPACITY: usize = 10_000;
+
+type LinkKey = (i64, i32, String);
+
+/// Links detected in messages, keyed by chat, message and link, e.g. `pixiv:<id>`.
+/// Edits of a message only react to the links the message did not have before
+#[derive(Debug, Default)]
+pub struct HandledLinks {
+    state: Mutex<HandledLinksState>,
+}
+
+#[derive(Debug, Default)]
+struct HandledLinksState {
+    set: HashSet<LinkKey>,
+    order: VecDeque<LinkKey>,
+}
+
+impl HandledLinks {
+    /// Remember the link of the message, returns false if it was handled before
+    pub fn insert(&self, chat_id: i64, message_id: i32, link: impl Into<String>) -> bool {
+        let key = (chat_id, message_id, link.into());
+        let mut state = self.state.lock().unwrap();
+        if !state.set.insert(key.clone()) {
+            return false;
+        }
+        state.order.push_back(key);
+        if state.order.len() > HANDLED_LINKS_CAPACITY && let Some(oldest) = state.order.pop_front() {
+            state.set.remove(&oldest);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_new_links_of_a_message_are_handled() {
+        let links = HandledLinks::default();
+        assert!(links.insert(1, 10, "pixiv:123"));
+        assert!(!links.insert(1, 10, "pixiv:123"));
+        assert!(links.insert(1, 10, "pixiv:456"));
+        assert!(links.insert(1, 11, "pixiv:123"));
+        assert!(links.insert(2, 10, "pixiv:123"));
+    }
+
+    #[test]
+    fn oldest_links_are_forgotten() {
+        let links = HandledLinks::default();
+        for message_id in 0..=HANDLED_LINKS_CAPACITY as i32 {
+            assert!(links.insert(1, message_id, "pixiv:123"));
+        }
+        assert!(links.insert(1, 0, "pixiv:123"));
+        assert!(!links.insert(1, HANDLED_LINKS_CAPACITY as i32, "pixiv:123"));
+    }
+}
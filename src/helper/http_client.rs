@@ -0,0 +1,282 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::Client;
+use reqwest::cookie::Jar;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::config::{BotConfig, HttpProfile, TlsVersion};
+
+/// Modules choosing a profile in `http.modules`
+pub const HTTP_MODULES: &[&str] = &["pixiv", "kemono", "fanbox", "telegraph", "telegram_files"];
+
+/// Profile of modules not listed in `http.modules`, if defined
+const DEFAULT_PROFILE: &str = "default";
+
+/// pixiv and fanbox downloads may be large, so they limit the connection and the gaps between reads rather than the whole request
+const PIXIV_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const PIXIV_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum HttpClientError {
+    UnknownModule(String),
+    UnknownProfile { module: String, profile: String },
+    InvalidHeader { profile: String, name: String },
+    InvalidCookieUrl { profile: String, url: String },
+    /// Failed to read a root certificate
+    IoError(std::io::Error),
+    ReqwestError(reqwest::Error),
+}
+
+impl From<std::io::Error> for HttpClientError {
+    fn from(value: std::io::Error) -> Self {
+        HttpClientError::IoError(value)
+    }
+}
+
+impl From<reqwest::Error> for HttpClientError {
+    fn from(value: reqwest::Error) -> Self {
+        HttpClientError::ReqwestError(value)
+    }
+}
+
+impl Display for HttpClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpClientError::UnknownModule(module) => write!(f, "Unknown module {module} in http.modules"),
+            HttpClientError::UnknownProfile { module, profile } => {
+                write!(f, "Module {module} uses undefined HTTP profile {profile}")
+            }
+            HttpClientError::InvalidHeader { profile, name } => write!(f, "Invalid header {name} in HTTP profile {profile}"),
+            HttpClientError::InvalidCookieUrl { profile, url } => write!(f, "Invalid cookie URL {url} in HTTP profile {profile}"),
+            HttpClientError::IoError(e) => write!(f, "IO Error: {e}"),
+            HttpClientError::ReqwestError(e) => write!(f, "Reqwest Error: {e}"),
+        }
+    }
+}
+
+impl Error for HttpClientError {}
+
+/// HTTP clients of the modules besides pixiv, which keeps its client in `PixivContext`
+#[derive(Debug)]
+pub struct HttpClients {
+    pub kemono: Client,
+    /// Fanbox API, used to find the creator of fanbox links
+    pub fanbox: Client,
+    pub telegraph: Client,
+    /// Files downloaded from the Bot API server
+    pub telegram_files: Client,
+}
+
+impl HttpClients {
+    pub fn from_config(config: &BotConfig) -> Result<HttpClients, HttpClientError> {
+        if let Some(module) = config.http.modules.keys().find(|module| !HTTP_MODULES.contains(&module.as_str())) {
+            return Err(HttpClientError::UnknownModule(module.clone()));
+        }
+        Ok(HttpClients {
+            kemono: module_client(config, "kemono")?,
+            fanbox: module_client(config, "fanbox")?,
+            telegraph: module_client(config, "telegraph")?,
+            telegram_files: module_client(config, "telegram_files")?,
+        })
+    }
+}
+
+/// Build the client of the module from its profile, over the defaults of the module
+pub fn module_client(config: &BotConfig, module: &str) -> Result<Client, HttpClientError> {
+    let mut builder = Client::builder();
+    let default_user_agent = match module {
+        "pixiv" | "fanbox" => {
+            builder = builder.connect_timeout(PIXIV_CONNECT_TIMEOUT).read_timeout(PIXIV_READ_TIMEOUT);
+            config.pixiv.client_user_agent.as_ref()
+        }
+        "kemono" => {
+            builder = builder.timeout(Duration::from_mins(10));
+            config.kemono.client_user_agent.as_ref()
+        }
+        _ => None,
+    };
+
+    let profile = match config.http.modules.get(module) {
+        Some(name) => match config.http.profiles.get(name) {
+            Some(profile) => Some((name.as_str(), profile)),
+            None => return Err(HttpClientError::UnknownProfile { module: module.to_string(), profile: name.clone() }),
+        },
+        None => config.http.profiles.get_key_value(DEFAULT_PROFILE).map(|(name, profile)| (name.as_str(), profile)),
+    };
+
+    if let Some(user_agent) = default_user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+    if let Some((name, profile)) = profile {
+        builder = apply_profile(builder, name, profile)?;
+        log::info!(target: "http_client", "Module {module} uses HTTP profile {name}");
+    }
+    Ok(builder.build()?)
+}
+
+fn apply_profile(
+    mut builder: reqwest::ClientBuilder,
+    name: &str,
+    profile: &HttpProfile
+) -> Result<reqwest::ClientBuilder, HttpClientError> {
+    if let Some(proxy) = profile.proxy.as_ref() {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    if let Some(user_agent) = profile.user_agent.as_ref() {
+        builder = builder.user_agent(user_agent);
+    }
+    if let Some(timeout) = profile.timeout {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+    if let Some(connect_timeout) = profile.connect_timeout {
+        builder = builder.connect_timeout(Duration::from_secs(connect_timeout));
+    }
+    if let Some(read_timeout) = profile.read_timeout {
+        builder = builder.read_timeout(Duration::from_secs(read_timeout));
+    }
+
+    let mut headers = HeaderMap::new();
+    for (key, value) in profile.headers.iter() {
+        let invalid = || HttpClientError::InvalidHeader { profile: name.to_string(), name: key.clone() };
+        let key = HeaderName::from_bytes(key.as_bytes()).map_err(|_| invalid())?;
+        let value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+        headers.insert(key, value);
+    }
+    builder = builder.default_headers(headers);
+
+    if !profile.cookies.is_empty() {
+        let jar = Jar::default();
+        for (url, cookies) in profile.cookies.iter() {
+            let parsed = url.parse::<reqwest::Url>().map_err(|_| HttpClientError::InvalidCookieUrl {
+                profile: name.to_string(), url: url.clone()
+            })?;
+            for cookie in cookies.split(';').map(str::trim).filter(|cookie| !cookie.is_empty()) {
+                jar.add_cookie_str(cookie, &parsed);
+            }
+        }
+        builder = builder.cookie_provider(Arc::new(jar));
+    }
+
+    for path in profile.root_certificates.iter() {
+        let pem = std::fs::read(path)?;
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+    }
+    if profile.accept_invalid_certs {
+        log::warn!(target: "http_client", "HTTP profile {name} accepts invalid certificates");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    if let Some(version) = profile.min_tls_version {
+        builder = builder.min_tls_version(match version {
+            TlsVersion::Tls12 => reqwest::tls::Version::TLS_1_2,
+            TlsVersion::Tls13 => reqwest::tls::Version::TLS_1_3,
+        });
+    }
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+    use axum::http::HeaderMap as AxumHeaderMap;
+    use axum::routing::get;
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn config(http: Value) -> BotConfig {
+        serde_json::from_value(json!({
+            "telegram": { "token": "0:test", "bot_api_server": "http://127.0.0.1:9" },
+            "telegraph": { "access_token": "" },
+            "sticker": {},
+            "pixiv": {},
+            "kemono": {},
+            "http": http,
+        })).unwrap()
+    }
+
+    /// Serve the request headers back on an ephemeral port
+    async fn serve_headers() -> String {
+        let app = Router::new().route("/", get(|headers: AxumHeaderMap| async move {
+            ["user-agent", "x-test", "cookie"].iter()
+                .map(|name| headers.get(*name).and_then(|value| value.to_str().ok()).unwrap_or("-").to_string())
+                .collect::<Vec<_>>()
+                .join("|")
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn profiles_apply_to_their_modules() {
+        let url = serve_headers().await;
+        let config = config(json!({
+            "profiles": {
+                "default": { "user_agent": "default-agent" },
+                "custom": {
+                    "user_agent": "custom-agent",
+                    "timeout": 30,
+                    "connect_timeout": 5,
+                    "read_timeout": 10,
+                    "headers": { "X-Test": "1" },
+                    "cookies": { url.clone(): "a=1; b=2" },
+                    "min_tls_version": "1.2",
+                },
+            },
+            "modules": { "kemono": "custom" },
+        }));
+        let clients = HttpClients::from_config(&config).unwrap();
+
+        let kemono = clients.kemono.get(&url).send().await.unwrap().text().await.unwrap();
+        let (headers, cookies) = kemono.rsplit_once('|').unwrap();
+        assert_eq!(headers, "custom-agent|1");
+        let mut cookies: Vec<&str> = cookies.split("; ").collect();
+        cookies.sort();
+        assert_eq!(cookies, vec!["a=1", "b=2"]);
+        let telegraph = clients.telegraph.get(&url).send().await.unwrap().text().await.unwrap();
+        assert_eq!(telegraph, "default-agent|-|-");
+    }
+
+    #[test]
+    fn proxy_urls_are_checked() {
+        let config = config(json!({
+            "profiles": {
+                "socks": { "proxy": "socks5://127.0.0.1:1080" },
+                "broken": { "proxy": "http://[::1" },
+            },
+            "modules": { "pixiv": "socks", "kemono": "broken" },
+        }));
+        assert!(module_client(&config, "pixiv").is_ok());
+        assert!(matches!(module_client(&config, "kemono"), Err(HttpClientError::ReqwestError(_))));
+    }
+
+    #[test]
+    fn profile_errors_are_reported() {
+        let config = config(json!({
+            "profiles": {
+                "bad_header": { "headers": { "Bad Header": "1" } },
+                "bad_cookie": { "cookies": { "not a url": "a=1" } },
+            },
+            "modules": { "pixiv": "missing", "kemono": "bad_header", "fanbox": "bad_cookie" },
+        }));
+        assert!(matches!(
+            module_client(&config, "pixiv"),
+            Err(HttpClientError::UnknownProfile { module, profile }) if module == "pixiv" && profile == "missing"
+        ));
+        assert!(matches!(
+            module_client(&config, "kemono"),
+            Err(HttpClientError::InvalidHeader { profile, name }) if profile == "bad_header" && name == "Bad Header"
+        ));
+        assert!(matches!(
+            module_client(&config, "fanbox"),
+            Err(HttpClientError::InvalidCookieUrl { profile, url }) if profile == "bad_cookie" && url == "not a url"
+        ));
+
+        let config = self::config(json!({ "modules": { "pixi": "default" } }));
+        assert!(matches!(HttpClients::from_config(&config), Err(HttpClientError::UnknownModule(module)) if module == "pixi"));
+    }
+}
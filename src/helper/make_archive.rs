@@ -0,0 +1,273 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use frankenstein::AsyncTelegramApi;
+use frankenstein::input_file::FileUpload;
+use frankenstein::methods::SendDocumentParams;
+use frankenstein::types::Message;
+use zip::CompressionMethod;
+use zip::write::SimpleFileOptions;
+
+use crate::context::Context;
+use crate::helper::{bot_actions, param_builders};
+use crate::jobs::scheduler::JobGuard;
+
+/// Room for the zip headers, the files are stored without compression so the rest is the file content
+const ARCHIVE_OVERHEAD: u64 = 1024;
+const ENTRY_OVERHEAD: u64 = 256;
+
+/// A file to be put in the archive
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    /// The name in the archive
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl ArchiveEntry {
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> ArchiveEntry {
+        ArchiveEntry { name: name.into(), path: path.into() }
+    }
+
+    fn packed_size(&self, size: u64) -> u64 {
+        size + ENTRY_OVERHEAD + 2 * self.name.len() as u64
+    }
+}
+
+/// Bytes of a file put in one part, files larger than a part are put in pieces `<name>.001`, `<name>.002`...
+#[derive(Debug)]
+struct ArchivePiece {
+    name: String,
+    path: PathBuf,
+    /// Offset and length in the file, None for the whole file
+    range: Option<(u64, u64)>,
+}
+
+#[derive(Debug, Default)]
+pub struct ArchiveParts {
+    /// The zips in order, each one is a complete archive
+    pub paths: Vec<PathBuf>,
+    /// Names of the files larger than a part, which are split into pieces across the parts
+    pub split: Vec<String>,
+}
+
+/// Pack the files in order into `<stem>.zip`, or `<stem>.part1.zip`, `<stem>.part2.zip`... if they do not fit in one zip of `max_size` bytes.
+/// Files larger than a part are split into pieces filling the parts, files failed to open are skipped
+pub async fn make_archives(entries: Vec<ArchiveEntry>, dir: &Path, stem: &str, max_size: u64) -> anyhow::Result<ArchiveParts> {
+    let dir = dir.to_path_buf();
+    let stem = stem.to_string();
+    tokio::task::spawn_blocking(move || make_archives_blocking(entries, &dir, &stem, max_size)).await?
+}
+
+fn make_archives_blocking(entries: Vec<ArchiveEntry>, dir: &Path, stem: &str, max_size: u64) -> anyhow::Result<ArchiveParts> {
+    let mut parts = ArchiveParts::default();
+    let mut groups: Vec<Vec<ArchivePiece>> = vec![];
+    let mut group_size = ARCHIVE_OVERHEAD;
+    for entry in entries {
+        let file_size = match std::fs::metadata(&entry.path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                log::warn!(
+                    target: "make_archive",
+                    "Failed to open downloaded file {} for archiving: {}",
+                    entry.path.to_string_lossy(), e
+                );
+                continue;
+            }
+        };
+        let size = entry.packed_size(file_size);
+        if ARCHIVE_OVERHEAD + size <= max_size {
+            let piece = ArchivePiece { name: entry.name, path: entry.path, range: None };
+            match groups.last_mut() {
+                Some(group) if group_size + size <= max_size => {
+                    group.push(piece);
+                    group_size += size;
+                }
+                _ => {
+                    groups.push(vec![piece]);
+                    group_size = ARCHIVE_OVERHEAD + size;
+                }
+            }
+            continue;
+        }
+
+        // Each piece starts a part of its own, the last one leaves room for the next files
+        let piece_overhead = ARCHIVE_OVERHEAD + ENTRY_OVERHEAD + 2 * (entry.name.len() as u64 + 4);
+        let piece_size = max_size.saturating_sub(piece_overhead).max(1);
+        log::info!(
+            target: "make_archive",
+            "File {} ({file_size} bytes) is larger than an archive part, splitting into {} pieces",
+            entry.name, file_size.div_ceil(piece_size)
+        );
+        let mut offset = 0;
+        while offset < file_size {
+            let length = u64::min(piece_size, file_size - offset);
+            let name = format!("{}.{:03}", entry.name, offset / piece_size + 1);
+            groups.push(vec![ArchivePiece { name, path: entry.path.clone(), range: Some((offset, length)) }]);
+            group_size = piece_overhead + length;
+            offset += length;
+        }
+        parts.split.push(entry.name);
+    }
+
+    let part_count = groups.len();
+    for (index, group) in groups.into_iter().enumerate() {
+        let file_name = match part_count {
+            1 => format!("{stem}.zip"),
+            _ => format!("{stem}.part{}.zip", index + 1),
+        };
+        let path = dir.join(file_name);
+        write_archive(&path, group)?;
+        parts.paths.push(path);
+    }
+    Ok(parts)
+}
+
+fn write_archive(path: &Path, entries: Vec<ArchivePiece>) -> anyhow::Result<()> {
+    let archive_file = std::fs::File::create(path)?;
+    let mut archive = zip::ZipWriter::new(archive_file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .unix_permissions(0o755);
+    for entry in entries {
+        let mut file = match std::fs::File::open(&entry.path) {
+            Ok(f) => f,
+            Err(e) => {
+                log::warn!(
+                    target: "make_archive",
+                    "Failed to open downloaded file {} for archiving: {}",
+                    entry.path.to_string_lossy(), e
+                );
+                continue;
+            }
+        };
+        archive.start_file(entry.name, options)?;
+        match entry.range {
+            Some((offset, length)) => {
+                file.seek(SeekFrom::Start(offset))?;
+                std::io::copy(&mut file.take(length), &mut archive)?;
+            }
+            None => {
+                std::io::copy(&mut file, &mut archive)?;
+            }
+        }
+    }
+    archive.finish()?;
+    Ok(())
+}
+
+/// Upload the parts in order as replies, the caption (HTML) of each part ends with `(1/3)` if there are several parts.
+/// The parts are paths, or file IDs of parts sent before. Returns the sent messages
+pub async fn send_archives<T>(
+    ctx: &Context,
+    msg: &Message,
+    parts: &[T],
+    caption: Option<&str>,
+    job: Option<&JobGuard>
+) -> anyhow::Result<Vec<Message>>
+where
+    T: Clone + Into<FileUpload>
+{
+    let part_count = parts.len();
+    let mut messages = Vec::with_capacity(part_count);
+    for (index, part) in parts.iter().enumerate() {
+        if let Some(job) = job {
+            job.check()?;
+        }
+        let caption = match (caption, part_count) {
+            (caption, 1) => caption.map(|caption| caption.to_string()),
+            (Some(caption), _) => Some(format!("{caption} ({}/{part_count})", index + 1)),
+            (None, _) => Some(format!("({}/{part_count})", index + 1)),
+        };
+
+        bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::UploadDocument).await?;
+
+        let send_document_param = SendDocumentParams::builder()
+            .chat_id(msg.chat.id)
+            .document(part.clone())
+            .parse_mode(frankenstein::ParseMode::Html)
+            .maybe_caption(caption)
+            .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
+            .build();
+        messages.push(ctx.bot.send_document(&send_document_param).await?.result);
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_files(dir: &Path, sizes: &[usize]) -> Vec<ArchiveEntry> {
+        sizes.iter().enumerate().map(|(index, size)| {
+            let path = dir.join(format!("{index}.bin"));
+            std::fs::write(&path, vec![0u8; *size]).unwrap();
+            ArchiveEntry::new(format!("{index}.bin"), path)
+        }).collect()
+    }
+
+    fn archive_names(path: &Path) -> Vec<String> {
+        let archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        archive.file_names().map(|name| name.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn single_archive_when_it_fits() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = write_files(dir.path(), &[1000, 2000]);
+        let parts = make_archives(entries, dir.path(), "set", 10_000).await.unwrap();
+        assert_eq!(parts.paths, vec![dir.path().join("set.zip")]);
+        assert!(parts.split.is_empty());
+        assert_eq!(archive_names(&parts.paths[0]).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn splits_in_order_under_max_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = write_files(dir.path(), &[3000, 3000, 3000, 3000, 3000]);
+        let max_size = 8000;
+        let parts = make_archives(entries, dir.path(), "set", max_size).await.unwrap();
+        assert_eq!(parts.paths.len(), 3);
+        assert_eq!(parts.paths[0], dir.path().join("set.part1.zip"));
+
+        let mut names = vec![];
+        for path in parts.paths.iter() {
+            assert!(std::fs::metadata(path).unwrap().len() <= max_size);
+            names.extend(archive_names(path));
+        }
+        names.sort();
+        assert_eq!(names, vec!["0.bin", "1.bin", "2.bin", "3.bin", "4.bin"]);
+        assert_eq!(archive_names(&parts.paths[0]).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn splits_oversized_files_into_pieces() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut entries = write_files(dir.path(), &[500]);
+        let content: Vec<u8> = (0..25_000u32).map(|index| (index % 251) as u8).collect();
+        std::fs::write(dir.path().join("large.bin"), &content).unwrap();
+        entries.push(ArchiveEntry::new("large.bin", dir.path().join("large.bin")));
+        entries.push(ArchiveEntry::new("missing.bin", dir.path().join("missing.bin")));
+
+        let max_size = 10_000;
+        let parts = make_archives(entries, dir.path(), "set", max_size).await.unwrap();
+        assert_eq!(parts.split, vec!["large.bin".to_string()]);
+        assert_eq!(parts.paths.len(), 4);
+
+        let mut joined = vec![];
+        let mut names = vec![];
+        for path in parts.paths.iter() {
+            assert!(std::fs::metadata(path).unwrap().len() <= max_size);
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+            for index in 0..archive.len() {
+                let mut file = archive.by_index(index).unwrap();
+                names.push(file.name().to_string());
+                if file.name().starts_with("large.bin.") {
+                    file.read_to_end(&mut joined).unwrap();
+                }
+            }
+        }
+        assert_eq!(names, vec!["0.bin", "large.bin.001", "large.bin.002", "large.bin.003"]);
+        assert_eq!(joined, content);
+    }
+}
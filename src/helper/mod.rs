@@ -3,4 +3,11 @@ pub mod param_builders;
 pub mod message_utils;
 pub mod name_utils;
 pub mod download;
-pub mod log;
\ No newline at end of file
+pub mod handled_links;
+pub mod http_client;
+pub mod log;
+pub mod make_archive;
+pub mod permission;
+pub mod progress;
+pub mod telegram_client;
+pub mod upload_cache;
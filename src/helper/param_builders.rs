@@ -1,4 +1,4 @@
-use frankenstein::types::{ReplyKeyboardRemove, ReplyMarkup, ReplyParameters};
+use frankenstein::types::{InlineKeyboardButton, InlineKeyboardMarkup, ReplyKeyboardRemove, ReplyMarkup, ReplyParameters};
 
 pub fn reply_parameters(message_id: i32, chat_id: Option<i64>) -> ReplyParameters {
     ReplyParameters::builder()
@@ -9,4 +9,8 @@ pub fn reply_parameters(message_id: i32, chat_id: Option<i64>) -> ReplyParameter
 
 pub fn reply_keyboard_remove() -> ReplyMarkup {
     ReplyMarkup::ReplyKeyboardRemove(ReplyKeyboardRemove::builder().remove_keyboard(true).build())
+}
+
+pub fn inline_keyboard(rows: Vec<Vec<InlineKeyboardButton>>) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::builder().inline_keyboard(rows).build()
 }
\ No newline at end of file
@@ -0,0 +1,43 @@
+use frankenstein::AsyncTelegramApi;
+use frankenstein::methods::GetChatMemberParams;
+use frankenstein::types::{ChatMember, Message};
+
+use crate::context::Context;
+use crate::helper::log::LogOp;
+
+/// Check if the sender of the message is an administrator of the chat
+pub async fn check_admin_right(ctx: &Context, msg: &Message) -> anyhow::Result<bool> {
+    let Some(user_id) = msg.from.as_ref().map(|u| u.id) else {
+        return Ok(false);
+    };
+
+    log::debug!(
+        target: "permission",
+        "{} Checking member admin right ({} at {})",
+        LogOp(msg), user_id, msg.chat.id
+    );
+
+    check_member_admin_right(ctx, msg.chat.id, user_id).await
+}
+
+pub async fn check_member_admin_right(ctx: &Context, chat_id: i64, user_id: u64) -> anyhow::Result<bool> {
+    // Bot admins can manage any chat
+    if ctx.config.access.is_admin(Some(user_id as i64)) {
+        return Ok(true);
+    }
+
+    let get_member = ctx.bot.get_chat_member(&GetChatMemberParams::builder()
+        .chat_id(chat_id)
+        .user_id(user_id)
+        .build()
+    ).await?;
+
+    let result = match get_member.result {
+        ChatMember::Administrator(_) |
+        ChatMember::Creator(_) 
+            => true,
+        _ => false
+    };
+
+    Ok(result)
+}
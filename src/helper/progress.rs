@@ -0,0 +1,431 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use frankenstein::types::{InlineKeyboardMarkup, Message, ReplyMarkup};
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use crate::context::Context;
+use crate::helper::bot_actions;
+use crate::helper::download::DownloadProgress;
+use crate::i18n::{Locale, message_locale, tr_args};
+use crate::jobs::cancel_markup;
+use crate::jobs::scheduler::JobGuard;
+
+const BAR_WIDTH: usize = 10;
+/// Throughput is measured over the last seconds
+const SPEED_WINDOW: Duration = Duration::from_secs(5);
+/// How often the progress of a tracked future is checked
+const TICK: Duration = Duration::from_secs(1);
+
+const REACTION_STARTED: &str = "👀";
+const REACTION_DOWNLOADING: &str = "⚡";
+const REACTION_DONE: &str = "👌";
+
+/// Shows the progress of a job, as a message with the cancel button or as reactions on the request.
+/// Shown only if the job runs longer than `ProgressConfig.delay`, and cleaned up if dropped without `finish`
+pub struct ProgressReporter {
+    ctx: Arc<Context>,
+    chat_id: i64,
+    /// The message requesting the job
+    request_id: i32,
+    locale: Locale,
+    markup: InlineKeyboardMarkup,
+    reactions: bool,
+    title: String,
+    /// Shown instead of the counts, e.g. requesting metadata
+    status: Option<String>,
+    progress: DownloadProgress,
+    total_bytes: Option<u64>,
+    started: Instant,
+    /// Downloaded bytes of the last seconds
+    samples: VecDeque<(Instant, u64)>,
+    shown: bool,
+    message_id: Option<i32>,
+    /// The progress message is deleted or reactions are not allowed, stop reporting
+    gone: bool,
+    finished: bool,
+    last_text: String,
+    last_reaction: Option<&'static str>,
+    last_edit: Instant,
+    interval: Duration,
+}
+
+impl ProgressReporter {
+    pub fn new(ctx: Arc<Context>, msg: &Message, job: &JobGuard, title: impl Into<String>) -> ProgressReporter {
+        let locale = message_locale(&ctx, msg);
+        let reactions = ctx.settings.progress_reactions(msg.chat.id, &ctx.config.progress);
+        let interval = Duration::from_secs(ctx.config.progress.min_interval);
+        ProgressReporter {
+            chat_id: msg.chat.id,
+            request_id: msg.message_id,
+            locale,
+            markup: cancel_markup(job, locale),
+            reactions,
+            title: title.into(),
+            status: None,
+            progress: DownloadProgress::default(),
+            total_bytes: None,
+            started: Instant::now(),
+            samples: VecDeque::new(),
+            shown: false,
+            message_id: None,
+            gone: false,
+            finished: false,
+            last_text: String::new(),
+            last_reaction: None,
+            last_edit: Instant::now(),
+            interval,
+            ctx,
+        }
+    }
+
+    /// Enables the byte based bar and ETA, 0 means unknown
+    pub fn set_total_bytes(&mut self, total_bytes: u64) {
+        self.total_bytes = (total_bytes > 0).then_some(total_bytes);
+    }
+
+    /// Show the status right away, until the next progress update
+    pub async fn status(&mut self, text: impl Into<String>) {
+        self.status = Some(text.into());
+        self.refresh(true).await;
+    }
+
+    pub async fn update(&mut self, progress: DownloadProgress) {
+        let now = Instant::now();
+        self.samples.push_back((now, progress.downloaded_bytes));
+        while self.samples.len() > 2 && self.samples.front().is_some_and(|(at, _)| now - *at > SPEED_WINDOW) {
+            self.samples.pop_front();
+        }
+        self.status = None;
+        self.progress = progress;
+        self.refresh(false).await;
+    }
+
+    /// Wait for the future, updating with the progress sent to the receiver.
+    /// The future is still polled during the edits, which may wait long for flood control
+    pub async fn track<T>(&mut self, future: impl Future<Output = T>, receiver: &mut watch::Receiver<DownloadProgress>) -> T {
+        tokio::pin!(future);
+        loop {
+            tokio::select! {
+                output = &mut future => return output,
+                _ = tokio::time::sleep(TICK) => {}
+            }
+            let progress = *receiver.borrow_and_update();
+
+            // The edit is not dropped halfway if the future finishes, so a sent progress message is not lost
+            let mut output = None;
+            let update = self.update(progress);
+            tokio::pin!(update);
+            loop {
+                tokio::select! {
+                    result = &mut future, if output.is_none() => output = Some(result),
+                    _ = &mut update => break,
+                }
+            }
+            if let Some(output) = output {
+                return output;
+            }
+        }
+    }
+
+    /// Remove the progress message, or mark the request as done in reactions mode
+    pub async fn finish(mut self) {
+        self.finished = true;
+        if !self.shown || self.gone {
+            return;
+        }
+        if self.reactions {
+            self.set_reaction(Some(REACTION_DONE)).await;
+        } else if let Some(message_id) = self.message_id {
+            clear_progress(&self.ctx, self.chat_id, Some(message_id), self.request_id).await;
+        }
+    }
+
+    async fn refresh(&mut self, force: bool) {
+        if self.gone || self.finished {
+            return;
+        }
+        let now = Instant::now();
+        let wait = match self.shown {
+            true => self.interval,
+            false => Duration::from_secs(self.ctx.config.progress.delay),
+        };
+        let since = if self.shown { self.last_edit } else { self.started };
+        if !force && now - since < wait {
+            return;
+        }
+
+        if self.reactions {
+            let downloading = self.progress.downloaded_bytes > 0 || self.progress.completed_items > 0;
+            self.set_reaction(Some(if downloading { REACTION_DOWNLOADING } else { REACTION_STARTED })).await;
+        } else {
+            self.edit_message().await;
+        }
+        self.shown = true;
+        self.last_edit = Instant::now();
+        self.adapt_interval(self.last_edit - now);
+    }
+
+    /// Slow down as the job goes on, and when edits are held back by flood control
+    fn adapt_interval(&mut self, edit_time: Duration) {
+        let config = &self.ctx.config.progress;
+        let min = Duration::from_secs(config.min_interval);
+        let max = Duration::from_secs(config.max_interval).max(min);
+        let interval = (self.started.elapsed() / 20).clamp(min, max);
+        self.interval = interval.max(edit_time * 2).min(max);
+    }
+
+    async fn edit_message(&mut self) {
+        let text = self.render();
+        if text == self.last_text {
+            return;
+        }
+        let result = match self.message_id {
+            Some(message_id) => bot_actions::edit_message_text_with_markup(
+                &self.ctx.bot, self.chat_id, message_id, &text, self.markup.clone()
+            ).await.map(|_| None),
+            None => bot_actions::send_reply_message_with_markup(
+                &self.ctx.bot, self.chat_id, &text, self.request_id,
+                ReplyMarkup::InlineKeyboardMarkup(self.markup.clone())
+            ).await.map(|message| Some(message.message_id)),
+        };
+        match result {
+            Ok(message_id) => {
+                self.message_id = self.message_id.or(message_id);
+                self.last_text = text;
+            }
+            Err(e) if is_not_modified(&e) => self.last_text = text,
+            Err(e) if is_message_gone(&e) => {
+                log::info!(target: "progress", "[Chat: {}] Progress message is gone, stop reporting", self.chat_id);
+                self.gone = true;
+            }
+            Err(e) => log::warn!(target: "progress", "[Chat: {}] Failed to update progress message: {e}", self.chat_id),
+        }
+    }
+
+    async fn set_reaction(&mut self, reaction: Option<&'static str>) {
+        if self.last_reaction == reaction {
+            return;
+        }
+        match bot_actions::set_reaction(&self.ctx.bot, self.chat_id, self.request_id, reaction).await {
+            Ok(_) => self.last_reaction = reaction,
+            Err(e) => {
+                // Reactions are disabled in the chat, or the request is deleted
+                log::info!(target: "progress", "[Chat: {}] Failed to set reaction, stop reporting: {e}", self.chat_id);
+                self.gone = true;
+            }
+        }
+    }
+
+    /// Bytes per second over the last seconds
+    fn speed(&self) -> f64 {
+        let (Some((first_at, first_bytes)), Some((last_at, last_bytes))) = (self.samples.front(), self.samples.back()) else {
+            return 0.0;
+        };
+        let seconds = (*last_at - *first_at).as_secs_f64();
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+        last_bytes.saturating_sub(*first_bytes) as f64 / seconds
+    }
+
+    fn render(&self) -> String {
+        match self.status.as_ref() {
+            Some(status) => format!("{}\n{status}", self.title),
+            None => render_progress(
+                self.locale, &self.title, &self.progress, self.total_bytes, self.speed(), self.started.elapsed()
+            ),
+        }
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        // The job failed or is cancelled
+        if self.finished || !self.shown || self.gone {
+            return;
+        }
+        let ctx = self.ctx.clone();
+        let (chat_id, message_id, request_id) = (self.chat_id, self.message_id, self.request_id);
+        self.ctx.tasks.spawn(async move {
+            clear_progress(&ctx, chat_id, message_id, request_id).await;
+        });
+    }
+}
+
+/// Delete the progress message, or remove the reaction if there is no message
+async fn clear_progress(ctx: &Context, chat_id: i64, message_id: Option<i32>, request_id: i32) {
+    let result = match message_id {
+        Some(message_id) => bot_actions::delete_message(&ctx.bot, chat_id, message_id).await,
+        None => bot_actions::set_reaction(&ctx.bot, chat_id, request_id, None).await,
+    };
+    match result {
+        Err(e) if !is_message_gone(&e) => log::warn!(target: "progress", "[Chat: {chat_id}] Failed to clear progress: {e}"),
+        _ => {}
+    }
+}
+
+fn is_not_modified(error: &frankenstein::Error) -> bool {
+    matches!(error, frankenstein::Error::Api(response) if response.description.contains("message is not modified"))
+}
+
+fn is_message_gone(error: &frankenstein::Error) -> bool {
+    matches!(
+        error,
+        frankenstein::Error::Api(response) if response.description.contains("message to edit not found")
+            || response.description.contains("message to delete not found")
+            || response.description.contains("MESSAGE_ID_INVALID")
+    )
+}
+
+/// Done part of the job, by bytes if the total size is known, otherwise by items
+fn progress_fraction(progress: &DownloadProgress, total_bytes: Option<u64>) -> f64 {
+    let fraction = match total_bytes {
+        Some(total_bytes) => progress.downloaded_bytes as f64 / total_bytes as f64,
+        None if progress.total_items > 0 => (progress.completed_items + progress.failed_items) as f64 / progress.total_items as f64,
+        None => 0.0,
+    };
+    fraction.clamp(0.0, 1.0)
+}
+
+/// Remaining time by the recent speed if the total size is known, otherwise by the time taken so far
+fn estimate_eta(progress: &DownloadProgress, total_bytes: Option<u64>, speed: f64, elapsed: Duration) -> Option<Duration> {
+    if let Some(total_bytes) = total_bytes && speed > 0.0 {
+        let remaining = total_bytes.saturating_sub(progress.downloaded_bytes);
+        return Some(Duration::from_secs_f64(remaining as f64 / speed));
+    }
+    let fraction = progress_fraction(progress, total_bytes);
+    if fraction <= 0.0 || fraction >= 1.0 || elapsed.is_zero() {
+        return None;
+    }
+    Some(elapsed.mul_f64((1.0 - fraction) / fraction))
+}
+
+fn render_progress(
+    locale: Locale,
+    title: &str,
+    progress: &DownloadProgress,
+    total_bytes: Option<u64>,
+    speed: f64,
+    elapsed: Duration
+) -> String {
+    let mut lines = vec![title.to_string()];
+    let fraction = progress_fraction(progress, total_bytes);
+    lines.push(format!("{} {:.0}%", progress_bar(fraction), fraction * 100.0));
+
+    let mut items = tr_args(locale, "progress.items", &[&progress.completed_items, &progress.total_items]);
+    if progress.failed_items > 0 {
+        items.push_str(&tr_args(locale, "progress.failed", &[&progress.failed_items]));
+    }
+    lines.push(items);
+
+    let bytes = match total_bytes {
+        Some(total_bytes) => format!("{} / {}", format_bytes(progress.downloaded_bytes), format_bytes(total_bytes)),
+        None => format_bytes(progress.downloaded_bytes),
+    };
+    lines.push(format!("{bytes} · {}/s", format_bytes(speed as u64)));
+
+    if let Some(eta) = estimate_eta(progress, total_bytes, speed, elapsed) {
+        lines.push(tr_args(locale, "progress.eta", &[&format_duration(eta)]));
+    }
+    lines.join("\n")
+}
+
+fn progress_bar(fraction: f64) -> String {
+    let filled = ((fraction * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled))
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 1;
+    // Decided after rounding, so 1023.97 KiB is shown as 1.0 MiB rather than 1024.0 KiB
+    while unit + 1 < UNITS.len() && (value * 10.0).round() >= 10240.0 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(completed_items: usize, total_items: usize, downloaded_bytes: u64) -> DownloadProgress {
+        DownloadProgress { total_items, completed_items, failed_items: 0, downloaded_bytes }
+    }
+
+    #[test]
+    fn bytes_roll_over_to_larger_units() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(1024 * 1024 - 1), "1.0 MiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3072.0 MiB");
+    }
+
+    #[test]
+    fn durations_roll_over_to_larger_units() {
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        assert_eq!(format_duration(Duration::from_millis(59_900)), "59s");
+        assert_eq!(format_duration(Duration::from_secs(60)), "1m 00s");
+        assert_eq!(format_duration(Duration::from_secs(3599)), "59m 59s");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1h 00m");
+        assert_eq!(format_duration(Duration::from_secs(90_061)), "25h 01m");
+    }
+
+    #[test]
+    fn bar_is_filled_by_fraction() {
+        assert_eq!(progress_bar(0.0), "░░░░░░░░░░");
+        assert_eq!(progress_bar(0.55), "██████░░░░");
+        assert_eq!(progress_bar(1.0), "██████████");
+    }
+
+    #[test]
+    fn fraction_uses_bytes_when_total_size_is_known() {
+        assert_eq!(progress_fraction(&progress(1, 4, 0), None), 0.25);
+        assert_eq!(progress_fraction(&progress(1, 4, 750), Some(1000)), 0.75);
+        assert_eq!(progress_fraction(&progress(0, 0, 0), None), 0.0);
+        // Retried attempts may write more than the total size
+        assert_eq!(progress_fraction(&progress(4, 4, 1500), Some(1000)), 1.0);
+    }
+
+    #[test]
+    fn eta_by_speed_or_elapsed_time() {
+        let elapsed = Duration::from_secs(10);
+        assert_eq!(estimate_eta(&progress(0, 2, 500), Some(1500), 100.0, elapsed), Some(Duration::from_secs(10)));
+        // Unknown total size, the rest of the items take as long as the done ones
+        assert_eq!(estimate_eta(&progress(1, 4, 500), None, 100.0, elapsed), Some(Duration::from_secs(30)));
+        assert_eq!(estimate_eta(&progress(1, 4, 500), None, 0.0, Duration::ZERO), None);
+        assert_eq!(estimate_eta(&progress(0, 4, 0), None, 0.0, elapsed), None);
+        assert_eq!(estimate_eta(&progress(4, 4, 500), None, 0.0, elapsed), None);
+    }
+
+    #[test]
+    fn renders_known_and_unknown_total_size() {
+        let text = render_progress(Locale::En, "Downloading", &progress(1, 2, 512 * 1024), Some(1024 * 1024), 1024.0, Duration::from_secs(3));
+        assert_eq!(text, "Downloading\n█████░░░░░ 50%\n1/2 done\n512.0 KiB / 1.0 MiB · 1.0 KiB/s\nAbout 8m 32s left");
+
+        let mut failed = progress(1, 4, 0);
+        failed.failed_items = 1;
+        let text = render_progress(Locale::En, "Downloading", &failed, None, 0.0, Duration::ZERO);
+        assert_eq!(text, "Downloading\n█████░░░░░ 50%\n1/4 done (1 failed)\n0 B · 0 B/s");
+    }
+}
@@ -0,0 +1,244 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::client_reqwest::Bot;
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::config::ApiCallConfig;
+
+/// Prefixes of the methods sending something to a chat, which are subject to flood control
+const THROTTLED_METHOD_PREFIXES: &[&str] = &["send", "edit", "copy", "forward"];
+
+/// Prefixes of the methods safe to call again after a server error, which may come after the call took effect.
+/// Sending calls are retried only when Telegram surely did not process them, or duplicate messages appear
+const IDEMPOTENT_METHOD_PREFIXES: &[&str] = &["get", "edit", "answer", "delete", "set"];
+
+/// Forget idle chats once there are so many
+const CHAT_SLOT_SWEEP_THRESHOLD: usize = 1024;
+
+/// The Telegram API client used by all modules,
+/// retries on flood control and transient errors, and keeps the sending rate under the limits
+#[derive(Debug)]
+pub struct TelegramClient {
+    bot: Bot,
+    config: ApiCallConfig,
+    /// The earliest time of the next sending call to each chat
+    chat_slots: DashMap<i64, Instant>,
+    /// The earliest time of the next sending call to any chat
+    global_slot: Mutex<Instant>,
+}
+
+impl TelegramClient {
+    pub fn new(bot: Bot, config: ApiCallConfig) -> TelegramClient {
+        TelegramClient {
+            bot,
+            config,
+            chat_slots: DashMap::new(),
+            global_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// The chat the call sends to, None if the call is not throttled
+    fn throttled_chat(method: &str, params: Option<&Value>) -> Option<i64> {
+        if !THROTTLED_METHOD_PREFIXES.iter().any(|prefix| method.starts_with(prefix)) {
+            return None;
+        }
+        params?.get("chat_id")?.as_i64()
+    }
+
+    fn chat_interval(&self, chat_id: i64) -> Duration {
+        // Users have positive IDs, groups and channels have negative IDs
+        if chat_id > 0 {
+            Duration::from_millis(self.config.private_chat_interval)
+        } else {
+            Duration::from_millis(self.config.group_chat_interval)
+        }
+    }
+
+    /// Reserve the next slot of the chat and wait for it
+    async fn throttle(&self, chat_id: i64) {
+        let now = Instant::now();
+        let chat_at = {
+            let mut slot = self.chat_slots.entry(chat_id).or_insert(now);
+            let at = (*slot).max(now);
+            *slot = at + self.chat_interval(chat_id);
+            at
+        };
+        let at = {
+            let mut slot = self.global_slot.lock().unwrap();
+            let at = (*slot).max(chat_at);
+            *slot = at + Duration::from_millis(self.config.global_interval);
+            at
+        };
+        if self.chat_slots.len() > CHAT_SLOT_SWEEP_THRESHOLD {
+            self.chat_slots.retain(|_, slot| *slot > now);
+        }
+        tokio::time::sleep_until(at).await;
+    }
+
+    /// Hold back the following calls to the chat, or all calls if the chat is unknown
+    fn hold_back(&self, chat_id: Option<i64>, delay: Duration) {
+        let until = Instant::now() + delay;
+        match chat_id {
+            Some(chat_id) => {
+                let mut slot = self.chat_slots.entry(chat_id).or_insert(until);
+                *slot = (*slot).max(until);
+            }
+            None => {
+                let mut slot = self.global_slot.lock().unwrap();
+                *slot = (*slot).max(until);
+            }
+        }
+    }
+
+    /// The delay before retrying, None if the error is not worth retrying or the call is not safe to repeat
+    fn retry_delay(&self, method: &str, error: &frankenstein::Error, attempt: u32) -> Option<Duration> {
+        let backoff = Duration::from_secs(2u64.saturating_pow(attempt).min(self.config.max_backoff));
+        match error {
+            frankenstein::Error::Api(response) if response.error_code == 429 => {
+                let retry_after = response.parameters.as_ref().and_then(|parameters| parameters.retry_after);
+                Some(retry_after.map(|seconds| Duration::from_secs(seconds as u64)).unwrap_or(backoff))
+            }
+            frankenstein::Error::Api(response) if response.error_code >= 500 => {
+                let idempotent = IDEMPOTENT_METHOD_PREFIXES.iter().any(|prefix| method.starts_with(prefix));
+                idempotent.then_some(backoff)
+            }
+            // The request never reached Telegram, so it is safe to send again
+            frankenstein::Error::HttpReqwest(error) if error.is_connect() => Some(backoff),
+            _ => None
+        }
+    }
+
+    async fn with_retry<Output, F, Fut>(&self, method: &str, chat_id: Option<i64>, call: F) -> Result<Output, frankenstein::Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<Output, frankenstein::Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            if let Some(chat_id) = chat_id {
+                self.throttle(chat_id).await;
+            }
+            let error = match call().await {
+                Ok(output) => return Ok(output),
+                Err(error) => error,
+            };
+            let delay = match self.retry_delay(method, &error, attempt) {
+                Some(delay) if attempt < self.config.max_retries => delay,
+                _ => return Err(error),
+            };
+            attempt += 1;
+            log::warn!(
+                target: "telegram_api",
+                "{method} failed: {error}, retrying in {}s ({attempt}/{})",
+                delay.as_secs(), self.config.max_retries
+            );
+            self.hold_back(chat_id, delay);
+            if chat_id.is_none() {
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+fn encode_params(params: impl serde::Serialize + std::fmt::Debug) -> Result<Value, frankenstein::Error> {
+    serde_json::to_value(&params).map_err(|source| frankenstein::Error::JsonEncode {
+        source,
+        input: format!("{params:?}"),
+    })
+}
+
+#[async_trait::async_trait]
+impl AsyncTelegramApi for TelegramClient {
+    type Error = frankenstein::Error;
+
+    async fn request<Params, Output>(
+        &self,
+        method: &str,
+        params: Option<Params>,
+    ) -> Result<Output, Self::Error>
+    where
+        Params: serde::ser::Serialize + std::fmt::Debug + std::marker::Send,
+        Output: serde::de::DeserializeOwned,
+    {
+        // Long polling has its own retry loop, and must not wait for sending calls
+        if method == "getUpdates" {
+            return self.bot.request(method, params).await;
+        }
+        let params = params.map(encode_params).transpose()?;
+        let chat_id = TelegramClient::throttled_chat(method, params.as_ref());
+        self.with_retry(method, chat_id, || self.bot.request(method, params.clone())).await
+    }
+
+    async fn request_with_form_data<Params, Output>(
+        &self,
+        method: &str,
+        params: Params,
+        files: Vec<(&str, PathBuf)>,
+    ) -> Result<Output, Self::Error>
+    where
+        Params: serde::ser::Serialize + std::fmt::Debug + std::marker::Send,
+        Output: serde::de::DeserializeOwned,
+    {
+        let params = encode_params(params)?;
+        let chat_id = TelegramClient::throttled_chat(method, Some(&params));
+        self.with_retry(method, chat_id, || {
+            self.bot.request_with_form_data(method, params.clone(), files.clone())
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use frankenstein::response::ErrorResponse;
+    use serde_json::json;
+
+    use super::*;
+
+    fn client() -> TelegramClient {
+        let config = ApiCallConfig { max_backoff: 30, ..ApiCallConfig::default() };
+        TelegramClient::new(Bot::new_url("http://127.0.0.1:9/bot0:test"), config)
+    }
+
+    fn api_error(value: Value) -> frankenstein::Error {
+        frankenstein::Error::Api(serde_json::from_value::<ErrorResponse>(value).unwrap())
+    }
+
+    #[test]
+    fn flood_control_is_retried_after_retry_after() {
+        let error = api_error(json!({
+            "ok": false, "error_code": 429, "description": "Too Many Requests",
+            "parameters": { "retry_after": 7 },
+        }));
+        assert_eq!(client().retry_delay("sendMessage", &error, 0), Some(Duration::from_secs(7)));
+        assert_eq!(client().retry_delay("editMessageText", &error, 0), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn server_errors_are_retried_only_for_idempotent_methods() {
+        let error = api_error(json!({ "ok": false, "error_code": 502, "description": "Bad Gateway" }));
+        for method in ["getFile", "editMessageText", "answerCallbackQuery", "deleteMessage", "setMessageReaction"] {
+            assert_eq!(client().retry_delay(method, &error, 1), Some(Duration::from_secs(2)), "{method}");
+        }
+        // May have been delivered already
+        for method in ["sendMessage", "sendMediaGroup", "sendDocument", "copyMessage", "forwardMessage"] {
+            assert_eq!(client().retry_delay(method, &error, 1), None, "{method}");
+        }
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let error = api_error(json!({ "ok": false, "error_code": 400, "description": "Bad Request" }));
+        assert_eq!(client().retry_delay("getFile", &error, 0), None);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let error = api_error(json!({ "ok": false, "error_code": 500, "description": "Internal Server Error" }));
+        assert_eq!(client().retry_delay("getFile", &error, 10), Some(Duration::from_secs(30)));
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use dashmap::DashMap;
+use frankenstein::types::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::config::UploadCacheConfig;
+use crate::storage::{Document, Migration, Storage, unchanged};
+
+struct UploadCacheDocument;
+
+impl Document for UploadCacheDocument {
+    type Data = HashMap<String, CachedUpload>;
+
+    const NAME: &'static str = "upload_cache.json";
+    const MIGRATIONS: &'static [Migration] = &[unchanged];
+    const LEGACY_FILE: Option<&'static str> = Some("upload_cache.json");
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedUpload {
+    file_ids: Vec<String>,
+    /// Unix timestamp in seconds, the least recently used entries are dropped first
+    used_at: i64,
+}
+
+/// File IDs of media uploaded before, keyed by the source, e.g. `pixiv:<id>:p0:photos`,
+/// so popular works are resent without downloading again. Saved in the store
+#[derive(Debug)]
+pub struct UploadCache {
+    map: DashMap<String, CachedUpload>,
+    max_entries: usize,
+    /// The cache is not used if None
+    storage: Option<Arc<Storage>>,
+    dirty: AtomicBool,
+}
+
+impl UploadCache {
+    pub fn from_config(config: &UploadCacheConfig, storage: Arc<Storage>) -> UploadCache {
+        let mut cache = UploadCache {
+            map: DashMap::new(),
+            max_entries: config.max_entries,
+            storage: config.enabled.then_some(storage),
+            dirty: AtomicBool::new(false),
+        };
+        if let Some(storage) = cache.storage.as_ref() {
+            match storage.load::<UploadCacheDocument>() {
+                Ok(saved) => cache.map.extend(saved.unwrap_or_default()),
+                Err(e) => log::warn!(target: "upload_cache", "Failed to load upload cache: {e}"),
+            }
+        }
+        cache
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// The file IDs of all keys in order, None if any of them is missing
+    pub fn get_all(&self, keys: &[String]) -> Option<Vec<String>> {
+        self.storage.as_ref()?;
+        let now = chrono::Utc::now().timestamp();
+        let mut file_ids = vec![];
+        for key in keys {
+            let mut entry = self.map.get_mut(key)?;
+            entry.used_at = now;
+            file_ids.extend(entry.file_ids.iter().cloned());
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+        Some(file_ids)
+    }
+
+    pub fn insert(&self, key: String, file_ids: Vec<String>) {
+        if self.storage.is_none() || file_ids.is_empty() {
+            return;
+        }
+        let used_at = chrono::Utc::now().timestamp();
+        self.map.insert(key, CachedUpload { file_ids, used_at });
+        if self.map.len() > self.max_entries {
+            self.evict();
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Forget the keys, e.g. Telegram rejects the file IDs
+    pub fn remove(&self, keys: &[String]) {
+        for key in keys {
+            self.map.remove(key);
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Drop the least recently used entries down to 90% of the limit
+    fn evict(&self) {
+        let mut used: Vec<(i64, String)> = self.map.iter()
+            .map(|entry| (entry.used_at, entry.key().clone()))
+            .collect();
+        used.sort_unstable();
+        let keep = self.max_entries * 9 / 10;
+        for (_, key) in used.into_iter().take(self.map.len().saturating_sub(keep)) {
+            self.map.remove(&key);
+        }
+    }
+
+    /// Write the cache to the store if changed
+    pub async fn save(&self) {
+        let Some(storage) = self.storage.as_ref() else {
+            return;
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let snapshot = || self.map.iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        if let Err(e) = storage.save::<UploadCacheDocument>(snapshot).await {
+            log::warn!(target: "upload_cache", "Failed to save upload cache: {e}");
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// File ID of the media in a sent message, the largest size for photos
+pub fn message_file_id(msg: &Message) -> Option<String> {
+    if let Some(photo) = msg.photo.as_ref().and_then(|sizes| sizes.last()) {
+        return Some(photo.file_id.clone());
+    }
+    if let Some(document) = msg.document.as_ref() {
+        return Some(document.file_id.clone());
+    }
+    msg.video.as_ref().map(|video| video.file_id.clone())
+        .or_else(|| msg.animation.as_ref().map(|animation| animation.file_id.clone()))
+}
+
+/// Telegram refused a cached file ID, e.g. the file is gone from the servers
+pub fn is_stale_file_id(error: &anyhow::Error) -> bool {
+    error.chain()
+        .filter_map(|cause| cause.downcast_ref::<frankenstein::Error>())
+        .any(|error| matches!(
+            error,
+            frankenstein::Error::Api(response) if response.error_code == 400 && (
+                response.description.contains("wrong file identifier")
+                    || response.description.contains("wrong remote file identifier")
+                    || response.description.contains("FILE_REFERENCE")
+                    || response.description.contains("MEDIA_EMPTY")
+            )
+        ))
+}
@@ -0,0 +1,174 @@
+//! English
+
+pub const CATALOG: &[(&str, &str)] = &[
+    // Basic commands
+    ("help.header", "This is Mint Tea~ Currently supported features:"),
+    ("cmd.help", "Show help"),
+    ("cmd.exit", "Exit the current feature"),
+    ("help.basic", "Basic commands"),
+    ("modal.expired", "No activity for a while, exited the current feature automatically~"),
+    ("common.admin_only_command", "In groups, this command can only be used by administrators."),
+    ("common.admin_only_action", "In groups, this action can only be performed by administrators."),
+    ("common.message_too_old", "This message is too old, please use the command again~"),
+    ("common.download_failed", "Failed to download the file..."),
+    ("common.transcode_failed", "Failed to transcode the file..."),
+    // Errors
+    ("error.network", "Something went wrong with the network, please try again later...\nIncident ID: {}"),
+    ("error.upstream_not_found", "The source site doesn't seem to have this content...\nIncident ID: {}"),
+    ("error.upstream_blocked", "The source site refused the request, the content may be restricted...\nIncident ID: {}"),
+    ("error.too_large", "The file is too large to be sent...\nIncident ID: {}"),
+    ("error.telegram_rejected", "Telegram refused to send it...\nIncident ID: {}"),
+    ("error.internal", "Something went wrong while handling it...\nIncident ID: {}"),
+    // Settings
+    ("cmd.settings", "Change feature settings of this chat"),
+    ("help.settings", "Chat settings"),
+    ("settings.already_default", "Already using the default settings~"),
+    ("settings.updated", "Settings updated~"),
+    ("settings.on", "On"),
+    ("settings.off", "Off"),
+    ("settings.default_value", "{} (default)"),
+    ("settings.title", "Settings of this chat, tap a button to toggle:"),
+    ("settings.language", "Language"),
+    ("settings.language_auto", "Follow each user"),
+    ("settings.reset", "Reset all to default"),
+    ("settings.pixiv_link", "Detect Pixiv links"),
+    ("settings.spoiler_nsfw", "Spoiler for NSFW illusts"),
+    ("settings.spoiler_r18g", "Spoiler for R-18G illusts"),
+    ("settings.kemono_link", "Detect Kemono links"),
+    ("settings.fanbox_link", "Detect Fanbox links"),
+    ("settings.progress_reactions", "Show progress as reactions"),
+    // Jobs
+    ("cmd.jobs", "List running and queued download jobs (bot admins)"),
+    ("jobs.queued", "Your download is queued at #{}, please wait a moment~"),
+    ("jobs.shutdown", "The bot is shutting down, the queued job is cancelled, please try again later..."),
+    ("jobs.bot_admin_only", "Only bot admins can use this command."),
+    ("jobs.none", "No running or queued jobs~"),
+    ("jobs.running", "Running jobs ({}):"),
+    ("jobs.waiting", "Queued jobs ({}):"),
+    ("help.jobs", "Download jobs"),
+    ("cmd.cancel", "Cancel your download jobs"),
+    ("jobs.cancel_button", "Cancel"),
+    ("jobs.cancelled", "The download job is cancelled, temp files are cleaned up~"),
+    ("jobs.cancelling", "Cancelling..."),
+    ("jobs.cancel_usage", "Usage: /cancel [job ID]"),
+    ("jobs.nothing_to_cancel", "You have no jobs to cancel here~"),
+    ("jobs.already_finished", "This job has already finished~"),
+    ("jobs.not_owner", "Only the user who started the job can cancel it."),
+    ("jobs.joined", "The same request is being processed, the result will be sent here when it is done~"),
+    ("jobs.disk_full", "The temp storage is full, new downloads cannot start right now, please try again later..."),
+    ("jobs.temp_usage", "Temp storage: {} ({} entries)"),
+    // Progress
+    ("progress.items", "{}/{} done"),
+    ("progress.failed", " ({} failed)"),
+    ("progress.eta", "About {} left"),
+    // Archive
+    ("archive.split", "{} files are larger than the upload limit and are split into .001, .002... pieces across the parts, join them in order after extracting~"),
+    // Sticker
+    ("cmd.sticker_convert", "Convert stickers, images and animations"),
+    ("cmd.sticker_set_download", "Download a sticker set"),
+    ("help.sticker", "Sticker conversion and sticker set download"),
+    ("sticker.private_only", "Sticker commands only work in private chats~"),
+    ("sticker.convert_prompt", "Please send the sticker, image or animation to convert~\nTo exit, tap the command -> /exit"),
+    ("sticker.set_download_prompt", "Please send a sticker from the sticker set to download~\nTo exit, tap the command -> /exit"),
+    ("sticker.exit_button", "Exit"),
+    ("sticker.exited", "Exited sticker mode~"),
+    ("sticker.file_not_found", "Can't seem to find that file..."),
+    ("sticker.file_info_failed", "Failed to get the file info..."),
+    ("sticker.convert_done", "Conversion done~\nYou can keep sending stickers to convert~\nTo exit, tap the command -> /exit"),
+    ("sticker.unsupported_sticker", "Only WebP and WebM stickers are supported for now..."),
+    ("sticker.no_media", "Doesn't look like there's an image or animation..."),
+    ("sticker.unsupported_format", "Only {} images and {} animations are supported for now..."),
+    ("sticker.size_limit", "Only files up to {} KiB are supported for now..."),
+    ("sticker.not_in_set", "This sticker doesn't belong to any sticker set..."),
+    ("sticker.set_not_found", "Can't seem to find that sticker set..."),
+    ("sticker.set_download_done", "Download done~\nYou can keep sending sticker sets to download~\nTo exit, tap the command -> /exit"),
+    ("sticker.set_download_partial", "Sticker download finished~ ({} stickers failed)"),
+    ("sticker.set_download_title", "Downloading stickers..."),
+    // Pixiv
+    ("cmd.pixiv", "Download illusts from Pixiv"),
+    ("help.pixiv", "Pixiv illust download"),
+    ("pixiv.invalid_id", "Doesn't look like a valid pixiv ID..."),
+    ("pixiv.download_started", "Start downloading the illust..."),
+    ("pixiv.help", "/pixiv command help\n- Usage: /pixiv <id> [nolim|files|archive|detail|metaonly]\n\nOptions:\n- nolim: allow galleries with more than 10 pages\n- files: send the illusts as files\n- archive: send a zip archive of the gallery\n- detail: detailed illust description\n- metaonly: send the metadata (illust description) only\n"),
+    ("pixiv.illust_not_found", "Can't find this pixiv gallery..."),
+    ("pixiv.source_blocked", "The source link is blocked..."),
+    ("pixiv.source_invalid", "Something seems wrong with the source link...?"),
+    ("pixiv.send_photos", "Send photos"),
+    ("pixiv.send_files", "Send files"),
+    ("pixiv.send_archive", "Send archive"),
+    ("pixiv.download_partial", "Gallery download finished, but {} pages failed to download..."),
+    ("pixiv.page_limited", "The gallery has {} pages, only the first 10 are shown here.\nTo send the whole gallery, use the nolim option."),
+    ("pixiv.ugoira_not_found", "Can't find this pixiv ugoira..."),
+    ("pixiv.download_title", "Downloading illusts..."),
+    // Kemono
+    ("cmd.kemono", "Preview or download archives on kemono.cr"),
+    ("help.kemono", "Kemono and Fanbox archive download"),
+    ("kemono.invalid_link", "Doesn't look like a valid kemono.cr link..."),
+    ("kemono.creator_not_found", "Can't seem to find this creator... (or querying the FANBOX creator failed)"),
+    ("kemono.page_not_found", "Can't find this kemono.cr page..."),
+    ("kemono.requesting_metadata", "Requesting file metadata..."),
+    ("kemono.help", "/kemono command help\n"),
+    ("kemono.creator_home", "Possible kemono.cr page of the creator: {}"),
+    ("kemono.size_exceeded", "The files are larger than {} MB in total... ({} MiB)\nPlease download them on kemono.cr yourself."),
+    ("kemono.download_partial", "Download finished, but {} files failed to download..."),
+    ("kemono.telegraph_preview", "Telegraph preview: <a href=\"{}\">{}</a>"),
+    ("kemono.download_title", "Downloading files from kemono.cr..."),
+    // Monitor
+    ("monitor.edited_copy", "✏️ The message above is an edited version"),
+    ("monitor.not_forwarded", "This doesn't seem to be a forwarded message... Please forward a message from the user to monitor\nTo exit, use the command /exit"),
+    ("monitor.forward_hidden", "The sender of this message is hidden... Please forward another message from the user to monitor\nTo exit, use the command /exit"),
+    ("monitor.not_external_reply", "This doesn't seem to be a reply to a message in another chat...\nTo be specific, reply to the user to monitor in the group to monitor, choose \"Reply in Another Chat\", and reply with anything here.\nTo exit, use the command /exit"),
+    ("monitor.reply_hidden", "The sender of this message is hidden... Please reply to another message.\nTo exit, use the command /exit"),
+    ("monitor.reply_no_chat", "The replied message has no group info, so the rule won't filter by group."),
+    ("monitor.select_user_retry", "Please tap the button below to select a user to monitor~\nTo skip filtering by user, use the command /skip\nTo exit, use the command /exit"),
+    ("monitor.nothing_selected", "At least one user or one group is needed.\nTo start adding a monitor rule again, use the command /monitor"),
+    ("monitor.select_chat_retry", "Please tap the button below to select a group to monitor~\nTo skip filtering by group, use the command /skip\nTo exit, use the command /exit"),
+    ("monitor.keyword_prompt", "Please send keywords separated by spaces, 64 characters at most.\nTo skip filtering by keywords, use the command /skip"),
+    ("monitor.forward_prompt", "Please forward a message from the user to monitor~"),
+    ("monitor.reply_prompt", "Please reply here to a message sent by \"the user to monitor\" in \"the group to monitor\"~"),
+    ("monitor.select_user_prompt", "Please select a user to monitor~\nTo skip filtering by user, use the command /skip"),
+    ("monitor.select_chat_prompt", "Please select a group to monitor~\nTo skip filtering by group, use the command /skip"),
+    ("monitor.select_user_button", "Select user"),
+    ("monitor.select_chat_button", "Select group"),
+    ("monitor.rule_created", "Monitor rule created: <code>{}</code>"),
+    ("monitor.rule_user", " - User: {}"),
+    ("monitor.rule_user_named", " - User: {} ({})"),
+    ("monitor.rule_no_user", " - User: (any user)"),
+    ("monitor.rule_chat", " - Group: {}"),
+    ("monitor.rule_chat_named", " - Group: {} ({})"),
+    ("monitor.rule_no_chat", " - Group: (any group)"),
+    ("monitor.rule_no_keyword", " - Keywords: (any keyword)"),
+    ("monitor.rule_keywords", " - Keywords: {}"),
+    ("cmd.monitor", "Monitor and forward messages in groups"),
+    ("help.monitor", "Group message monitor"),
+    ("monitor.invalid_uuid", "The UUID doesn't look right..."),
+    ("monitor.remove_missing_uuid", "Please add the UUID of the rule to remove after /monitor remove~"),
+    ("monitor.rule_not_found", "Can't seem to find this rule..."),
+    ("monitor.rule_count", "You have {} monitor rules:"),
+    ("monitor.remove_button", "Remove {}"),
+    ("monitor.rule_removed", "Removed the rule with UUID {}~"),
+    ("monitor.rules_removed", "Removed {} rules~"),
+    ("monitor.help", r"/monitor <b>command help</b>
+<blockquote expandable> - Aliases: /mon or /monitor
+ - Usage: /mon &lt;command&gt; [args]
+
+<b>Add a rule</b>:
+ - Commands: <code>add</code>, <code>a</code>
+ - <code>add</code>: select the user and group interactively, and set keywords
+ - <code>add forward</code>: select the user by forwarding a message
+   - <code>add f</code>: short form
+ - <code>add reply</code>: select the user and group by replying in another chat
+   - <code>add r</code>: short form
+
+<b>List all rules</b>:
+ - Commands: <code>list</code>, <code>ls</code>, <code>rules</code>
+
+<b>Remove a rule</b>:
+ - Commands: <code>remove</code>, <code>rm</code>
+ - <code>remove &lt;uuid&gt;</code>: remove the rule with the UUID
+
+<b>Remove all rules</b>:
+ - Commands: <code>removeall</code>, <code>rmall</code>
+</blockquote>
+"),
+];
@@ -0,0 +1,174 @@
+//! Japanese
+
+pub const CATALOG: &[(&str, &str)] = &[
+    // Basic commands
+    ("help.header", "ミントティーです～ 現在使える機能はこちら"),
+    ("cmd.help", "ヘルプを表示"),
+    ("cmd.exit", "現在の機能を終了"),
+    ("help.basic", "基本コマンド"),
+    ("modal.expired", "しばらく操作がなかったので、現在の機能を自動的に終了しました～"),
+    ("common.admin_only_command", "グループでは、このコマンドは管理者のみ使用できます。"),
+    ("common.admin_only_action", "グループでは、この操作は管理者のみ実行できます。"),
+    ("common.message_too_old", "このメッセージは古すぎます、もう一度コマンドを使ってください～"),
+    ("common.download_failed", "ファイルのダウンロードに失敗しました……"),
+    ("common.transcode_failed", "ファイルの変換に失敗しました……"),
+    // Errors
+    ("error.network", "ネットワークに問題が発生したようです、しばらくしてからもう一度お試しください……\nインシデント ID：{}"),
+    ("error.upstream_not_found", "元のサイトにこのコンテンツはないようです……\nインシデント ID：{}"),
+    ("error.upstream_blocked", "元のサイトにリクエストを拒否されました、コンテンツが制限されているかもしれません……\nインシデント ID：{}"),
+    ("error.too_large", "ファイルが大きすぎて送信できません……\nインシデント ID：{}"),
+    ("error.telegram_rejected", "Telegram に送信を拒否されました……\nインシデント ID：{}"),
+    ("error.internal", "処理中にエラーが発生しました……\nインシデント ID：{}"),
+    // Settings
+    ("cmd.settings", "このチャットの機能設定を変更"),
+    ("help.settings", "チャット設定"),
+    ("settings.already_default", "すでにデフォルト設定です～"),
+    ("settings.updated", "設定を更新しました～"),
+    ("settings.on", "オン"),
+    ("settings.off", "オフ"),
+    ("settings.default_value", "{}（デフォルト）"),
+    ("settings.title", "このチャットの設定です、ボタンで切り替えられます："),
+    ("settings.language", "言語"),
+    ("settings.language_auto", "ユーザーに合わせる"),
+    ("settings.reset", "すべてデフォルトに戻す"),
+    ("settings.pixiv_link", "Pixiv リンクを認識"),
+    ("settings.spoiler_nsfw", "NSFW イラストにスポイラー"),
+    ("settings.spoiler_r18g", "R-18G イラストにスポイラー"),
+    ("settings.kemono_link", "Kemono リンクを認識"),
+    ("settings.fanbox_link", "Fanbox リンクを認識"),
+    ("settings.progress_reactions", "進捗をリアクションで表示"),
+    // Jobs
+    ("cmd.jobs", "実行中・待機中のダウンロードジョブを表示（ボット管理者）"),
+    ("jobs.queued", "ダウンロードは待機中です。現在 {} 番目です、少々お待ちください～"),
+    ("jobs.shutdown", "ボットが停止するため、待機中のジョブはキャンセルされました。後でもう一度お試しください……"),
+    ("jobs.bot_admin_only", "このコマンドはボット管理者のみ使用できます。"),
+    ("jobs.none", "実行中・待機中のジョブはありません～"),
+    ("jobs.running", "実行中のジョブ（{}）："),
+    ("jobs.waiting", "待機中のジョブ（{}）："),
+    ("help.jobs", "ダウンロードジョブ"),
+    ("cmd.cancel", "自分のダウンロードジョブをキャンセル"),
+    ("jobs.cancel_button", "キャンセル"),
+    ("jobs.cancelled", "ダウンロードジョブをキャンセルし、一時ファイルを削除しました～"),
+    ("jobs.cancelling", "キャンセルしています……"),
+    ("jobs.cancel_usage", "使い方：/cancel [ジョブ番号]"),
+    ("jobs.nothing_to_cancel", "ここでキャンセルできるジョブはありません～"),
+    ("jobs.already_finished", "このジョブはすでに終了しています～"),
+    ("jobs.not_owner", "ジョブを開始したユーザーのみキャンセルできます。"),
+    ("jobs.joined", "同じリクエストを処理中です。完了したら結果をこちらにも送信します～"),
+    ("jobs.disk_full", "一時領域が不足しているため、新しいダウンロードを開始できません。しばらくしてからもう一度お試しください……"),
+    ("jobs.temp_usage", "一時領域：{}（{} 項目）"),
+    // Progress
+    ("progress.items", "{}/{} 完了"),
+    ("progress.failed", "（{} 件失敗）"),
+    ("progress.eta", "残り約 {}"),
+    // Archive
+    ("archive.split", "{} 個のファイルがアップロード上限を超えたため、.001、.002…… に分割してアーカイブに入れました。展開後に順番に結合してください～"),
+    // Sticker
+    ("cmd.sticker_convert", "スタンプ・画像・GIF を変換"),
+    ("cmd.sticker_set_download", "スタンプセットをダウンロード"),
+    ("help.sticker", "スタンプ変換とスタンプセットのダウンロード"),
+    ("sticker.private_only", "スタンプコマンドはプライベートチャットでのみ使えます～"),
+    ("sticker.convert_prompt", "変換したいスタンプ・画像・GIF を送ってください～\n終了するには、コマンドをタップ -> /exit"),
+    ("sticker.set_download_prompt", "ダウンロードしたいスタンプセットのスタンプを一つ送ってください～\n終了するには、コマンドをタップ -> /exit"),
+    ("sticker.exit_button", "終了"),
+    ("sticker.exited", "スタンプモードを終了しました～"),
+    ("sticker.file_not_found", "そのファイルが見つからないようです……"),
+    ("sticker.file_info_failed", "ファイル情報の取得に失敗しました……"),
+    ("sticker.convert_done", "変換が完了しました～\n引き続き変換したいスタンプを送れます～\n終了するには、コマンドをタップ -> /exit"),
+    ("sticker.unsupported_sticker", "今のところ WebP と WebM 以外のスタンプには対応していません……"),
+    ("sticker.no_media", "画像や GIF が見当たらないようです……"),
+    ("sticker.unsupported_format", "今のところ {} 形式の画像と {} 形式の GIF にのみ対応しています……"),
+    ("sticker.size_limit", "今のところ最大 {} KiB のファイルにのみ対応しています……"),
+    ("sticker.not_in_set", "このスタンプはどのスタンプセットにも属していません……"),
+    ("sticker.set_not_found", "そのスタンプセットが見つからないようです……"),
+    ("sticker.set_download_done", "ダウンロードが完了しました～\n引き続きダウンロードしたいスタンプセットを送れます～\n終了するには、コマンドをタップ -> /exit"),
+    ("sticker.set_download_partial", "スタンプのダウンロードが完了しました～ ({} 枚が失敗)"),
+    ("sticker.set_download_title", "スタンプをダウンロード中……"),
+    // Pixiv
+    ("cmd.pixiv", "Pixiv からイラストをダウンロード"),
+    ("help.pixiv", "Pixiv イラストのダウンロード"),
+    ("pixiv.invalid_id", "正しい pixiv ID が認識できなかったようです……"),
+    ("pixiv.download_started", "イラストのダウンロードを開始します……"),
+    ("pixiv.help", "/pixiv コマンドのヘルプ\n- 使い方：/pixiv <id> [nolim|files|archive|detail|metaonly]\n\nオプション：\n- nolim: 10 ページを超える作品を許可\n- files: イラストをファイルで送信\n- archive: 作品の zip アーカイブを送信\n- detail: イラストの詳細な説明\n- metaonly: メタデータ（イラストの説明）のみ送信\n"),
+    ("pixiv.illust_not_found", "この pixiv 作品が見つかりませんでした……"),
+    ("pixiv.source_blocked", "画像元のリンクがブロックされています……"),
+    ("pixiv.source_invalid", "画像元のリンクに問題があるようです……？"),
+    ("pixiv.send_photos", "画像で送信"),
+    ("pixiv.send_files", "ファイルで送信"),
+    ("pixiv.send_archive", "アーカイブで送信"),
+    ("pixiv.download_partial", "作品のダウンロードが完了しましたが、{} ページが失敗したようです……"),
+    ("pixiv.page_limited", "この作品は全 {} ページです、ここでは最初の 10 ページのみ表示しています。\n作品全体を送信するには、nolim オプションを使ってください。"),
+    ("pixiv.ugoira_not_found", "この pixiv うごイラが見つかりませんでした……"),
+    ("pixiv.download_title", "イラストをダウンロード中……"),
+    // Kemono
+    ("cmd.kemono", "kemono.cr のアーカイブをプレビュー・ダウンロード"),
+    ("help.kemono", "Kemono と Fanbox のアーカイブダウンロード"),
+    ("kemono.invalid_link", "正しい kemono.cr リンクが認識できなかったようです……"),
+    ("kemono.creator_not_found", "このクリエイターが見つからないようです…… (FANBOX クリエイターの検索に失敗した可能性もあります)"),
+    ("kemono.page_not_found", "この kemono.cr ページが見つかりませんでした……"),
+    ("kemono.requesting_metadata", "ファイルのメタデータを取得中……"),
+    ("kemono.help", "/kemono コマンドのヘルプ\n"),
+    ("kemono.creator_home", "このクリエイターの kemono.cr ページかもしれません： {}"),
+    ("kemono.size_exceeded", "ファイルの合計サイズが {} MB を超えています…… ({} MiB)\nkemono.cr で直接ダウンロードしてください。"),
+    ("kemono.download_partial", "ダウンロードが完了しましたが、{} 個のファイルが失敗したようです……"),
+    ("kemono.telegraph_preview", "Telegraph プレビュー: <a href=\"{}\">{}</a>"),
+    ("kemono.download_title", "kemono.cr からファイルをダウンロード中……"),
+    // Monitor
+    ("monitor.edited_copy", "✏️ 上のメッセージは編集後のものです"),
+    ("monitor.not_forwarded", "これは転送されたメッセージではないようです……監視したいユーザーのメッセージを転送してください\n終了するには、コマンド /exit を使ってください"),
+    ("monitor.forward_hidden", "このメッセージの送信者情報は非表示になっています……監視したいユーザーのメッセージをもう一度転送してください\n終了するには、コマンド /exit を使ってください"),
+    ("monitor.not_external_reply", "これは他のチャットでの返信ではないようです……\n具体的には、監視したいグループで監視したいユーザーに返信し、「別のチャットで返信」を選んで、ここで何か返信してください。\n終了するには、コマンド /exit を使ってください"),
+    ("monitor.reply_hidden", "このメッセージの送信者情報は非表示になっています……もう一度メッセージに返信してください。\n終了するには、コマンド /exit を使ってください"),
+    ("monitor.reply_no_chat", "返信先のメッセージにグループ情報がないため、グループでは絞り込まないルールになります。"),
+    ("monitor.select_user_retry", "下のボタンをタップして、監視したいユーザーを選んでください～\nユーザーで絞り込まない場合は、コマンド /skip でスキップ\n終了するには、コマンド /exit を使ってください"),
+    ("monitor.nothing_selected", "ユーザーかグループを少なくとも一つ選ぶ必要があります。\n監視ルールの追加をやり直すには、コマンド /monitor を使ってください"),
+    ("monitor.select_chat_retry", "下のボタンをタップして、監視したいグループを選んでください～\nグループで絞り込まない場合は、コマンド /skip でスキップ\n終了するには、コマンド /exit を使ってください"),
+    ("monitor.keyword_prompt", "スペース区切りのキーワードを送ってください、合計 64 文字までです。\nキーワードで絞り込まない場合は、コマンド /skip でスキップ"),
+    ("monitor.forward_prompt", "監視したいユーザーのメッセージを転送してください～"),
+    ("monitor.reply_prompt", "「監視したいユーザー」が「監視したいグループ」で送ったメッセージに、ここで返信してください～"),
+    ("monitor.select_user_prompt", "監視したいユーザーを選んでください～\nユーザーで絞り込まない場合は、コマンド /skip でスキップ"),
+    ("monitor.select_chat_prompt", "監視したいグループを選んでください～\nグループで絞り込まない場合は、コマンド /skip でスキップ"),
+    ("monitor.select_user_button", "ユーザーを選択"),
+    ("monitor.select_chat_button", "グループを選択"),
+    ("monitor.rule_created", "監視ルールを作成しました: <code>{}</code>"),
+    ("monitor.rule_user", " - ユーザー: {}"),
+    ("monitor.rule_user_named", " - ユーザー: {} ({})"),
+    ("monitor.rule_no_user", " - ユーザー: (すべてのユーザー)"),
+    ("monitor.rule_chat", " - グループ: {}"),
+    ("monitor.rule_chat_named", " - グループ: {} ({})"),
+    ("monitor.rule_no_chat", " - グループ: (すべてのグループ)"),
+    ("monitor.rule_no_keyword", " - キーワード: (すべてのキーワード)"),
+    ("monitor.rule_keywords", " - キーワード: {}"),
+    ("cmd.monitor", "グループのメッセージを監視して転送"),
+    ("help.monitor", "グループメッセージの監視"),
+    ("monitor.invalid_uuid", "UUID の形式が正しくないようです……"),
+    ("monitor.remove_missing_uuid", "/monitor remove コマンドの後ろに削除したいルールの UUID を付けてください～"),
+    ("monitor.rule_not_found", "このルールが見つからないようです……"),
+    ("monitor.rule_count", "監視ルールが {} 件あります："),
+    ("monitor.remove_button", "{} を削除"),
+    ("monitor.rule_removed", "UUID {} のルールを削除しました～"),
+    ("monitor.rules_removed", "{} 件のルールを削除しました～"),
+    ("monitor.help", r"/monitor <b>コマンドのヘルプ</b>
+<blockquote expandable> - 別名: /mon または /monitor
+ - 使い方: /mon &lt;command&gt; [args]
+
+<b>ルールの追加</b>:
+ - コマンド: <code>add</code>, <code>a</code>
+ - <code>add</code>: ユーザーとグループを対話的に選び、キーワードを設定
+ - <code>add forward</code>: メッセージの転送でユーザーを選択
+   - <code>add f</code>: 省略形
+ - <code>add reply</code>: 他のチャットでの返信でユーザーとグループを選択
+   - <code>add r</code>: 省略形
+
+<b>すべてのルールを表示</b>:
+ - コマンド: <code>list</code>, <code>ls</code>, <code>rules</code>
+
+<b>ルールの削除</b>:
+ - コマンド: <code>remove</code>, <code>rm</code>
+ - <code>remove &lt;uuid&gt;</code>: 指定した UUID のルールを削除
+
+<b>すべてのルールを削除</b>:
+ - コマンド: <code>removeall</code>, <code>rmall</code>
+</blockquote>
+"),
+];
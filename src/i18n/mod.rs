@@ -0,0 +1,143 @@
+mod zh_hant;
+mod en;
+mod ja;
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use frankenstein::types::{CallbackQuery, Message, User};
+use serde::{Deserialize, Serialize};
+
+use crate::context::Context;
+
+/// Locales with a message catalog, Traditional Chinese is the most complete one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "zh-hant")]
+    ZhHant,
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "ja")]
+    Ja,
+}
+
+impl Locale {
+    pub const ALL: &[Locale] = &[Locale::ZhHant, Locale::En, Locale::Ja];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::ZhHant => "zh-hant",
+            Locale::En => "en",
+            Locale::Ja => "ja",
+        }
+    }
+
+    /// Language name shown to users
+    pub fn name(&self) -> &'static str {
+        match self {
+            Locale::ZhHant => "繁體中文",
+            Locale::En => "English",
+            Locale::Ja => "日本語",
+        }
+    }
+
+    /// The two-letter language code used by `set_my_commands`
+    pub fn language_code(&self) -> &'static str {
+        match self {
+            Locale::ZhHant => "zh",
+            Locale::En => "en",
+            Locale::Ja => "ja",
+        }
+    }
+
+    /// Match an IETF language tag from `User.language_code`, e.g. `en-US`
+    pub fn from_language_code(code: &str) -> Option<Locale> {
+        let primary = code.split(['-', '_']).next()?.to_ascii_lowercase();
+        match primary.as_str() {
+            "zh" => Some(Locale::ZhHant),
+            "en" => Some(Locale::En),
+            "ja" => Some(Locale::Ja),
+            _ => None
+        }
+    }
+
+    fn catalog(&self) -> &'static HashMap<&'static str, &'static str> {
+        static ZH_HANT: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| zh_hant::CATALOG.iter().copied().collect());
+        static EN: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| en::CATALOG.iter().copied().collect());
+        static JA: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| ja::CATALOG.iter().copied().collect());
+        match self {
+            Locale::ZhHant => &ZH_HANT,
+            Locale::En => &EN,
+            Locale::Ja => &JA,
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Locale::ALL.iter()
+            .find(|locale| locale.as_str() == s)
+            .copied()
+            .ok_or(())
+    }
+}
+
+/// Look up the message, falls back to Traditional Chinese, then the key itself
+pub fn tr(locale: Locale, key: &'static str) -> &'static str {
+    locale.catalog().get(key)
+        .or_else(|| Locale::ZhHant.catalog().get(key))
+        .copied()
+        .unwrap_or_else(|| {
+            log::warn!(target: "i18n", "Missing message {key}");
+            key
+        })
+}
+
+/// Look up the message and fill the `{}` placeholders in order
+pub fn tr_args(locale: Locale, key: &'static str, args: &[&(dyn Display + Sync)]) -> String {
+    let template = tr(locale, key);
+    let mut result = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        result.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+/// Chat override first, then the language of the user, then the configured default
+pub fn select_locale(ctx: &Context, chat_id: i64, user: Option<&User>) -> Locale {
+    ctx.settings.get(chat_id).language
+        .or_else(|| user
+            .and_then(|user| user.language_code.as_deref())
+            .and_then(Locale::from_language_code)
+        )
+        .unwrap_or(ctx.config.default_locale)
+}
+
+pub fn message_locale(ctx: &Context, msg: &Message) -> Locale {
+    select_locale(ctx, msg.chat.id, msg.from.as_deref())
+}
+
+pub fn callback_locale(ctx: &Context, query: &CallbackQuery) -> Locale {
+    let chat_id = crate::callback::callback_message(query)
+        .map(|msg| msg.chat.id)
+        .unwrap_or(query.from.id as i64);
+    select_locale(ctx, chat_id, Some(&query.from))
+}
+
+/// For messages sent without a triggering user, e.g. notices
+pub fn chat_locale(ctx: &Context, chat_id: i64) -> Locale {
+    select_locale(ctx, chat_id, None)
+}
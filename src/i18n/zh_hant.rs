@@ -0,0 +1,174 @@
+//! Traditional Chinese, the fallback of other locales
+
+pub const CATALOG: &[(&str, &str)] = &[
+    // Basic commands
+    ("help.header", "這裡是薄荷茶～ 目前支持這些功能"),
+    ("cmd.help", "顯示幫助信息"),
+    ("cmd.exit", "退出當前的功能"),
+    ("help.basic", "基本指令"),
+    ("modal.expired", "太久沒有操作了，已經自動退出當前的功能～"),
+    ("common.admin_only_command", "在群組中，這個指令只能由管理員執行。"),
+    ("common.admin_only_action", "在群組中，這個操作只能由管理員執行。"),
+    ("common.message_too_old", "這條消息太舊了，請重新使用指令哦——"),
+    ("common.download_failed", "下載文件失敗惹……"),
+    ("common.transcode_failed", "文件轉碼失敗惹……"),
+    // Errors
+    ("error.network", "網絡好像出了點問題，請稍後再試……\n事件編號：{}"),
+    ("error.upstream_not_found", "來源網站上似乎沒有這個內容呢……\n事件編號：{}"),
+    ("error.upstream_blocked", "來源網站拒絕了請求呢，可能是內容受到限制……\n事件編號：{}"),
+    ("error.too_large", "文件太大了，沒辦法發送呢……\n事件編號：{}"),
+    ("error.telegram_rejected", "Telegram 拒絕了發送請求呢……\n事件編號：{}"),
+    ("error.internal", "處理的時候出錯了……\n事件編號：{}"),
+    // Settings
+    ("cmd.settings", "修改本聊天中的功能設置"),
+    ("help.settings", "聊天設置"),
+    ("settings.already_default", "已經是默認設置了～"),
+    ("settings.updated", "設置已更新～"),
+    ("settings.on", "開啟"),
+    ("settings.off", "關閉"),
+    ("settings.default_value", "{}（默認）"),
+    ("settings.title", "本聊天的設置，點擊按鈕切換："),
+    ("settings.language", "語言"),
+    ("settings.language_auto", "跟隨用戶"),
+    ("settings.reset", "全部恢復默認"),
+    ("settings.pixiv_link", "識別 Pixiv 鏈接"),
+    ("settings.spoiler_nsfw", "NSFW 插畫加上遮罩"),
+    ("settings.spoiler_r18g", "R-18G 插畫加上遮罩"),
+    ("settings.kemono_link", "識別 Kemono 鏈接"),
+    ("settings.fanbox_link", "識別 Fanbox 鏈接"),
+    ("settings.progress_reactions", "以表情回應顯示進度"),
+    // Jobs
+    ("cmd.jobs", "查看運行和排隊中的下載任務（機器人管理員）"),
+    ("jobs.queued", "下載任務正在排隊中，目前排在第 {} 位，請稍等一下哦——"),
+    ("jobs.shutdown", "機器人正在關閉，排隊中的任務已取消，請稍後再試……"),
+    ("jobs.bot_admin_only", "這個指令只能由機器人管理員執行。"),
+    ("jobs.none", "目前沒有運行或排隊中的任務~"),
+    ("jobs.running", "運行中的任務（{}）："),
+    ("jobs.waiting", "排隊中的任務（{}）："),
+    ("help.jobs", "下載任務"),
+    ("cmd.cancel", "取消自己的下載任務"),
+    ("jobs.cancel_button", "取消"),
+    ("jobs.cancelled", "下載任務已取消，臨時文件已清理~"),
+    ("jobs.cancelling", "正在取消……"),
+    ("jobs.cancel_usage", "用法：/cancel [任務編號]"),
+    ("jobs.nothing_to_cancel", "你在這裡沒有可以取消的任務哦~"),
+    ("jobs.already_finished", "這個任務已經結束了~"),
+    ("jobs.not_owner", "只有發起任務的用戶可以取消這個任務。"),
+    ("jobs.joined", "相同的請求正在處理中，完成後會直接傳送結果"),
+    ("jobs.disk_full", "暫存空間不足，暫時無法開始新的下載任務，請稍後再試……"),
+    ("jobs.temp_usage", "暫存空間：{}（{} 個項目）"),
+    // Progress
+    ("progress.items", "已完成 {}/{}"),
+    ("progress.failed", "（{} 個失敗）"),
+    ("progress.eta", "預計剩餘 {}"),
+    // Archive
+    ("archive.split", "有 {} 個文件超過上傳大小限制，已經拆成 .001、.002… 分段放進歸檔，解壓後按順序接回去就好呢～"),
+    // Sticker
+    ("cmd.sticker_convert", "轉換貼紙、圖片和動圖"),
+    ("cmd.sticker_set_download", "下載貼紙包"),
+    ("help.sticker", "貼紙轉換和貼紙下載"),
+    ("sticker.private_only", "貼紙指令只能在私聊中使用哦——"),
+    ("sticker.convert_prompt", "請發送想要轉換的貼紙、圖片或動圖～\n如果要退出，請點擊指令 -> /exit"),
+    ("sticker.set_download_prompt", "請發送一張想要下載的貼紙包中的貼紙～\n如果要退出，請點擊指令 -> /exit"),
+    ("sticker.exit_button", "退出"),
+    ("sticker.exited", "已退出貼紙模式～"),
+    ("sticker.file_not_found", "好像找不到那個文件呢……"),
+    ("sticker.file_info_failed", "獲取文件信息失敗惹……"),
+    ("sticker.convert_done", "轉換完成啦～\n您可以繼續發送要轉換的貼紙～\n如果要退出，請點擊指令 -> /exit"),
+    ("sticker.unsupported_sticker", "現在還不支援 WebP 和 WebM 格式外的貼紙哦……"),
+    ("sticker.no_media", "似乎沒有看到有圖片或動圖呢……"),
+    ("sticker.unsupported_format", "目前只支援 {} 格式的圖片和 {} 格式的動圖呢……"),
+    ("sticker.size_limit", "目前只支持最大 {} KiB 的文件呢……"),
+    ("sticker.not_in_set", "這張貼紙不屬於任何貼紙包呢……"),
+    ("sticker.set_not_found", "似乎找不到那個貼紙包呢……"),
+    ("sticker.set_download_done", "下載完成啦～\n您可以繼續發送要下載的貼紙包～\n如果要退出，請點擊指令 -> /exit"),
+    ("sticker.set_download_partial", "貼紙下載完成了～ ({} 張貼紙下載失敗)"),
+    ("sticker.set_download_title", "正在下載貼紙……"),
+    // Pixiv
+    ("cmd.pixiv", "從 Pixiv 下載插畫"),
+    ("help.pixiv", "Pixiv 插畫下載"),
+    ("pixiv.invalid_id", "似乎沒有識別到正確的 pixiv ID 呢……"),
+    ("pixiv.download_started", "開始下載插畫……"),
+    ("pixiv.help", "/pixiv 指令幫助\n- 使用方法：/pixiv <id> [nolim|files|archive|detail|metaonly]\n\n參數說明：\n- nolim: 允許 10 頁插畫以上的畫廊\n- files: 發送插畫文件\n- archive: 發送畫廊的 zip 歸檔\n- detail: 詳細插畫描述信息\n- metaonly: 只發送元數據（插畫描述信息）\n"),
+    ("pixiv.illust_not_found", "沒有找到這個 pixiv 畫廊呢……"),
+    ("pixiv.source_blocked", "圖源的鏈接被屏蔽了呢……"),
+    ("pixiv.source_invalid", "圖源的鏈接好像有點問題呢……？"),
+    ("pixiv.send_photos", "發送圖片"),
+    ("pixiv.send_files", "發送文件"),
+    ("pixiv.send_archive", "發送歸檔"),
+    ("pixiv.download_partial", "畫廊下載完成了，但似乎有 {} 頁插畫下載失敗了呢……"),
+    ("pixiv.page_limited", "原畫廊共有 {} 頁，此處僅展示前 10 頁。\n如果要發送整個畫廊，請使用 nolim 參數。"),
+    ("pixiv.ugoira_not_found", "沒有找到這個 pixiv 动图呢……"),
+    ("pixiv.download_title", "正在下載插畫……"),
+    // Kemono
+    ("cmd.kemono", "預覽或下載 kemono.cr 上的歸檔"),
+    ("help.kemono", "Kemono 和 Fanbox 歸檔下載"),
+    ("kemono.invalid_link", "似乎沒有識別到正確的 kemono.cr 鏈接呢……"),
+    ("kemono.creator_not_found", "似乎沒找到這個創作者呢…… (也有可能是查詢 FANBOX 創作者失敗了)"),
+    ("kemono.page_not_found", "沒有找到這個 kemono.cr 頁面呢……"),
+    ("kemono.requesting_metadata", "正在請求文件元數據……"),
+    ("kemono.help", "/kemono 指令幫助\n"),
+    ("kemono.creator_home", "該作者可能的 kemono.cr 主頁： {}"),
+    ("kemono.size_exceeded", "文件總大小超過 {} MB 了呢…… ({} MiB)\n請自行前往 kemono.cr 下載。"),
+    ("kemono.download_partial", "文件下載完成了，但似乎有 {} 個文件下載失敗了呢……"),
+    ("kemono.telegraph_preview", "Telegraph 預覽: <a href=\"{}\">{}</a>"),
+    ("kemono.download_title", "正在從 kemono.cr 下載文件……"),
+    // Monitor
+    ("monitor.edited_copy", "✏️ 上面的消息是編輯後的版本"),
+    ("monitor.not_forwarded", "這條消息似乎不是轉發的消息呢……請轉發一條要監視的用戶的消息\n如果需要退出，使用指令 /exit 退出"),
+    ("monitor.forward_hidden", "這條消息的發送者信息被隱藏了呢……請重新轉發一條要監視的用戶的消息\n如果需要退出，使用指令 /exit 退出"),
+    ("monitor.not_external_reply", "這條消息似乎不是在其它群組中回覆的消息呢……\n具體來講，您需要在要監視的群組中回覆要監視的用戶，然後選擇「在另一個聊天中回覆」，並在這裡回覆任意內容。\n如果需要退出，使用指令 /exit 退出"),
+    ("monitor.reply_hidden", "這條消息的發送者信息被隱藏了呢……請重新回覆一條消息。\n如果需要退出，使用指令 /exit 退出"),
+    ("monitor.reply_no_chat", "這條消息轉發的消息中似乎沒有群組的信息，所以規則設置為不對群組進行篩選。"),
+    ("monitor.select_user_retry", "請點擊下方的按鈕，選擇一個要監視的用戶～\n如果不需要根據用戶篩選，使用指令 /skip 跳过\n如果需要退出，使用指令 /exit 退出"),
+    ("monitor.nothing_selected", "至少需要選擇監視一個用戶或一個群組。\n如果需要重新開始添加監視規則，使用指令 /monitor"),
+    ("monitor.select_chat_retry", "請點擊下方的按鈕，請選擇一個要監視的群組～\n如果不需要根據群組篩選，使用指令 /skip 跳过\n如果需要退出，使用指令 /exit 退出"),
+    ("monitor.keyword_prompt", "請發送以空格分隔的關鍵詞，總字符數量不超過 64 字。\n如果不需要根據關鍵詞篩選，使用指令 /skip 跳过"),
+    ("monitor.forward_prompt", "請轉發一條要監視的用戶的消息～"),
+    ("monitor.reply_prompt", "請在這裡回覆一條「要監視的用戶」在「要監視的群組」發送的消息～"),
+    ("monitor.select_user_prompt", "請選擇一個要監視的用戶～\n如果不需要根據用戶篩選，使用指令 /skip 跳过"),
+    ("monitor.select_chat_prompt", "請選擇一個要監視的群組～\n如果不需要根據群組篩選，使用指令 /skip 跳过"),
+    ("monitor.select_user_button", "選擇用戶"),
+    ("monitor.select_chat_button", "選擇群組"),
+    ("monitor.rule_created", "創建監視規則: {}"),
+    ("monitor.rule_user", " - 用戶: {}"),
+    ("monitor.rule_user_named", " - 用戶: {} ({})"),
+    ("monitor.rule_no_user", " - 用戶: (不匹配用戶)"),
+    ("monitor.rule_chat", " - 群組: {}"),
+    ("monitor.rule_chat_named", " - 群組: {} ({})"),
+    ("monitor.rule_no_chat", " - 群組: (不匹配群組)"),
+    ("monitor.rule_no_keyword", " - 關鍵詞: (不匹配關鍵詞)"),
+    ("monitor.rule_keywords", " - 關鍵詞: {}"),
+    ("cmd.monitor", "監控並轉發群組中的消息"),
+    ("help.monitor", "群組消息監控"),
+    ("monitor.invalid_uuid", "UUID 的格式似乎不太對呢……"),
+    ("monitor.remove_missing_uuid", "請在 /monitor remove 指令後面加上要刪除的規則的 UUID 哦——"),
+    ("monitor.rule_not_found", "好像找不到這條規則呢……"),
+    ("monitor.rule_count", "你有 {} 條監視規則哦："),
+    ("monitor.remove_button", "刪除 {}"),
+    ("monitor.rule_removed", "刪除了一條 UUID 為 {} 的規則——"),
+    ("monitor.rules_removed", "刪除了 {} 條規則——"),
+    ("monitor.help", r"/monitor <b>指令幫助</b>
+<blockquote expandable> - 指令別名: /mon 或 /monitor
+ - 使用方法: /mon &lt;command&gt; [args]
+
+<b>添加規則指令</b>:
+ - 指令: <code>add</code>, <code>a</code>
+ - <code>add</code>: 交互式選擇用戶和群組、並設置關鍵詞
+ - <code>add forward</code>: 通過轉發消息選擇用戶
+   - <code>add f</code>: 簡略指令
+ - <code>add reply</code>: 通過在其他聊天中回覆，選擇用戶和群組
+   - <code>add r</code>: 簡略指令
+
+<b>列出所有規則</b>:
+ - 指令: <code>list</code>, <code>ls</code>, <code>rules</code>
+
+<b>刪除規則</b>:
+ - 指令: <code>remove</code>, <code>rm</code>
+ - <code>remove &lt;uuid&gt;</code>: 刪除指定 UUID 的規則
+
+<b>刪除所有規則</b>:
+ - 指令: <code>removeall</code>, <code>rmall</code>
+</blockquote>
+"),
+];
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use tokio::sync::watch;
+
+/// The file IDs sent by the first request, None until it finishes
+type FlightResult = Option<Vec<String>>;
+
+/// Requests being processed, keyed by source, ID and mode, e.g. `pixiv:<id>:photos`.
+/// Identical requests arriving meanwhile wait for the first one and resend its file IDs
+#[derive(Debug, Default)]
+pub struct InFlightRequests {
+    map: Arc<DashMap<String, (u64, watch::Receiver<FlightResult>)>>,
+    next_id: AtomicU64,
+}
+
+pub enum Joined {
+    /// No identical request is running, this one does the work
+    First(FlightGuard),
+    Waiter(FlightWaiter),
+}
+
+/// Held by the first request, waiters are released when finished or dropped
+#[derive(Debug)]
+pub struct FlightGuard {
+    id: u64,
+    key: String,
+    sender: watch::Sender<FlightResult>,
+    map: Arc<DashMap<String, (u64, watch::Receiver<FlightResult>)>>,
+}
+
+impl FlightGuard {
+    /// Hand the sent file IDs to the waiters, in the order they are resent
+    pub fn finish(self, file_ids: Vec<String>) {
+        self.sender.send_replace(Some(file_ids));
+    }
+}
+
+impl Drop for FlightGuard {
+    fn drop(&mut self) {
+        self.map.remove_if(&self.key, |_, (id, _)| *id == self.id);
+    }
+}
+
+#[derive(Debug)]
+pub struct FlightWaiter {
+    receiver: watch::Receiver<FlightResult>,
+}
+
+impl FlightWaiter {
+    /// The file IDs of the first request, None if it failed or sent an incomplete result
+    pub async fn wait(&mut self) -> FlightResult {
+        // Err if the guard is dropped without finishing, the value is None then
+        let _ = self.receiver.wait_for(|result| result.is_some()).await;
+        self.receiver.borrow().clone()
+    }
+}
+
+impl InFlightRequests {
+    pub fn join(&self, key: &str) -> Joined {
+        match self.map.entry(key.to_string()) {
+            Entry::Occupied(entry) => Joined::Waiter(FlightWaiter { receiver: entry.get().1.clone() }),
+            Entry::Vacant(entry) => {
+                let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                let (sender, receiver) = watch::channel(None);
+                entry.insert((id, receiver));
+                Joined::First(FlightGuard { id, key: key.to_string(), sender, map: self.map.clone() })
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tempfile::TempDir;
+use tokio::time::sleep;
+
+use crate::context::Context;
+use crate::jobs::scheduler::JobGuard;
+
+/// Temp dirs of jobs are named `job<id>-...`
+const JOB_DIR_PREFIX: &str = "job";
+
+/// Temp dir of the job, the janitor removes it once the job is gone
+pub fn job_temp_dir(ctx: &Context, job: &JobGuard) -> std::io::Result<TempDir> {
+    tempfile::Builder::new()
+        .prefix(&format!("{JOB_DIR_PREFIX}{}-", job.id()))
+        .tempdir_in(&ctx.temp_root_path)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TempUsage {
+    pub bytes: u64,
+    /// Files and directories right under the temp directory
+    pub entries: usize,
+}
+
+/// Size of everything in the temp directory
+pub async fn temp_usage(path: &Path) -> TempUsage {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut usage = TempUsage::default();
+        let Ok(entries) = std::fs::read_dir(&path) else {
+            return usage;
+        };
+        for entry in entries.flatten() {
+            usage.bytes += entry_size(&entry.path());
+            usage.entries += 1;
+        }
+        usage
+    }).await.unwrap_or_default()
+}
+
+fn entry_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| entry_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Whether `bytes` more fit in the temp directory under the quota
+pub async fn temp_has_room(ctx: &Context, bytes: u64) -> bool {
+    let Some(quota) = ctx.config.temp.quota else {
+        return true;
+    };
+    temp_usage(&ctx.temp_root_path).await.bytes + bytes <= quota * 1_000_000
+}
+
+/// Remove everything in the temp directory, used when no job is running. Returns how many entries are removed
+pub fn clear_temp_dir(path: &Path) -> usize {
+    remove_entries(path, |_, _| true)
+}
+
+/// Remove the temp dirs of finished jobs, and other entries older than `TempConfig.max_age`
+fn sweep_temp_dir(ctx: &Context) -> usize {
+    let max_age = Duration::from_secs(ctx.config.temp.max_age);
+    remove_entries(&ctx.temp_root_path, |name, modified| {
+        let job_id = name.strip_prefix(JOB_DIR_PREFIX)
+            .and_then(|rest| rest.split_once('-'))
+            .and_then(|(id, _)| id.parse::<u64>().ok());
+        match job_id {
+            Some(job_id) => ctx.jobs.get(job_id).is_none(),
+            None => modified.elapsed().is_ok_and(|age| age > max_age),
+        }
+    })
+}
+
+fn remove_entries(path: &Path, is_stale: impl Fn(&str, SystemTime) -> bool) -> usize {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!(target: "temp_janitor", "Failed to read temp directory: {e}");
+            return 0;
+        }
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let modified = entry.metadata().and_then(|metadata| metadata.modified()).unwrap_or(SystemTime::now());
+        if !is_stale(&name, modified) {
+            continue;
+        }
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => removed += 1,
+            // Removed by the job in between
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!(target: "temp_janitor", "Failed to remove {}: {e}", path.to_string_lossy()),
+        }
+    }
+    removed
+}
+
+/// Remove leftovers in the temp directory periodically
+pub async fn temp_janitor(ctx: Arc<Context>) {
+    let interval = Duration::from_secs(ctx.config.temp.sweep_interval.max(1));
+    loop {
+        tokio::select! {
+            _ = sleep(interval) => {},
+            _ = ctx.shutdown.cancelled() => break,
+        }
+
+        let ctx_cloned = ctx.clone();
+        let removed = tokio::task::spawn_blocking(move || sweep_temp_dir(&ctx_cloned)).await.unwrap_or(0);
+        if removed > 0 {
+            log::info!(target: "temp_janitor", "Removed {removed} stale entries in temp directory");
+        }
+    }
+}
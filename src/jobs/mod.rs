@@ -0,0 +1,304 @@
+pub mod in_flight;
+pub mod janitor;
+pub mod scheduler;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use frankenstein::types::{CallbackQuery, InlineKeyboardMarkup, Message};
+use futures::future::BoxFuture;
+
+use crate::callback::{CallbackAnswer, CallbackData, callback_button, callback_message};
+use crate::context::Context;
+use crate::handler::{CallbackHandlerResult, Handler, HandlerResult, UpdateKind};
+use crate::helper::{bot_actions, param_builders};
+use crate::helper::log::LogOp;
+use crate::helper::message_utils::{get_command, get_sender_id};
+use crate::helper::progress::format_bytes;
+use crate::i18n::{Locale, callback_locale, message_locale, tr, tr_args};
+use crate::jobs::in_flight::FlightWaiter;
+use crate::jobs::janitor::{temp_has_room, temp_usage};
+use crate::jobs::scheduler::{JobGuard, JobInfo, JobKind};
+use crate::updater::dispatcher::release_worker;
+
+pub const COMMAND_LIST: &[(&str, &str)] = &[
+    ("cancel", "cmd.cancel"),
+    ("jobs", "cmd.jobs"),
+];
+
+pub const CALLBACK_NAMESPACE: &str = "job";
+
+pub struct JobsHandler;
+
+impl Handler for JobsHandler {
+    fn name(&self) -> &'static str { "jobs" }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] { COMMAND_LIST }
+
+    fn help(&self) -> Option<&'static str> { Some("help.jobs") }
+
+    // Handled before modal states, so /cancel is always available
+    fn priority(&self) -> i32 { -10 }
+
+    fn handle_message(&self, ctx: Arc<Context>, msg: Arc<Message>, _kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        jobs_handler(ctx, msg)
+    }
+
+    fn callback_namespace(&self) -> Option<&'static str> { Some(CALLBACK_NAMESPACE) }
+
+    fn handle_callback(&self, ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+        jobs_callback_handler(ctx, query, data)
+    }
+}
+
+/// The cancel button attached to progress messages
+pub fn cancel_markup(job: &JobGuard, locale: Locale) -> InlineKeyboardMarkup {
+    let button = callback_button(tr(locale, "jobs.cancel_button"), CALLBACK_NAMESPACE, "cancel", &[&job.id().to_string()]);
+    param_builders::inline_keyboard(vec![vec![button]])
+}
+
+/// Users cancel their own jobs in the chat, bot admins cancel any job
+fn can_cancel(ctx: &Context, job: &JobInfo, chat_id: i64, sender_id: Option<i64>) -> bool {
+    ctx.config.access.is_admin(sender_id)
+        || (sender_id.is_some() && job.user_id == sender_id && job.chat_id == chat_id)
+}
+
+/// Wait for a free slot of the scheduler, telling the user the queue position if the job has to wait,
+/// returns None if the job is cancelled or the bot shuts down before the job starts,
+/// or `expected_size` bytes, an estimate of the files of the job, do not fit in the temp quota
+pub async fn start_job(
+    ctx: &Context,
+    msg: &Message,
+    kind: JobKind,
+    description: impl Into<String>,
+    expected_size: u64
+) -> anyhow::Result<Option<JobGuard>> {
+    // The job may wait in the queue and run for long, later updates of the sender, e.g. /cancel, go on meanwhile
+    release_worker();
+
+    if !temp_has_room(ctx, expected_size).await {
+        log::warn!(
+            target: "jobs",
+            "{} Job ({}) refused, {} more bytes do not fit in the temp quota",
+            LogOp(msg), kind.as_str(), expected_size
+        );
+        bot_actions::send_reply_message(
+            &ctx.bot, msg.chat.id, tr(message_locale(ctx, msg), "jobs.disk_full"),
+            msg.message_id, None
+        ).await?;
+        return Ok(None);
+    }
+
+    let (guard, queue_position) = ctx.jobs.submit(kind, msg.chat.id, get_sender_id(msg), description.into());
+    let Some(queue_position) = queue_position else {
+        return Ok(Some(guard));
+    };
+
+    log::info!(
+        target: "jobs",
+        "{} Job {} ({}) queued at #{}",
+        LogOp(msg), guard.id(), kind.as_str(), queue_position.position
+    );
+    bot_actions::send_reply_message(
+        &ctx.bot, msg.chat.id,
+        tr_args(message_locale(ctx, msg), "jobs.queued", &[&queue_position.position]),
+        msg.message_id, None
+    ).await?;
+
+    tokio::select! {
+        _ = queue_position.start => {
+            log::info!(target: "jobs", "{} Job {} started", LogOp(msg), guard.id());
+            Ok(Some(guard))
+        }
+        _ = guard.token().cancelled() => {
+            log::info!(target: "jobs", "{} Job {} cancelled before start", LogOp(msg), guard.id());
+            bot_actions::send_reply_message(
+                &ctx.bot, msg.chat.id, tr(message_locale(ctx, msg), "jobs.cancelled"),
+                msg.message_id, None
+            ).await?;
+            Ok(None)
+        }
+        _ = ctx.shutdown.cancelled() => {
+            bot_actions::send_reply_message(
+                &ctx.bot, msg.chat.id, tr(message_locale(ctx, msg), "jobs.shutdown"),
+                msg.message_id, None
+            ).await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Like `start_job` for updates without a message to reply to, e.g. inline queries, the job is waited for silently,
+/// it belongs to the private chat of the user so `/cancel` there reaches it
+pub async fn start_user_job(
+    ctx: &Context,
+    kind: JobKind,
+    user_id: i64,
+    description: impl Into<String>,
+    expected_size: u64
+) -> Option<JobGuard> {
+    release_worker();
+
+    if !temp_has_room(ctx, expected_size).await {
+        log::warn!(
+            target: "jobs",
+            "[User: {user_id}] Job ({}) refused, {} more bytes do not fit in the temp quota",
+            kind.as_str(), expected_size
+        );
+        return None;
+    }
+
+    let (guard, queue_position) = ctx.jobs.submit(kind, user_id, Some(user_id), description.into());
+    let Some(queue_position) = queue_position else {
+        return Some(guard);
+    };
+    log::info!(
+        target: "jobs",
+        "[User: {user_id}] Job {} ({}) queued at #{}",
+        guard.id(), kind.as_str(), queue_position.position
+    );
+
+    tokio::select! {
+        _ = queue_position.start => Some(guard),
+        _ = guard.token().cancelled() => None,
+        _ = ctx.shutdown.cancelled() => None,
+    }
+}
+
+/// Wait for the identical request in flight, telling the user if it takes longer than the progress delay,
+/// returns the file IDs it sent, None if it failed
+pub async fn wait_in_flight(ctx: &Context, msg: &Message, mut waiter: FlightWaiter) -> anyhow::Result<Option<Vec<String>>> {
+    release_worker();
+    let delay = Duration::from_secs(ctx.config.progress.delay);
+    if let Ok(result) = tokio::time::timeout(delay, waiter.wait()).await {
+        return Ok(result);
+    }
+    bot_actions::send_reply_message(
+        &ctx.bot, msg.chat.id, tr(message_locale(ctx, msg), "jobs.joined"),
+        msg.message_id, None
+    ).await?;
+    Ok(waiter.wait().await)
+}
+
+pub fn jobs_handler(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
+    let fut = jobs_handler_impl(ctx, msg);
+    Box::pin(fut)
+}
+
+async fn jobs_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerResult {
+    match get_command(&msg).as_deref() {
+        Some("jobs") => list_jobs(ctx, msg).await?,
+        Some("cancel") => cancel_jobs(ctx, msg).await?,
+        _ => return Ok(std::ops::ControlFlow::Continue(()))
+    }
+    Ok(std::ops::ControlFlow::Break(()))
+}
+
+/// `/cancel` cancels all jobs of the sender in the chat, `/cancel <id>` cancels the job with the ID
+async fn cancel_jobs(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
+    let locale = message_locale(&ctx, &msg);
+    let sender_id = get_sender_id(&msg);
+    let arg = msg.text.as_deref().and_then(|text| text.split_whitespace().nth(1));
+
+    let count = match arg {
+        Some(arg) => {
+            let Ok(id) = arg.trim_start_matches('#').parse::<u64>() else {
+                bot_actions::send_reply_message(&ctx.bot, msg.chat.id, tr(locale, "jobs.cancel_usage"), msg.message_id, None).await?;
+                return Ok(());
+            };
+            ctx.jobs.cancel(|job| job.id == id && can_cancel(&ctx, job, msg.chat.id, sender_id))
+        }
+        None => ctx.jobs.cancel(|job| sender_id.is_some() && job.user_id == sender_id && job.chat_id == msg.chat.id)
+    };
+
+    log::info!(target: "jobs", "{} Cancelled {count} jobs", LogOp(&msg));
+    // The cancelled jobs reply by themselves
+    if count == 0 {
+        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, tr(locale, "jobs.nothing_to_cancel"), msg.message_id, None).await?;
+    }
+    Ok(())
+}
+
+async fn list_jobs(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
+    let locale = message_locale(&ctx, &msg);
+
+    if !ctx.config.access.is_admin(get_sender_id(&msg)) {
+        bot_actions::send_reply_message(
+            &ctx.bot, msg.chat.id, tr(locale, "jobs.bot_admin_only"),
+            msg.message_id, None
+        ).await?;
+        return Ok(());
+    }
+
+    let (running, queued) = ctx.jobs.snapshot();
+    let mut text = jobs_text(&running, &queued, locale);
+
+    let usage = temp_usage(&ctx.temp_root_path).await;
+    let size = match ctx.config.temp.quota {
+        Some(quota) => format!("{} / {}", format_bytes(usage.bytes), format_bytes(quota * 1_000_000)),
+        None => format_bytes(usage.bytes),
+    };
+    text.push_str("\n\n");
+    text.push_str(&tr_args(locale, "jobs.temp_usage", &[&size, &usage.entries]));
+
+    bot_actions::send_message(&ctx.bot, msg.chat.id, text).await?;
+
+    Ok(())
+}
+
+pub fn jobs_callback_handler(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+    let fut = jobs_callback_handler_impl(ctx, query, data);
+    Box::pin(fut)
+}
+
+async fn jobs_callback_handler_impl(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> CallbackHandlerResult {
+    let locale = callback_locale(&ctx, &query);
+    let Some(msg) = callback_message(&query) else {
+        return Ok(CallbackAnswer::Alert(tr(locale, "common.message_too_old").to_string()));
+    };
+
+    match data.action.as_str() {
+        "cancel" => {
+            let Some(id) = data.arg(0).and_then(|s| s.parse::<u64>().ok()) else {
+                return Ok(CallbackAnswer::Silent);
+            };
+            let Some(job) = ctx.jobs.get(id) else {
+                return Ok(CallbackAnswer::Notice(tr(locale, "jobs.already_finished").to_string()));
+            };
+            if !can_cancel(&ctx, &job, msg.chat.id, Some(query.from.id as i64)) {
+                return Ok(CallbackAnswer::Alert(tr(locale, "jobs.not_owner").to_string()));
+            }
+            ctx.jobs.cancel(|job| job.id == id);
+            log::info!(target: "jobs", "Job {id} cancelled by {} from button", query.from.id);
+            Ok(CallbackAnswer::Notice(tr(locale, "jobs.cancelling").to_string()))
+        }
+        _ => Ok(CallbackAnswer::Silent)
+    }
+}
+
+fn jobs_text(running: &[JobInfo], queued: &[JobInfo], locale: Locale) -> String {
+    if running.is_empty() && queued.is_empty() {
+        return tr(locale, "jobs.none").to_string();
+    }
+    let mut text = tr_args(locale, "jobs.running", &[&running.len()]);
+    for job in running {
+        text.push_str(&job_line(job));
+    }
+    text.push('\n');
+    text.push_str(&tr_args(locale, "jobs.waiting", &[&queued.len()]));
+    for job in queued {
+        text.push_str(&job_line(job));
+    }
+    text
+}
+
+fn job_line(job: &JobInfo) -> String {
+    let user = match job.user_id {
+        Some(user_id) => user_id.to_string(),
+        None => "-".to_string()
+    };
+    format!(
+        "\n#{} [{}] {} (chat: {}, user: {}, {}s)",
+        job.id, job.kind.as_str(), job.description, job.chat_id, user, job.since.elapsed().as_secs()
+    )
+}
@@ -0,0 +1,300 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::JobsConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Pixiv,
+    Ugoira,
+    Kemono,
+    StickerSet,
+}
+
+impl JobKind {
+    /// The key in `JobsConfig.kind_limits`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Pixiv => "pixiv",
+            JobKind::Ugoira => "ugoira",
+            JobKind::Kemono => "kemono",
+            JobKind::StickerSet => "sticker_set",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JobInfo {
+    pub id: u64,
+    pub kind: JobKind,
+    pub chat_id: i64,
+    pub user_id: Option<i64>,
+    /// Shown in /jobs, e.g. the pixiv ID
+    pub description: String,
+    /// When the job is queued, or started if running
+    pub since: Instant,
+    /// Cancelled by /cancel or the cancel button
+    pub cancel: CancellationToken,
+}
+
+#[derive(Debug)]
+struct QueuedJob {
+    info: JobInfo,
+    start: oneshot::Sender<()>,
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+    config: JobsConfig,
+    running: Vec<JobInfo>,
+    queued: VecDeque<QueuedJob>,
+}
+
+impl SchedulerState {
+    fn can_start(&self, job: &JobInfo) -> bool {
+        if self.running.len() >= self.config.max_running {
+            return false;
+        }
+        let user_running = self.running.iter()
+            .filter(|running| job.user_id.is_some() && running.user_id == job.user_id)
+            .count();
+        if user_running >= self.config.max_per_user {
+            return false;
+        }
+        match self.config.kind_limits.get(job.kind.as_str()) {
+            Some(limit) => self.running.iter().filter(|running| running.kind == job.kind).count() < *limit,
+            None => true
+        }
+    }
+
+    /// Start queued jobs in order, a job blocked by its user or kind limit does not block the ones behind it
+    fn promote(&mut self) {
+        let mut index = 0;
+        while index < self.queued.len() {
+            if !self.can_start(&self.queued[index].info) {
+                index += 1;
+                continue;
+            }
+            let Some(QueuedJob { mut info, start }) = self.queued.remove(index) else {
+                break;
+            };
+            info.since = Instant::now();
+            // The receiver is never dropped before the guard, which removes the job from the queue
+            let _ = start.send(());
+            self.running.push(info);
+        }
+    }
+}
+
+/// Queue of download jobs, limits how many jobs run at the same time
+#[derive(Debug)]
+pub struct JobScheduler {
+    state: Arc<Mutex<SchedulerState>>,
+    next_id: AtomicU64,
+}
+
+/// A job submitted to the scheduler, the job is removed when dropped
+#[derive(Debug)]
+pub struct JobGuard {
+    id: u64,
+    cancel: CancellationToken,
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl JobGuard {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Token for workers and subprocesses of the job
+    pub fn token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Checked between the steps of the job, e.g. `job.check()?`
+    pub fn check(&self) -> Result<(), JobCancelled> {
+        match self.cancel.is_cancelled() {
+            true => Err(JobCancelled),
+            false => Ok(())
+        }
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.running.retain(|job| job.id != self.id);
+        state.queued.retain(|job| job.info.id != self.id);
+        state.promote();
+    }
+}
+
+/// Raised when the job is cancelled, reported to the user as a notice instead of a failure
+#[derive(Debug)]
+pub struct JobCancelled;
+
+impl std::fmt::Display for JobCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Job cancelled")
+    }
+}
+
+impl std::error::Error for JobCancelled {}
+
+/// Returned by [`JobScheduler::submit`] if the job has to wait
+#[derive(Debug)]
+pub struct QueuePosition {
+    /// 1-based position in the queue
+    pub position: usize,
+    /// Resolved when the job is started
+    pub start: oneshot::Receiver<()>,
+}
+
+impl JobScheduler {
+    pub fn new(config: JobsConfig) -> JobScheduler {
+        JobScheduler {
+            state: Arc::new(Mutex::new(SchedulerState {
+                config,
+                running: Vec::new(),
+                queued: VecDeque::new(),
+            })),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Start the job if the limits allow, otherwise put it at the end of the queue
+    pub fn submit(&self, kind: JobKind, chat_id: i64, user_id: Option<i64>, description: String) -> (JobGuard, Option<QueuePosition>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancel = CancellationToken::new();
+        let info = JobInfo { id, kind, chat_id, user_id, description, since: Instant::now(), cancel: cancel.clone() };
+        let guard = JobGuard { id, cancel, state: self.state.clone() };
+
+        let mut state = self.state.lock().unwrap();
+        if state.queued.is_empty() && state.can_start(&info) {
+            state.running.push(info);
+            return (guard, None);
+        }
+        let (sender, receiver) = oneshot::channel();
+        state.queued.push_back(QueuedJob { info, start: sender });
+        state.promote();
+        let queue_position = state.queued.iter()
+            .position(|job| job.info.id == id)
+            .map(|index| QueuePosition { position: index + 1, start: receiver });
+        (guard, queue_position)
+    }
+
+    pub fn get(&self, id: u64) -> Option<JobInfo> {
+        let state = self.state.lock().unwrap();
+        state.running.iter()
+            .chain(state.queued.iter().map(|job| &job.info))
+            .find(|job| job.id == id)
+            .cloned()
+    }
+
+    /// Cancel the running and queued jobs matching the filter, returns how many are cancelled
+    pub fn cancel(&self, filter: impl Fn(&JobInfo) -> bool) -> usize {
+        let state = self.state.lock().unwrap();
+        let mut count = 0;
+        for job in state.running.iter().chain(state.queued.iter().map(|job| &job.info)) {
+            if !job.cancel.is_cancelled() && filter(job) {
+                job.cancel.cancel();
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Running and queued jobs, in order
+    pub fn snapshot(&self) -> (Vec<JobInfo>, Vec<JobInfo>) {
+        let state = self.state.lock().unwrap();
+        let running = state.running.clone();
+        let queued = state.queued.iter().map(|job| job.info.clone()).collect();
+        (running, queued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn scheduler(max_running: usize, max_per_user: usize, kind_limits: &[(&str, usize)]) -> JobScheduler {
+        JobScheduler::new(JobsConfig {
+            max_running,
+            max_per_user,
+            kind_limits: kind_limits.iter().map(|(kind, limit)| (kind.to_string(), *limit)).collect::<HashMap<_, _>>(),
+        })
+    }
+
+    fn submit(jobs: &JobScheduler, kind: JobKind, user_id: i64) -> (JobGuard, Option<QueuePosition>) {
+        jobs.submit(kind, user_id, Some(user_id), String::new())
+    }
+
+    #[test]
+    fn global_limit_queues_in_order() {
+        let jobs = scheduler(2, 10, &[]);
+        let (first, position) = submit(&jobs, JobKind::Pixiv, 1);
+        assert!(position.is_none());
+        let (_second, position) = submit(&jobs, JobKind::Pixiv, 2);
+        assert!(position.is_none());
+        let (third, position) = submit(&jobs, JobKind::Pixiv, 3);
+        let mut third_start = position.unwrap();
+        assert_eq!(third_start.position, 1);
+        let (_fourth, position) = submit(&jobs, JobKind::Pixiv, 4);
+        assert_eq!(position.unwrap().position, 2);
+
+        drop(first);
+        assert!(third_start.start.try_recv().is_ok());
+        let (running, queued) = jobs.snapshot();
+        assert!(running.iter().any(|job| job.id == third.id()));
+        assert_eq!(queued.len(), 1);
+    }
+
+    #[test]
+    fn blocked_user_does_not_block_others() {
+        let jobs = scheduler(3, 1, &[]);
+        let (_first, _) = submit(&jobs, JobKind::Pixiv, 1);
+        let (_second, position) = submit(&jobs, JobKind::Kemono, 1);
+        assert_eq!(position.unwrap().position, 1);
+        // Started although a job is queued before it
+        let (other, position) = submit(&jobs, JobKind::Pixiv, 2);
+        assert!(position.is_none());
+        assert!(jobs.snapshot().0.iter().any(|job| job.id == other.id()));
+    }
+
+    #[test]
+    fn kind_limit() {
+        let jobs = scheduler(3, 3, &[("ugoira", 1)]);
+        let (first, _) = submit(&jobs, JobKind::Ugoira, 1);
+        let (_second, position) = submit(&jobs, JobKind::Ugoira, 2);
+        let mut position = position.unwrap();
+        let (_pixiv, pixiv_position) = submit(&jobs, JobKind::Pixiv, 3);
+        assert!(pixiv_position.is_none());
+        assert!(position.start.try_recv().is_err());
+        drop(first);
+        assert!(position.start.try_recv().is_ok());
+    }
+
+    #[test]
+    fn cancel_and_drop() {
+        let jobs = scheduler(1, 1, &[]);
+        let (running, _) = submit(&jobs, JobKind::Pixiv, 1);
+        let (queued, _) = submit(&jobs, JobKind::Pixiv, 2);
+        assert_eq!(jobs.cancel(|job| job.user_id == Some(2)), 1);
+        assert!(queued.check().is_err());
+        assert!(running.check().is_ok());
+        // Already cancelled jobs are not counted again
+        assert_eq!(jobs.cancel(|_| true), 1);
+
+        drop(queued);
+        drop(running);
+        let (running, queued) = jobs.snapshot();
+        assert!(running.is_empty() && queued.is_empty());
+    }
+}
@@ -7,6 +7,16 @@ pub struct KemonoConfig {
     pub enable_kemono_link_detection: bool,
     #[serde(default = "default_enable_fanbox_link_detection")]
     pub enable_fanbox_link_detection: bool,
+    /// Posts larger than this in MB are not downloaded, the archive is split into parts if it is too large to upload.
+    /// 500 MB if absent, or 2000 MB with a local Bot API server
+    pub max_total_size: Option<u64>,
+}
+
+impl KemonoConfig {
+    /// In MB
+    pub fn max_total_size(&self, local_mode: bool) -> u64 {
+        self.max_total_size.unwrap_or(if local_mode { 2000 } else { 500 })
+    }
 }
 
 fn default_enable_kemono_link_detection() -> bool { false }
@@ -4,36 +4,64 @@ mod post;
 mod creator;
 mod telegraph;
 
-use std::collections::VecDeque;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
 
-use frankenstein::AsyncTelegramApi;
-use frankenstein::methods::SendDocumentParams;
 use frankenstein::types::Message;
 use futures::future::BoxFuture;
-use reqwest::{Client, StatusCode};
+use reqwest::StatusCode;
 use serde::Deserialize;
-use tokio::sync::Mutex;
-use zip::CompressionMethod;
-use zip::write::SimpleFileOptions;
+use sha2::{Digest, Sha256};
+use tokio::sync::watch;
 
-use crate::handler::HandlerResult;
-use crate::helper::download::download_url_to_path;
+use crate::handler::{Handler, HandlerResult, UpdateKind};
+use crate::helper::download::{DownloadProgress, DownloadTask};
+use crate::helper::progress::ProgressReporter;
 use crate::helper::log::LogOp;
+use crate::helper::make_archive::{ArchiveEntry, make_archives, send_archives};
 use crate::helper::message_utils::get_command;
-use crate::helper::{bot_actions, param_builders};
+use crate::helper::bot_actions;
+use crate::helper::upload_cache::{is_stale_file_id, message_file_id};
 use crate::context::Context;
+use crate::i18n::{message_locale, tr, tr_args};
+use crate::jobs::{start_job, wait_in_flight};
+use crate::jobs::in_flight::Joined;
+use crate::jobs::janitor::{job_temp_dir, temp_has_room};
+use crate::jobs::scheduler::JobKind;
 use crate::kemono::creator::CreatorProfile;
 use crate::kemono::parser::{FanboxRequest, KemonoCommandParam, KemonoRequest, parse_fanbox_link, parse_kemono_command, parse_kemono_link};
-use crate::kemono::post::{KemonoFile, KemonoPostResponse};
+use crate::kemono::post::{KemonoFile, KemonoPost, KemonoPostResponse};
 use crate::kemono::telegraph::send_telegraph_preview;
 
 pub const COMMAND_LIST: &[(&'static str, &'static str)] = &[
-    ("kemono", "預覽或下載 kemono.cr 上的歸檔"),
+    ("kemono", "cmd.kemono"),
 ];
 
+/// Rough size of a post file, to check the temp quota before the file metadata is requested
+const ESTIMATED_FILE_SIZE: u64 = 10_000_000;
+
+pub struct KemonoHandler;
+
+impl Handler for KemonoHandler {
+    fn name(&self) -> &'static str { "kemono" }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] { COMMAND_LIST }
+
+    fn help(&self) -> Option<&'static str> { Some("help.kemono") }
+
+    fn update_kinds(&self) -> &'static [UpdateKind] {
+        &[UpdateKind::Message, UpdateKind::EditedMessage, UpdateKind::ChannelPost, UpdateKind::EditedChannelPost]
+    }
+
+    fn handle_message(&self, ctx: Arc<Context>, msg: Arc<Message>, kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        match kind {
+            UpdateKind::Message => kemono_handler(ctx, msg),
+            // Only link detection for edited messages and channel posts
+            _ => kemono_link_handler(ctx, msg)
+        }
+    }
+}
+
 pub fn kemono_handler(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
     let fut = kemono_handler_impl(ctx, msg);
     return Box::pin(fut);
@@ -58,7 +86,7 @@ async fn kemono_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerRes
                         fanbox_download_handler(ctx, msg, req).await?;
                     }
                     parser::KemonoCommandParseResult::InvalidLink => {
-                        bot_actions::send_message(&ctx.bot, msg.chat.id, "似乎沒有識別到正確的 kemono.cr 鏈接呢……").await?;
+                        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "kemono.invalid_link")).await?;
                     }
                     parser::KemonoCommandParseResult::ShowHelp => {
                         send_kemono_command_help(ctx, msg).await?;
@@ -69,10 +97,32 @@ async fn kemono_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerRes
             _ => return Ok(std::ops::ControlFlow::Continue(()))
         }
     }
-    
+
+    kemono_link_handler_impl(ctx, msg).await
+}
+
+/// Link detection only, also used for edited messages and channel posts
+pub fn kemono_link_handler(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
+    let fut = kemono_link_handler_impl(ctx, msg);
+    Box::pin(fut)
+}
+
+async fn kemono_link_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerResult {
+
+    let Some(text) = msg.text.as_ref() else {
+        return Ok(std::ops::ControlFlow::Continue(()))
+    };
+
+    let kemono_config = ctx.settings.kemono_config(msg.chat.id, &ctx.config.kemono);
+
     // Link detection for kemono
-    if ctx.config.kemono.enable_kemono_link_detection {
+    if kemono_config.enable_kemono_link_detection {
         if let Some((service, user_id, post_id)) = parse_kemono_link(text) {
+            // Edits of the message keep the link, it is only handled once
+            let link = format!("kemono:{service}/{user_id}/{post_id}");
+            if !ctx.handled_links.insert(msg.chat.id, msg.message_id, link) {
+                return Ok(std::ops::ControlFlow::Continue(()));
+            }
             let request = KemonoRequest {
                 service,
                 user_id,
@@ -84,8 +134,12 @@ async fn kemono_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerRes
         }
     }
     // Link detection for fanbox
-    if ctx.config.kemono.enable_fanbox_link_detection {
+    if kemono_config.enable_fanbox_link_detection {
         if let Some((username, post_id)) = parse_fanbox_link(text) {
+            let link = format!("fanbox:{username}/{}", post_id.as_deref().unwrap_or_default());
+            if !ctx.handled_links.insert(msg.chat.id, msg.message_id, link) {
+                return Ok(std::ops::ControlFlow::Continue(()));
+            }
             let request = FanboxRequest {
                 username,
                 post_id,
@@ -100,10 +154,7 @@ async fn kemono_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerRes
 }
 
 async fn send_kemono_command_help(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
-    const HELP_MSG : &'static str = 
-        "/kemono 指令幫助\n\
-        ";
-    bot_actions::send_message(&ctx.bot, msg.chat.id, HELP_MSG).await?;
+    bot_actions::send_message(&ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "kemono.help")).await?;
     Ok(())
 }
 
@@ -140,7 +191,7 @@ async fn fanbox_download_handler(
         LogOp(&msg), api_url
     );
 
-    let fanbox_req = ctx.pixiv.client.get(api_url)
+    let fanbox_req = ctx.http.fanbox.get(api_url)
         .header("Origin", "https://www.fanbox.cc");
 
     let response: FanboxCreatorGetResponse = fanbox_req.send().await?.json().await?;
@@ -161,7 +212,7 @@ async fn fanbox_download_handler(
         );
 
         bot_actions::send_reply_message(
-            &ctx.bot, msg.chat.id, "似乎沒找到這個創作者呢…… (也有可能是查詢 FANBOX 創作者失敗了)",
+            &ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "kemono.creator_not_found"),
             msg.message_id, None
         ).await?;
 
@@ -175,7 +226,7 @@ async fn fanbox_download_handler(
     let Some(post_id) = request.post_id else {
         bot_actions::send_reply_message(
             &ctx.bot, msg.chat.id, 
-            format!("該作者可能的 kemono.cr 主頁： {}", format!("https://kemono.cr/fanbox/user/{user_id}")),
+            tr_args(message_locale(&ctx, &msg), "kemono.creator_home", &[&format!("https://kemono.cr/fanbox/user/{user_id}")]),
             msg.message_id, None
         ).await?;
         return Ok(())
@@ -197,14 +248,9 @@ async fn kemono_download_handler(
     msg: Arc<Message>,
     request: KemonoRequest,
 ) -> anyhow::Result<()> {
+    let locale = message_locale(&ctx, &msg);
 
-    let client_builder = match ctx.config.kemono.client_user_agent.as_ref() {
-        Some(ua) => Client::builder().user_agent(ua),
-        None => Client::builder()
-    };
-    let client = client_builder
-        .timeout(Duration::from_mins(10))
-        .build()?;
+    let client = &ctx.http.kemono;
 
     let url = format!(
         "https://kemono.cr/api/v1/{}/user/{}/post/{}", 
@@ -227,19 +273,20 @@ async fn kemono_download_handler(
             LogOp(&msg)
         );
         bot_actions::send_reply_message(
-            &ctx.bot, msg.chat.id, "沒有找到這個 kemono.cr 頁面呢……",
+            &ctx.bot, msg.chat.id, tr(locale, "kemono.page_not_found"),
             msg.message_id, None
         ).await?;
         return Ok(());
     }
 
-    let response: KemonoPostResponse = response.json().await?;
+    let response: KemonoPostResponse = response.error_for_status()?.json().await?;
     let post = response.post;
 
     let url = format!("https://kemono.cr/api/v1/{}/user/{}/profile", request.service, request.user_id);
     let creator: CreatorProfile = client.get(url)
         .header("Accept", "text/css")
         .send().await?
+        .error_for_status()?
         .json().await?;
 
     // Send telegraph first
@@ -263,36 +310,60 @@ async fn kemono_download_handler(
         return Ok(())
     }
 
+    // Posts sent before are resent by the file IDs without waiting for a slot,
+    // identical requests in flight are waited for and resend the file IDs of the first one
+    let upload_key = kemono_upload_key(&post);
+    let caption = format!("<b>{}</b>", post.title);
+    let flight = loop {
+        if let Some(file_ids) = ctx.upload_cache.get_all(std::slice::from_ref(&upload_key)) {
+            log::info!(
+                target: "kemono_download",
+                "{} Sending {} cached archive parts",
+                LogOp(&msg), file_ids.len()
+            );
+            if kemono_send_file_ids(&ctx, &msg, &file_ids, &caption).await? {
+                return Ok(());
+            }
+            ctx.upload_cache.remove(std::slice::from_ref(&upload_key));
+        }
+        let waiter = match ctx.in_flight.join(&upload_key) {
+            Joined::First(flight) => break flight,
+            Joined::Waiter(waiter) => waiter,
+        };
+        log::info!(
+            target: "kemono_download",
+            "{} Waiting for the identical request in flight",
+            LogOp(&msg)
+        );
+        if let Some(file_ids) = wait_in_flight(&ctx, &msg, waiter).await?
+            && kemono_send_file_ids(&ctx, &msg, &file_ids, &caption).await? {
+            return Ok(());
+        }
+    };
+
+    let description = format!("{}/{}/{}", request.service, request.user_id, request.post_id);
+    // The real size is known from the file metadata after the job starts and checked again
+    let file_count = post.file.iter().count() + post.attachments.len();
+    let expected_size = file_count as u64 * ESTIMATED_FILE_SIZE;
+    let Some(job) = start_job(&ctx, &msg, JobKind::Kemono, description, expected_size).await? else {
+        return Ok(());
+    };
+
     // Create tempfile start download all files
-    let temp_dir = tempfile::tempdir_in(&ctx.temp_root_path)?;
+    let temp_dir = job_temp_dir(&ctx, &job)?;
 
-    // Construct tasks and worker queue
-    let mut queue = VecDeque::<DownloadTask>::new();
+    // Construct tasks, the file names are prefixed to keep the order in the archive
+    let mut files = Vec::<(String, KemonoFile)>::new();
     if let Some(banner) = post.file {
-        queue.push_back(DownloadTask::from_kemono_file(
-            banner, "banner_".to_string(), temp_dir.path()
-        ));
+        files.push(("banner_".to_string(), banner));
     }
     for (idx, file) in post.attachments.iter().enumerate() {
-        queue.push_back(DownloadTask::from_kemono_file(
-            file.clone(), 
-            format!("{:02}_", idx + 1),
-            temp_dir.path()
-        ));
+        files.push((format!("{:02}_", idx + 1), file.clone()));
     }
-
-    let task_count = queue.len();
-
-    // Create metadata checking task queue
-    let metadata_queue: VecDeque<String> = queue.iter().map(
-        |task| task.url.clone()
-    ).collect();
-    let metadata_tasks = Arc::new(Mutex::new(metadata_queue));
-
-    // Create list of files for later scanning
-    let scan_file_list: Vec<PathBuf> = queue.iter().map(
-        |task| task.save_path.clone()
-    ).collect();
+    let tasks: Vec<DownloadTask> = files.into_iter()
+        .map(|(prefix, file)| kemono_download_task(&file, &prefix, temp_dir.path()))
+        .collect();
+    let task_count = tasks.len();
 
     /* Get file size */
     log::info!(
@@ -301,293 +372,134 @@ async fn kemono_download_handler(
         LogOp(&msg)
     );
 
-    let total_size = Arc::new(Mutex::new(0 as u64));
-
-    let mut meta_join_handle_list = vec![];
-    const META_WORKER_COUNT: u64 = 8;
-    for worker_id in 0..u64::min(META_WORKER_COUNT, task_count as u64) {
-        let client_cloned = client.clone();
-        let tasks_cloned = metadata_tasks.clone();
-        let total_size_cloned = total_size.clone();
-        meta_join_handle_list.push(tokio::task::spawn(async move {
-            kemono_metadata_worker(
-                worker_id,
-                client_cloned,
-                tasks_cloned,
-                total_size_cloned,
-            ).await;
-        }));
-    }
+    let mut reporter = ProgressReporter::new(ctx.clone(), &msg, &job, tr(locale, "kemono.download_title"));
+    reporter.status(tr(locale, "kemono.requesting_metadata")).await;
 
-    let progress_message = bot_actions::send_reply_message(
-        &ctx.bot, msg.chat.id, "正在請求文件元數據……", msg.message_id, None
-    ).await?;
+    const META_WORKER_COUNT: usize = 8;
+    let total_size = ctx.downloader.total_size(client, &tasks, META_WORKER_COUNT, job.token()).await;
+    job.check()?;
 
-    for handle in meta_join_handle_list {
-        handle.await?
+    // Check file size, archives larger than the upload limit are split later
+    let max_total_size = ctx.config.kemono.max_total_size(ctx.config.telegram.local_mode);
+    if total_size > max_total_size * 1_000_000 {
+        reporter.finish().await;
+        let total_size_mib = total_size as f64 / (1024.0 * 1024.0);
+        bot_actions::send_reply_message(
+            &ctx.bot, msg.chat.id,
+            tr_args(locale, "kemono.size_exceeded", &[&max_total_size, &format!("{total_size_mib:.1}")]),
+            msg.message_id, None
+        ).await?;
+        return Ok(())
     }
 
-    let total_size = *total_size.lock().await;
-    let total_size_mib = total_size as f64 / (1024.0 * 1024.0);
-
-    // Check file size
-    // Use 
-    if total_size > 49_000_000 {
-        bot_actions::edit_message_text(
-            &ctx.bot, msg.chat.id, progress_message.message_id,
-            format!("文件總大小超過 50 MB 了呢…… ({:.1} MiB)\n請自行前往 kemono.cr 下載。", total_size_mib)
-        ).await?;
+    // Other jobs may fill the temp directory meanwhile, the quota is a soft limit
+    if !temp_has_room(&ctx, total_size).await {
+        reporter.finish().await;
+        log::warn!(
+            target: "kemono_download",
+            "{} Refused, {} bytes do not fit in the temp quota",
+            LogOp(&msg), total_size
+        );
+        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, tr(locale, "jobs.disk_full"), msg.message_id, None).await?;
         return Ok(())
     }
 
     /* Real download */
     log::info!(
         target: "kemono_download",
-        "{} Downloading files ({} bytes)",
-        LogOp(&msg), total_size
-    );
-
-    let download_tasks = Arc::new(Mutex::new(queue));
-
-    let completed = Arc::new(Mutex::new(Vec::<DownloadItem>::new()));
-
-    let mut join_handle_list = vec![];
-    const WORKER_COUNT: u64 = 4;
-    for download_worker_id in 0..u64::min(WORKER_COUNT, task_count as u64) {
-        let client_cloned = client.clone();
-        let tasks_cloned = download_tasks.clone();
-        let completed_cloned = completed.clone();
-        join_handle_list.push(tokio::task::spawn(async move {
-            kemono_download_worker(
-                download_worker_id,
-                client_cloned,
-                tasks_cloned,
-                completed_cloned,
-            ).await;
-        }));
-    }
-
-    // Wait until all done
-    let mut progress_text = format!(
-        "開始從 kemono.cr 下載文件…… ({:.1} MiB, 共 {} 個文件）", 
-        total_size_mib, task_count
+        "{} Downloading {} files ({} bytes)",
+        LogOp(&msg), task_count, total_size
     );
-    
-    bot_actions::edit_message_text(
-        &ctx.bot, msg.chat.id, progress_message.message_id, &progress_text
-    ).await?;
-
-    loop {
-        if join_handle_list.iter().map(|h| h.is_finished()).all(|s| s == true) {
-            break;
-        }
-        tokio::time::sleep(Duration::from_secs(1)).await;
-
-        let count: u64 = completed.lock().await.len() as u64;
-
-        let mut current_size: u64 = 0;
-        for path in &scan_file_list {
-            if let Ok(meta) = tokio::fs::metadata(path).await {
-                current_size += meta.len()
-            }
-        }
 
-        let current_size_mib = current_size as f64 / (1024.0 * 1024.0);
-    
-        log::info!(
-            target: "kemono_download",
-            "{} Downloading ({}/{}) attachments ({:.1} MiB / {:.1} MiB)", 
-            LogOp(&msg), count, task_count, current_size_mib, total_size_mib
-        );
-    
-        let new_text = format!(
-            "正在從 kemono.cr 下載文件 ({:.1} MiB / {:.1} MiB, {}/{} 個文件）", 
-            current_size_mib, total_size_mib, count, task_count
-        );
-
-        if new_text != progress_text {
-            progress_text = new_text;
-            bot_actions::edit_message_text(&ctx.bot, msg.chat.id, progress_message.message_id, &progress_text).await?;
-        }
-    }
-    bot_actions::delete_message(&ctx.bot, progress_message.chat.id, progress_message.message_id).await?;
-
-    let files = completed.lock().await.clone();
-
-    if files.len() != task_count as usize {
-        let fail_count = task_count as usize - files.len();
+    const WORKER_COUNT: usize = 4;
+    let (progress_sender, mut progress) = watch::channel(DownloadProgress::default());
+    let on_progress = |progress| { progress_sender.send_replace(progress); };
+    let download = ctx.downloader.download_all(client, &tasks, WORKER_COUNT, job.token(), &on_progress);
+    reporter.set_total_bytes(total_size);
+    let report = reporter.track(download, &mut progress).await;
+    reporter.finish().await;
+    job.check()?;
+
+    report.log_failures("kemono_download");
+    if !report.failed.is_empty() {
+        let fail_count = report.failed.len();
         log::warn!(
             target: "kemono_download",
             "{} Incomplete attachment download: {}/{} downloaded, {} failed",
-            LogOp(&msg), files.len(), task_count, fail_count
+            LogOp(&msg), report.completed.len(), task_count, fail_count
         );
         bot_actions::send_reply_message(
-            &ctx.bot, msg.chat.id, format!("文件下載完成了，但似乎有 {} 個文件下載失敗了呢……", fail_count), msg.message_id, None
+            &ctx.bot, msg.chat.id, tr_args(locale, "kemono.download_partial", &[&fail_count]), msg.message_id, None
         ).await?;
     }
-
-    let archive_file_name = format!("{}_{}_{}.zip", post.service, post.user, post.id);
-    let archive_path = temp_dir.path().join(&archive_file_name);
-    let archive_path_clone = archive_path.clone();
-    let tmp_path = temp_dir.path().to_path_buf();
-    // Archive file
-    // Blocking zip and write operation
-    let archiving_task = tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
-        let archive_file = std::fs::File::create(archive_path_clone)?;
-        let mut archive = zip::ZipWriter::new(archive_file);
-        let options = SimpleFileOptions::default()
-            .compression_method(CompressionMethod::Stored)
-            .unix_permissions(0o755);
-        for download_file in files {
-            let path = tmp_path.join(&download_file.save_name);
-            let mut file = match std::fs::File::open(&path) {
-                Ok(f) => f,
-                Err(e) => {
-                    log::warn!(
-                        target: "kemono_download archiver",
-                        "Failed to open downloaded file {} for archiving: {}", 
-                        path.to_string_lossy(), e
-                    );
-                    continue;
-                }
-            };
-
-            archive.start_file(download_file.save_name, options)?;
-            std::io::copy(&mut file, &mut archive)?;
-        }
-        archive.finish()?;
-        Ok(())
-    });
-
-    // Notice, JoinError is passed up by "?"
-    if let Err(e) = archiving_task.await? {
-        log::warn!(
-            target: "kemono_download",
-            "{} Failed to archive file {}: {}", 
-            LogOp(&msg), archive_file_name, e
-        );
-        return Ok(())
+    log::info!(
+        target: "kemono_download",
+        "{} Downloaded {} bytes",
+        LogOp(&msg), report.completed.iter().map(|download| download.size).sum::<u64>()
+    );
+    let entries = report.completed.iter()
+        .map(|download| ArchiveEntry::new(tasks[download.index].label(), &download.save_path))
+        .collect();
+    let archive_stem = format!("{}_{}_{}", post.service, post.user, post.id);
+    let parts = make_archives(entries, temp_dir.path(), &archive_stem, ctx.config.archive.part_size_bytes(&ctx.config.telegram)).await?;
+    if !parts.split.is_empty() {
+        bot_actions::send_reply_message(
+            &ctx.bot, msg.chat.id, tr_args(locale, "archive.split", &[&parts.split.len()]), msg.message_id, None
+        ).await?;
     }
 
     log::info!(
         target: "kemono_download",
-        "{} Upolading archive",
-        LogOp(&msg),
+        "{} Upolading archive ({} parts)",
+        LogOp(&msg), parts.paths.len()
     );
+    let messages = send_archives(&ctx, &msg, &parts.paths, Some(&caption), Some(&job)).await?;
 
-    bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::UploadDocument).await?;
-
-    let send_document_param = SendDocumentParams::builder()
-        .chat_id(msg.chat.id)
-        .document(archive_path)
-        .parse_mode(frankenstein::ParseMode::Html)
-        .caption(format!("<b>{}</b>", post.title))
-        .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
-        .build();
-    ctx.bot.send_document(&send_document_param).await?;
+    // Archives missing some files are not cached
+    if report.failed.is_empty() && let Some(file_ids) = messages.iter().map(message_file_id).collect::<Option<Vec<String>>>() {
+        ctx.upload_cache.insert(upload_key, file_ids.clone());
+        ctx.upload_cache.save().await;
+        flight.finish(file_ids);
+    }
 
     Ok(())
 }
 
-#[derive(Debug, Clone)]
-struct DownloadTask {
-    pub url: String,
-    pub save_name: String,
-    pub save_path: PathBuf,
-}
-
-impl DownloadTask {
-    pub fn from_kemono_file<P: AsRef<Path>>(file: KemonoFile, prefix: String, root_dir: P) -> DownloadTask {
-        const KEMONO_BASE_URL: &'static str = "https://kemono.cr";
-        let url = format!("{KEMONO_BASE_URL}{}", file.path);
-        let save_name = format!("{}{}", prefix, file.name);
-        DownloadTask {
-            url,
-            save_path: root_dir.as_ref().join(&save_name),
-            save_name,
+/// Send the archive parts by the file IDs sent before, returns false if Telegram rejects the file IDs
+async fn kemono_send_file_ids(ctx: &Context, msg: &Message, file_ids: &[String], caption: &str) -> anyhow::Result<bool> {
+    match send_archives(ctx, msg, file_ids, Some(caption), None).await {
+        Ok(_) => Ok(true),
+        Err(e) if is_stale_file_id(&e) => {
+            log::warn!(
+                target: "kemono_download",
+                "{} Sent archive is rejected, downloading again: {}",
+                LogOp(msg), e
+            );
+            Ok(false)
         }
+        Err(e) => Err(e),
     }
 }
 
-#[derive(Debug, Clone)]
-struct DownloadItem {
-    pub save_name: String,
-}
-
-async fn kemono_metadata_worker(
-    worker_id: u64,
-    client: Client,
-    queue: Arc<Mutex<VecDeque<String>>>,
-    downloading_size: Arc<Mutex<u64>>
-) {
-    loop {
-        let task = {
-            let mut guard = queue.lock().await;
-            guard.pop_front()
-        };
-
-        let Some(task) = task else {
-            // Tasks are empty now
-            return;
-        };
-
-        let resp = match client.head(&task).send().await {
-            Ok(resp) => resp,
-            Err(e) => {
-                log::warn!(
-                    target: &format!("kemono download meta worker#{}", worker_id),
-                    "Failed to check header of {} : {}",
-                    task, e
-                );
-                continue;
-            }
-        };
-
-        let length = resp.headers().get("content-length")
-            .and_then(|val| val.to_str().ok())
-            .and_then(|val| val.parse::<u64>().ok());
-
-        *downloading_size.lock().await += length.unwrap_or(0);
+/// Key in the upload cache, the file paths are included so edited posts are downloaded again
+fn kemono_upload_key(post: &KemonoPost) -> String {
+    let mut hasher = Sha256::new();
+    for file in post.file.iter().chain(post.attachments.iter()) {
+        hasher.update(file.path.as_bytes());
+        hasher.update(b"\n");
     }
+    let digest: String = hasher.finalize().iter().take(8).map(|byte| format!("{byte:02x}")).collect();
+    format!("kemono:{}:{}:{}", post.service, post.id, digest)
 }
 
-async fn kemono_download_worker(
-    worker_id: u64,
-    client: Client,
-    queue: Arc<Mutex<VecDeque<DownloadTask>>>,
-    completed: Arc<Mutex<Vec<DownloadItem>>>,
-) {
-    loop {
-        let task = {
-            let mut guard = queue.lock().await;
-            guard.pop_front()
-        };
-        
-        let Some(task) = task else {
-            // Tasks are empty now
-            return;
-        };
-
-        log::debug!(
-            target: &format!("kemono download worker#{}", worker_id),
-            "Downloading {} from {}",
-            task.save_name, task.url
-        );
-
-        if let Err(e) = download_url_to_path(
-            Some(client.clone()), &task.url, task.save_path
-        ).await {
-            log::warn!(
-                target: &format!("kemono download worker#{}", worker_id),
-                "Failed to download file {} : {}",
-                task.save_name, e
-            );
-            continue;
-        }
-
-        {
-            let mut guard = completed.lock().await;
-            guard.push(DownloadItem { save_name: task.save_name });
-        }
+/// Kemono stores files by their SHA-256, e.g. `/ab/cd/<sha256>.png`, which is used to verify the download
+fn kemono_download_task(file: &KemonoFile, prefix: &str, root_dir: &Path) -> DownloadTask {
+    const KEMONO_BASE_URL: &str = "https://kemono.cr";
+    let url = format!("{KEMONO_BASE_URL}{}", file.path);
+    let task = DownloadTask::new(url, root_dir.join(format!("{}{}", prefix, file.name)));
+    let hash = Path::new(&file.path).file_stem().and_then(|stem| stem.to_str());
+    match hash {
+        Some(hash) if hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) => task.sha256(hash),
+        _ => task
     }
-}
\ No newline at end of file
+}
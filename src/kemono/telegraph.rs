@@ -9,6 +9,7 @@ use tl::Parser;
 use crate::context::Context;
 use crate::helper::bot_actions;
 use crate::helper::log::LogOp;
+use crate::i18n::{message_locale, tr_args};
 use crate::kemono::creator::CreatorProfile;
 use crate::kemono::post::KemonoPost;
 use crate::telegraph::request::CreatePageRequest;
@@ -115,8 +116,7 @@ pub async fn send_telegraph_preview(
         return_content: false,
     };
 
-    let client = reqwest::Client::new();
-    let response: TelegraphResponse = client.post("https://api.telegra.ph/createPage")
+    let response: TelegraphResponse = ctx.http.telegraph.post("https://api.telegra.ph/createPage")
         .json(&create_page_req)
         .send()
         .await?
@@ -134,8 +134,8 @@ pub async fn send_telegraph_preview(
 
     let page: Page = Page::deserialize(page)?;
 
-    bot_actions::send_html_message(&ctx.bot, msg.chat.id, format!(
-        "Telegraph 預覽: <a href=\"{}\">{}</a>", page.url, page.title
+    bot_actions::send_html_message(&ctx.bot, msg.chat.id, tr_args(
+        message_locale(&ctx, &msg), "kemono.telegraph_preview", &[&page.url, &page.title]
     )).await?;
 
     Ok(())
@@ -3,8 +3,17 @@ mod helper;
 mod types;
 mod context;
 mod handler;
+mod error;
+mod i18n;
 mod basic_commands;
+mod callback;
+mod access;
 mod telegraph;
+mod updater;
+mod shutdown;
+mod settings;
+mod jobs;
+mod storage;
 
 mod sticker;
 mod pixiv;
@@ -13,25 +22,44 @@ mod kemono;
 
 use std::sync::Arc;
 
-use crate::basic_commands::{basic_command_handler};
+use crate::access::{message_allowed, reply_denied};
+use crate::basic_commands::BasicHandler;
 use crate::config::BotConfig;
 
-use crate::context::{Context, ModalState, ModalStateStorage};
-use crate::handler::{HandlerResult, ModalHandlerResult};
+use crate::context::{Context, ModalState, ModalStateStorage, modal_state_sweeper};
+use crate::callback::handle_callback_query;
+use crate::error::report_error;
+use crate::handler::{Handler, HandlerRegistry, UpdateKind};
+use crate::helper::download::DownloadEngine;
+use crate::helper::handled_links::HandledLinks;
+use crate::helper::http_client::HttpClients;
 use crate::helper::log::MessageDisplay;
-use crate::helper::message_utils::get_chat_sender;
-use crate::kemono::kemono_handler;
-use crate::monitor::context::MonitorContext;
-use crate::monitor::{monitor_command_handler, monitor_interceptor, monitor_modal_handler};
+use crate::helper::telegram_client::TelegramClient;
+use crate::helper::upload_cache::UploadCache;
+use crate::i18n::Locale;
+use crate::jobs::JobsHandler;
+use crate::jobs::in_flight::InFlightRequests;
+use crate::jobs::janitor::{clear_temp_dir, temp_janitor};
+use crate::jobs::scheduler::JobScheduler;
+use crate::helper::message_utils::{get_chat_sender, get_command};
+use crate::kemono::KemonoHandler;
+use crate::monitor::context::{MonitorContext, MonitorRulesDocument};
+use crate::monitor::{MonitorHandler, MonitorInterceptor};
 use crate::pixiv::context::PixivContext;
-use crate::pixiv::pixiv_handler;
-use crate::sticker::{sticker_handler, sticker_modal_handler};
+use crate::pixiv::PixivHandler;
+use crate::settings::SettingsHandler;
+use crate::settings::store::SettingsStore;
+use crate::sticker::StickerHandler;
+use crate::storage::Storage;
+use crate::shutdown::{graceful_shutdown, shutdown_signal};
+use crate::updater::run_updater;
 
-use futures::future::BoxFuture;
-use tokio::time::{sleep, Duration};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 
-use frankenstein::methods::{GetUpdatesParams, SetMyCommandsParams};
-use frankenstein::types::{BotCommand, Message};
+use frankenstein::inline_mode::InlineQuery;
+use frankenstein::methods::SetMyCommandsParams;
+use frankenstein::types::Message;
 use frankenstein::updates::Update;
 use frankenstein::AsyncTelegramApi;
 use frankenstein::client_reqwest::Bot;
@@ -49,7 +77,10 @@ async fn main() {
         }
     };
 
-    let bot = Bot::new_url(format!("{}/bot{}", config.telegram.bot_api_server, config.telegram.token));
+    let bot = TelegramClient::new(
+        Bot::new_url(format!("{}/bot{}", config.telegram.bot_api_server, config.telegram.token)),
+        config.telegram.api_call.clone()
+    );
 
     
     let cur_dir = match std::env::current_dir() {
@@ -65,73 +96,144 @@ async fn main() {
         log::error!("Failed to create temp directory: {e}");
         panic!();
     }
+    // Leftovers of the last run, e.g. crashed in the middle of a job
+    let removed = clear_temp_dir(&temp_path);
+    if removed > 0 {
+        log::info!("{removed} leftovers removed from temp directory.");
+    }
 
     let data_path = cur_dir.join("data");
     if let Err(e) = std::fs::create_dir_all(&data_path) {
         log::error!("Failed to create temp directory: {e}");
         panic!();
     }
+    // Initialize HTTP clients
+    let http = match HttpClients::from_config(&config) {
+        Ok(http) => http,
+        Err(e) => {
+            log::error!("Failed to initialize HTTP clients: {e}");
+            panic!()
+        }
+    };
     // Initialize pixiv
-    let pixiv_ctx = match PixivContext::from_config(&config.pixiv) {
+    let pixiv_ctx = match PixivContext::from_config(&config) {
         Ok(pixiv) => pixiv,
         Err(e) => {
             log::error!("Failed to initialize Pixiv Context: {e}");
             panic!()
         }
     };
+    let storage = Arc::new(Storage::new(&data_path));
     // Initialize monitor
     let monitor_ctx = MonitorContext::default();
-    if let Err(e) = monitor_ctx.ruleset.add_from_file(data_path.join("monitor_rules.json")) {
-        log::error!("Failed to load monitor ruleset from file: {e}");
+    match storage.load::<MonitorRulesDocument>() {
+        Ok(rules) => monitor_ctx.ruleset.add_rules(rules.unwrap_or_default()),
+        Err(e) => log::error!("Failed to load monitor rules: {e}"),
     }
     log::info!("{} monitor rules loaded.", monitor_ctx.ruleset.len());
 
+    let handlers = HandlerRegistry::new(all_handlers(), &config);
+    let modal_states = ModalStateStorage::from_config(&config.modal, storage.clone());
+    log::info!("{} modal states restored.", modal_states.len());
+    let settings = SettingsStore::load(storage.clone());
+    log::info!("{} chat settings loaded.", settings.len());
+    let jobs = JobScheduler::new(config.jobs.clone());
+    let downloader = DownloadEngine::new(config.download.clone());
+    let upload_cache = UploadCache::from_config(&config.upload_cache, storage.clone());
+    log::info!("{} cached uploads loaded.", upload_cache.len());
+
     let ctx = Context {
         bot, 
         config, 
         temp_root_path: temp_path, 
         data_root_path: data_path, 
-        modal_states: ModalStateStorage::default(), 
+        storage,
+        modal_states, 
+        handlers,
+        settings,
+        jobs,
+        in_flight: InFlightRequests::default(),
+        handled_links: HandledLinks::default(),
+        downloader,
+        upload_cache,
+        http,
         pixiv: pixiv_ctx, 
-        monitor: monitor_ctx
+        monitor: monitor_ctx,
+        tasks: TaskTracker::new(),
+        shutdown: CancellationToken::new(),
     };
     let ctx = Arc::new(ctx);
 
-    // Initialize commands 
-    if let Err(e) = ctx.bot.set_my_commands(&SetMyCommandsParams::builder().commands(get_bot_commands()).build()).await {
+    // Initialize commands, the ones without language code are for users of other languages
+    let default_commands = SetMyCommandsParams::builder()
+        .commands(ctx.handlers.bot_commands(ctx.config.default_locale))
+        .build();
+    if let Err(e) = ctx.bot.set_my_commands(&default_commands).await {
         log::warn!(target: "init", "Failed to set commands: {e}");
     }
+    for locale in Locale::ALL {
+        let param = SetMyCommandsParams::builder()
+            .commands(ctx.handlers.bot_commands(*locale))
+            .language_code(locale.language_code())
+            .build();
+        if let Err(e) = ctx.bot.set_my_commands(&param).await {
+            log::warn!(target: "init", "Failed to set commands for {}: {e}", locale.as_str());
+        }
+    }
 
     log::info!("Bot initialized");
 
-    let mut update_id: i64 = 0;
-    'update_loop: loop {
-        let result = match ctx.bot.get_updates(&GetUpdatesParams::builder()
-            .offset(update_id)
-            .timeout(15)
-            .build()
-        ).await {
-            Ok(result) => result,
-            Err(e) => {
-                log::error!(target: "update_loop", "Failed to get updates: {e}");
-                sleep(Duration::from_secs(5)).await;
-                continue 'update_loop;
-            }
-        }.result;
-        for update in result {
-            update_id = i64::max(update_id, update.update_id as i64 + 1);
-            let ctx_clone = ctx.clone();
-            tokio::spawn(async move {
-                handle_update(ctx_clone, update).await;
-            });
-        }
+    tokio::spawn(modal_state_sweeper(ctx.clone()));
+    tokio::spawn(temp_janitor(ctx.clone()));
+
+    let shutdown = ctx.shutdown.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        log::info!(target: "shutdown", "Shutdown signal received, stop receiving updates");
+        shutdown.cancel();
+    });
+
+    if let Err(e) = run_updater(ctx.clone()).await {
+        log::error!("Updater stopped: {e}");
     }
+
+    graceful_shutdown(ctx).await;
+}
+
+/// All modules, disabled ones are filtered out by `HandlerRegistry`
+fn all_handlers() -> Vec<Arc<dyn Handler>> {
+    vec![
+        Arc::new(BasicHandler),
+        Arc::new(MonitorInterceptor),
+        Arc::new(StickerHandler),
+        Arc::new(PixivHandler),
+        Arc::new(KemonoHandler),
+        Arc::new(MonitorHandler),
+        Arc::new(SettingsHandler),
+        Arc::new(JobsHandler),
+    ]
 }
 
 async fn handle_update(ctx: Arc<Context>, update: Update) {
+    let updates_config = &ctx.config.telegram.updates;
     match update.content {
         frankenstein::updates::UpdateContent::Message(message) => {
-            handle_message(ctx, Arc::new(*message)).await
+            handle_message(ctx, Arc::new(*message), UpdateKind::Message).await
+        }
+        frankenstein::updates::UpdateContent::CallbackQuery(query) => {
+            handle_callback_query(ctx, Arc::new(*query)).await
+        }
+        frankenstein::updates::UpdateContent::EditedMessage(message) if updates_config.edited_message => {
+            handle_message(ctx, Arc::new(*message), UpdateKind::EditedMessage).await
+        }
+        frankenstein::updates::UpdateContent::ChannelPost(message) if updates_config.channel_post => {
+            handle_message(ctx, Arc::new(*message), UpdateKind::ChannelPost).await
+        }
+        frankenstein::updates::UpdateContent::EditedChannelPost(message) if updates_config.edited_channel_post => {
+            handle_message(ctx, Arc::new(*message), UpdateKind::EditedChannelPost).await
+        }
+        frankenstein::updates::UpdateContent::InlineQuery(query) => {
+            handle_inline_query(ctx, Arc::new(query)).await
         }
         _ => {
             log::debug!(target: "update_handler", "Ignoring unhandled type {:?}", update.content);
@@ -139,14 +241,7 @@ async fn handle_update(ctx: Arc<Context>, update: Update) {
     };
 }
 
-static HANDLERS: &[fn(Arc<Context>, Arc<Message>) -> BoxFuture<'static, HandlerResult>] = &[
-    sticker_handler,
-    pixiv_handler,
-    kemono_handler,
-    monitor_command_handler,
-];
-
-async fn handle_message(ctx: Arc<Context>, msg: Arc<Message>) {
+async fn handle_message(ctx: Arc<Context>, msg: Arc<Message>, kind: UpdateKind) {
 
     log::debug!(
         "Chat ID: {}, From ID: {:?}, SenderChat ID: {:?}", 
@@ -160,39 +255,50 @@ async fn handle_message(ctx: Arc<Context>, msg: Arc<Message>) {
         MessageDisplay(&msg)
     );
 
-    /*
-        Monitor is run prior to any handlers
-    */
-    if let Err(e) = monitor_interceptor(ctx.clone(), msg.clone()).await {
-        log::error!("Monitor handler execution failed: {e}");
-    }
-    
-    // Basic handler is handled prior to all handlers & routers
-    match basic_command_handler(ctx.clone(), msg.clone()).await {
-        Ok(std::ops::ControlFlow::Continue(_)) => {}
-        Ok(std::ops::ControlFlow::Break(_)) => { return; }
-        Err(e) => {
-            log::error!("Handler execution failed: {e}, detail: {e:?}");
+    // Denied users and chats are ignored by all modules, except the ones watching every message
+    let allowed = message_allowed(&ctx, None, &msg);
+
+    // Modal states only apply to new messages
+    let mut modal_state = match (kind, allowed) {
+        (UpdateKind::Message, true) => ctx.modal_states.get_state(get_chat_sender(&msg)).await,
+        _ => None
+    };
+
+    for handler in ctx.handlers.for_kind(kind) {
+        // Route to modal handler once all handlers with negative priority are done
+        if handler.priority() >= 0 && let Some(state) = modal_state.take()
+            && route_modal(ctx.clone(), msg.clone(), state).await
+        {
             return;
         }
-    }
 
-    // Route to modal handler
-    if let Some(state) = ctx.modal_states.get_state(get_chat_sender(&msg)).await {
-        let result = modal_handler(ctx, msg, state).await;
-        if let Err(e) = result {
-            log::error!("Modal handler execution failed: {e}, detail: {e:?}");
+        // Handlers watching every message, e.g. copying messages matching monitor rules, are not limited
+        if handler.access_controlled() {
+            if !allowed {
+                if kind == UpdateKind::Message && let Err(e) = reply_denied(&ctx, &msg).await {
+                    log::warn!("Failed to reply access denied message: {e}");
+                }
+                return;
+            }
+            if !message_allowed(&ctx, Some(handler.name()), &msg) {
+                // Only reply to the commands of the module
+                let is_module_command = get_command(&msg)
+                    .is_some_and(|command| handler.commands().iter().any(|(name, _)| *name == command));
+                if !is_module_command {
+                    continue;
+                }
+                if kind == UpdateKind::Message && let Err(e) = reply_denied(&ctx, &msg).await {
+                    log::warn!("Failed to reply access denied message: {e}");
+                }
+                return;
+            }
         }
-        return;
-    }
 
-    // Normal handler
-    for handler in HANDLERS {
-        let result = handler(ctx.clone(), msg.clone()).await;
+        let result = handler.handle_message(ctx.clone(), msg.clone(), kind).await;
         let action = match result {
             Ok(action) => action,
             Err(e) => {
-                log::error!("Handler execution failed: {e}, detail: {e:?}");
+                report_error(&ctx, &msg, &e).await;
                 return;
             }
         };
@@ -201,28 +307,35 @@ async fn handle_message(ctx: Arc<Context>, msg: Arc<Message>) {
             std::ops::ControlFlow::Break(_) => { return; }
         }
     }
+    if let Some(state) = modal_state {
+        route_modal(ctx, msg, state).await;
+    }
     // log::debug!("Message is rejected by all handlers: {:?}");
 }
 
-async fn modal_handler(ctx: Arc<Context>, msg: Arc<Message>, state: ModalState) -> ModalHandlerResult {
-    match state {
-        ModalState::Sticker(state) => sticker_modal_handler(ctx, msg, state).await,
-        ModalState::Monitor(state) => monitor_modal_handler(ctx, msg, state).await
+/// Returns false if the module owning the state is disabled or denied, the state is dropped then
+async fn route_modal(ctx: Arc<Context>, msg: Arc<Message>, state: ModalState) -> bool {
+    let owner = ctx.handlers.modal_owner(&state)
+        .filter(|owner| message_allowed(&ctx, Some(owner.name()), &msg));
+    let Some(owner) = owner else {
+        ctx.modal_states.release_state(get_chat_sender(&msg)).await;
+        return false;
+    };
+    if let Err(e) = owner.handle_modal(ctx.clone(), msg.clone(), state).await {
+        report_error(&ctx, &msg, &e).await;
     }
+    true
 }
 
-fn get_bot_commands() -> Vec<BotCommand> {
-    let commands = vec![
-        basic_commands::COMMAND_LIST,
-        sticker::COMMAND_LIST,
-        pixiv::COMMAND_LIST,
-        kemono::COMMAND_LIST,
-    ].concat();
-    commands.into_iter().map(|(command, desc)| 
-        BotCommand::builder()
-        .command(command)
-        .description(desc)
-        .build()  
-    )
-    .collect()
-}
\ No newline at end of file
+async fn handle_inline_query(ctx: Arc<Context>, query: Arc<InlineQuery>) {
+    for handler in ctx.handlers.for_kind(UpdateKind::InlineQuery) {
+        // Inline queries have no chat, the user ID is used as the private chat ID
+        let user_id = query.from.id as i64;
+        if !ctx.config.access.is_allowed(Some(handler.name()), user_id, Some(user_id)) {
+            continue;
+        }
+        if let Err(e) = handler.handle_inline_query(ctx.clone(), query.clone()).await {
+            log::error!(target: "update_handler", "Failed to handle inline query: {e:?}");
+        }
+    }
+}
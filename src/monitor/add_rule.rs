@@ -2,19 +2,21 @@ use std::sync::Arc;
 
 use frankenstein::{AsyncTelegramApi, methods::SendMessageParams};
 use frankenstein::types::{ChatShared, KeyboardButton, KeyboardButtonRequestChat, KeyboardButtonRequestUsers, Message, MessageOrigin, ReplyKeyboardMarkup, ReplyMarkup, SharedUser};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::helper::{param_builders, name_utils};
 use crate::helper::message_utils::{get_chat_sender, get_command};
 use crate::handler::ModalHandlerResult;
 use crate::context::{Context, ModalState};
+use crate::i18n::{Locale, message_locale, tr, tr_args};
 use crate::helper::log::LogOp;
 use crate::helper::param_builders::reply_keyboard_remove;
-use crate::monitor::MonitorModalState;
+use crate::monitor::{MonitorModalState, save_rules};
 use crate::monitor::rules::{FilterRule, MonitorRule};
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SenderInfo {
     SharedUser(SharedUser),
     IdName((i64, Option<String>))
@@ -41,7 +43,7 @@ impl SenderInfo {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ChatInfo {
     ChatShared(ChatShared),
     IdName((i64, Option<String>))
@@ -116,7 +118,7 @@ async fn handler_wait_forward_state(ctx: Arc<Context>, msg: Arc<Message>) -> any
     let Some(origin) = msg.forward_origin.as_ref() else {
         ctx.bot.send_message(&build_message_with_markup(
             msg.chat.id,
-            "這條消息似乎不是轉發的消息呢……請轉發一條要監視的用戶的消息\n如果需要退出，使用指令 /exit 退出",
+            tr(message_locale(&ctx, &msg), "monitor.not_forwarded"),
             reply_keyboard_remove()
         )).await?;
         return Ok(());
@@ -147,7 +149,7 @@ async fn handler_wait_forward_state(ctx: Arc<Context>, msg: Arc<Message>) -> any
         MessageOrigin::HiddenUser(_) => {
             ctx.bot.send_message(&build_message_with_markup(
                 msg.chat.id,
-                "這條消息的發送者信息被隱藏了呢……請重新轉發一條要監視的用戶的消息\n如果需要退出，使用指令 /exit 退出",
+                tr(message_locale(&ctx, &msg), "monitor.forward_hidden"),
                 reply_keyboard_remove()
             )).await?;
             return Ok(())
@@ -166,7 +168,7 @@ async fn handler_wait_reply_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyho
     let Some(external_reply) = msg.external_reply.as_ref() else {
         ctx.bot.send_message(&build_message_with_markup(
             msg.chat.id,
-            "這條消息似乎不是在其它群組中回覆的消息呢……\n具體來講，您需要在要監視的群組中回覆要監視的用戶，然後選擇「在另一個聊天中回覆」，並在這裡回覆任意內容。\n如果需要退出，使用指令 /exit 退出",
+            tr(message_locale(&ctx, &msg), "monitor.not_external_reply"),
             reply_keyboard_remove()
         )).await?;
         return Ok(());
@@ -200,7 +202,7 @@ async fn handler_wait_reply_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyho
         MessageOrigin::HiddenUser(_) => {
             ctx.bot.send_message(&build_message_with_markup(
                 msg.chat.id,
-                "這條消息的發送者信息被隱藏了呢……請重新回覆一條消息。\n如果需要退出，使用指令 /exit 退出",
+                tr(message_locale(&ctx, &msg), "monitor.reply_hidden"),
                 reply_keyboard_remove()
             )).await?;
             return Ok(())
@@ -215,7 +217,7 @@ async fn handler_wait_reply_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyho
     if chat.is_none() {
         ctx.bot.send_message(&build_message_with_markup(
             msg.chat.id,
-            "這條消息轉發的消息中似乎沒有群組的信息，所以規則設置為不對群組進行篩選。",
+            tr(message_locale(&ctx, &msg), "monitor.reply_no_chat"),
             reply_keyboard_remove()
         )).await?;
     }
@@ -241,7 +243,7 @@ async fn handle_wait_user_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow:
     } else {
         ctx.bot.send_message(&build_message_with_markup(
             msg.chat.id,
-            "請點擊下方的按鈕，選擇一個要監視的用戶～\n如果不需要根據用戶篩選，使用指令 /skip 跳过\n如果需要退出，使用指令 /exit 退出",
+            tr(message_locale(&ctx, &msg), "monitor.select_user_retry"),
             reply_keyboard_remove()
         )).await?;
     }
@@ -252,7 +254,8 @@ async fn handle_wait_chat_state(ctx: Arc<Context>, msg: Arc<Message>, sender: Op
     if get_command(&msg).is_some_and(|s| s == "skip") {
         if sender.is_none() {
             log::info!(target: "monitor_add_rule_modal", "{} Skipped both user info and chat info, exit add rule modal", LogOp(&msg));
-            to_exit(ctx, msg, "至少需要選擇監視一個用戶或一個群組。\n如果需要重新開始添加監視規則，使用指令 /monitor").await?;
+            let text = tr(message_locale(&ctx, &msg), "monitor.nothing_selected");
+            to_exit(ctx, msg, text).await?;
         } else {
             log::info!(target: "monitor_add_rule_modal", "{} Received chat info, requesting keywords", LogOp(&msg));
             to_wait_keyword_state(ctx, msg, sender, None).await?;
@@ -264,7 +267,7 @@ async fn handle_wait_chat_state(ctx: Arc<Context>, msg: Arc<Message>, sender: Op
     } else {
         ctx.bot.send_message(&build_message_with_markup(
             msg.chat.id,
-            "請點擊下方的按鈕，請選擇一個要監視的群組～\n如果不需要根據群組篩選，使用指令 /skip 跳过\n如果需要退出，使用指令 /exit 退出",
+            tr(message_locale(&ctx, &msg), "monitor.select_chat_retry"),
             reply_keyboard_remove()
         )).await?;
     }
@@ -280,7 +283,7 @@ async fn handle_wait_keyword_state(ctx: Arc<Context>, msg: Arc<Message>, sender:
     let Some(text) = msg.text.as_ref() else {
         ctx.bot.send_message(&build_message_with_markup(
             msg.chat.id,
-            "請發送以空格分隔的關鍵詞，總字符數量不超過 64 字。\n如果不需要根據關鍵詞篩選，使用指令 /skip 跳过",
+            tr(message_locale(&ctx, &msg), "monitor.keyword_prompt"),
             reply_keyboard_remove()
         )).await?;
         return Ok(());
@@ -300,7 +303,7 @@ async fn handle_wait_keyword_state(ctx: Arc<Context>, msg: Arc<Message>, sender:
 async fn to_wait_forward_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
     ctx.bot.send_message(&build_message_with_markup(
         msg.chat.id, 
-        "請轉發一條要監視的用戶的消息～", 
+        tr(message_locale(&ctx, &msg), "monitor.forward_prompt"), 
         reply_keyboard_remove(),
     )).await?;
     ctx.modal_states.set_state(get_chat_sender(&msg), ModalState::Monitor(MonitorModalState::WaitForward)).await;
@@ -310,7 +313,7 @@ async fn to_wait_forward_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::
 async fn to_wait_reply_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
     ctx.bot.send_message(&build_message_with_markup(
         msg.chat.id, 
-        "請在這裡回覆一條「要監視的用戶」在「要監視的群組」發送的消息～", 
+        tr(message_locale(&ctx, &msg), "monitor.reply_prompt"), 
         reply_keyboard_remove(),
     )).await?;
     ctx.modal_states.set_state(get_chat_sender(&msg), ModalState::Monitor(MonitorModalState::WaitReply)).await;
@@ -320,8 +323,8 @@ async fn to_wait_reply_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Re
 async fn to_wait_user_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
     ctx.bot.send_message(&build_message_with_markup(
         msg.chat.id, 
-        "請選擇一個要監視的用戶～\n如果不需要根據用戶篩選，使用指令 /skip 跳过", 
-        user_request_markup()
+        tr(message_locale(&ctx, &msg), "monitor.select_user_prompt"), 
+        user_request_markup(message_locale(&ctx, &msg))
     )).await?;
     ctx.modal_states.set_state(get_chat_sender(&msg), ModalState::Monitor(MonitorModalState::WaitUserSelect)).await;
     Ok(())
@@ -330,8 +333,8 @@ async fn to_wait_user_state(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Res
 async fn to_wait_chat_state(ctx: Arc<Context>, msg: Arc<Message>, sender: Option<SenderInfo>) -> anyhow::Result<()> {
     ctx.bot.send_message(&build_message_with_markup(
         msg.chat.id, 
-        "請選擇一個要監視的群組～\n如果不需要根據群組篩選，使用指令 /skip 跳过", 
-        group_request_markup()
+        tr(message_locale(&ctx, &msg), "monitor.select_chat_prompt"), 
+        group_request_markup(message_locale(&ctx, &msg))
     )).await?;
     ctx.modal_states.set_state(get_chat_sender(&msg), ModalState::Monitor(MonitorModalState::WaitChatSelect(sender))).await;
     Ok(())
@@ -340,7 +343,7 @@ async fn to_wait_chat_state(ctx: Arc<Context>, msg: Arc<Message>, sender: Option
 async fn to_wait_keyword_state(ctx: Arc<Context>, msg: Arc<Message>, sender: Option<SenderInfo>, chat: Option<ChatInfo>) -> anyhow::Result<()> {
     ctx.bot.send_message(&build_message_with_markup(
         msg.chat.id,
-        "請發送以空格分隔的關鍵詞，總字符數量不超過 64 字。\n如果不需要根據關鍵詞篩選，使用指令 /skip 跳过",
+        tr(message_locale(&ctx, &msg), "monitor.keyword_prompt"),
         reply_keyboard_remove()
     )).await?;
     ctx.modal_states.set_state(get_chat_sender(&msg), ModalState::Monitor(MonitorModalState::WaitKeyword(sender, chat))).await;
@@ -357,7 +360,7 @@ async fn to_finish(
 
     let uuid = Uuid::new_v4();
 
-    let finish_message = build_finish_message(sender.as_ref(), chat.as_ref(), keywords.as_slice(), &uuid);
+    let finish_message = build_finish_message(sender.as_ref(), chat.as_ref(), keywords.as_slice(), &uuid, message_locale(&ctx, &msg));
 
     let sender_name = sender.as_ref().and_then(|sender| sender.shown_name());
     let chat_title = chat.as_ref().and_then(|chat| chat.shown_name());
@@ -383,14 +386,7 @@ async fn to_finish(
     );
 
     ctx.monitor.ruleset.add_rule(Arc::new(rule));
-    let ctx_cloned = ctx.clone();
-    tokio::task::spawn_blocking(move || {
-        if let Err(e) = ctx_cloned.monitor.ruleset.write_file(ctx_cloned.data_root_path.join("monitor_rules.json")) {
-            log::warn!(
-                target: "monitor_filesave", "Failed to save monitor rule file{e}"
-            );
-        }
-    });
+    save_rules(&ctx);
     ctx.modal_states.release_state(get_chat_sender(&msg)).await;
 
     ctx.bot.send_message(&build_message_with_markup(msg.chat.id, &finish_message, param_builders::reply_keyboard_remove())).await?;
@@ -402,23 +398,24 @@ fn build_finish_message(
     sender: Option<&SenderInfo>,
     chat: Option<&ChatInfo>,
     keywords: &[String], rule_uuid: &Uuid,
+    locale: Locale,
 ) -> String {
     let mut lines: Vec<String> = vec![];
-    lines.push(format!("創建監視規則: <code>{}</code>", rule_uuid));
+    lines.push(tr_args(locale, "monitor.rule_created", &[rule_uuid]));
     match sender.map(|inner| (inner.id(), inner.shown_name())) {
-        Some((id, None)) => lines.push(format!(" - 用戶:　{}", id)),
-        Some((id, Some(name))) => lines.push(format!(" - 用戶:　{} ({})", id, name)),
-        None => lines.push(" - 用戶: (不匹配用戶)".to_string())
+        Some((id, None)) => lines.push(tr_args(locale, "monitor.rule_user", &[&id])),
+        Some((id, Some(name))) => lines.push(tr_args(locale, "monitor.rule_user_named", &[&id, &name])),
+        None => lines.push(tr(locale, "monitor.rule_no_user").to_string())
     }
     match chat.map(|inner| (inner.id(), inner.shown_name())) {
-        Some((id, None)) => lines.push(format!(" - 群組: {}", id)),
-        Some((id, Some(name))) => lines.push(format!(" - 群組: {} ({})", id, name)),
-        None => lines.push(" - 用戶: (不匹配群組)".to_string())
+        Some((id, None)) => lines.push(tr_args(locale, "monitor.rule_chat", &[&id])),
+        Some((id, Some(name))) => lines.push(tr_args(locale, "monitor.rule_chat_named", &[&id, &name])),
+        None => lines.push(tr(locale, "monitor.rule_no_chat").to_string())
     }
     if keywords.is_empty() {
-        lines.push(" -　關鍵詞: (不匹配關鍵詞)".to_string())
+        lines.push(tr(locale, "monitor.rule_no_keyword").to_string())
     } else {
-        lines.push(format!(" - 關鍵詞: {}", keywords.join(", ")));
+        lines.push(tr_args(locale, "monitor.rule_keywords", &[&keywords.join(", ")]));
     }
 
     lines.join("\n").to_string()
@@ -434,14 +431,14 @@ async fn to_exit(ctx: Arc<Context>, msg: Arc<Message>, text: &str) -> anyhow::Re
     Ok(())
 }
 
-fn user_request_markup() -> ReplyMarkup {
+fn user_request_markup(locale: Locale) -> ReplyMarkup {
     let button_req_user = KeyboardButtonRequestUsers::builder()
         .request_id(0)
         .request_name(true)
         .build();
     let button = KeyboardButton::builder()
         .request_users(button_req_user)
-        .text("選擇用戶")
+        .text(tr(locale, "monitor.select_user_button"))
         .build();
     let markup = ReplyKeyboardMarkup::builder()
         .keyboard(vec![vec![button]])
@@ -451,7 +448,7 @@ fn user_request_markup() -> ReplyMarkup {
     ReplyMarkup::ReplyKeyboardMarkup(markup)
 }
 
-fn group_request_markup() -> ReplyMarkup {
+fn group_request_markup(locale: Locale) -> ReplyMarkup {
     let button_req_chat = KeyboardButtonRequestChat::builder()
         .request_id(0)
         .chat_is_channel(false)
@@ -459,7 +456,7 @@ fn group_request_markup() -> ReplyMarkup {
         .build();
     let button = KeyboardButton::builder()
         .request_chat(button_req_chat)
-        .text("選擇群組")
+        .text(tr(locale, "monitor.select_chat_button"))
         .build();
     let markup = ReplyKeyboardMarkup::builder()
         .keyboard(vec![vec![button]])
@@ -1,13 +1,13 @@
-use std::path::Path;
-use std::fmt::Display;
 use std::sync::Arc;
 
 use dashmap::DashMap;
 use frankenstein::types::Message;
+use serde_json::Value;
 use uuid::Uuid;
 
 use crate::monitor::rules::MonitorRule;
 use crate::helper::message_utils::get_sender_id;
+use crate::storage::{Document, Migration};
 
 #[derive(Debug)]
 pub struct MonitorRuleSet {
@@ -83,32 +83,17 @@ impl MonitorRuleSet {
         return receivers;
     }
 
-    pub fn write_file(&self, path: impl AsRef<Path>) -> Result<(), SaveFileError> {
-
-        let rules: Vec<Arc<MonitorRule>> = self.rules.iter()
-            .map(|it| it.value().clone())
-            .collect();
-
-        let file = std::fs::File::create(path)?;
-        let writer = std::io::BufWriter::new(file);
-
-        serde_json::to_writer(writer, &rules)?;
-
-        Ok(())
+    /// The rules to be saved
+    pub fn snapshot(&self) -> Vec<MonitorRule> {
+        self.rules.iter()
+            .map(|it| it.value().as_ref().clone())
+            .collect()
     }
 
-    pub fn add_from_file(&self, path: impl AsRef<Path>) -> Result<(), SaveFileError> {
-        
-        let file = std::fs::File::open(path)?;
-        let reader = std::io::BufReader::new(file);
-
-        let saved: Vec<MonitorRule> = serde_json::from_reader(reader)?;
-
-        for rule in saved {
+    pub fn add_rules(&self, rules: Vec<MonitorRule>) {
+        for rule in rules {
             self.add_rule(Arc::new(rule));
         }
-
-        Ok(())
     }
 
     pub fn len(&self) -> usize { self.rules.len() }
@@ -157,30 +142,34 @@ impl MonitorRuleSet {
     }
 }
 
-pub enum SaveFileError {
-    IoError(std::io::Error),
-    SerdeJsonError(serde_json::Error)
-}
+/// Monitor rules in the store, imported from the `monitor_rules.json` written before the store
+pub struct MonitorRulesDocument;
 
-impl From<std::io::Error> for SaveFileError {
-    fn from(value: std::io::Error) -> Self {
-        SaveFileError::IoError(value)
-    }
-}
+impl Document for MonitorRulesDocument {
+    type Data = Vec<MonitorRule>;
 
-impl From<serde_json::Error> for SaveFileError {
-    fn from(value: serde_json::Error) -> Self {
-        SaveFileError::SerdeJsonError(value)
-    }
+    const NAME: &'static str = "monitor_rules.json";
+    const MIGRATIONS: &'static [Migration] = &[rename_user_nickname];
+    const LEGACY_FILE: Option<&'static str> = Some("monitor_rules.json");
 }
 
-impl Display for SaveFileError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            SaveFileError::IoError(error) => write!(f, "SaveFileError: {error}"),
-            SaveFileError::SerdeJsonError(error) => write!(f, "SaveFileError: {error}"),
-        }
-    }
+/// Rules of old versions label the sender as `user_nickname`
+fn rename_user_nickname(value: Value) -> Result<Value, String> {
+    let Value::Array(rules) = value else {
+        return Err("rules are not an array".to_string());
+    };
+    let rules = rules.into_iter()
+        .map(|rule| {
+            let Value::Object(mut rule) = rule else {
+                return Err("rule is not an object".to_string());
+            };
+            if let Some(name) = rule.remove("user_nickname") && !rule.contains_key("sender_name") {
+                rule.insert("sender_name".to_string(), name);
+            }
+            Ok(Value::Object(rule))
+        })
+        .collect::<Result<Vec<Value>, String>>()?;
+    Ok(Value::Array(rules))
 }
 
 #[derive(Debug)]
@@ -194,4 +183,69 @@ impl Default for MonitorContext {
             ruleset: MonitorRuleSet::default()
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::monitor::rules::FilterRule;
+    use crate::storage::Storage;
+
+    use super::*;
+
+    const LEGACY_RULES: &str = r#"[
+        {
+            "uuid": "2f1d6c8e-5a4b-4c3d-9e8f-7a6b5c4d3e2f",
+            "filter": { "sender_id": 1001, "chat_id": null, "keywords": [] },
+            "forward_to": 42,
+            "user_nickname": "Alice",
+            "chat_title": null
+        },
+        {
+            "uuid": "8a7b6c5d-4e3f-4a1b-8c9d-0e1f2a3b4c5d",
+            "filter": { "sender_id": null, "chat_id": -100200, "keywords": ["hello"] },
+            "forward_to": 42,
+            "user_nickname": null,
+            "chat_title": "Group"
+        }
+    ]"#;
+
+    fn expected_rules() -> Vec<MonitorRule> {
+        vec![
+            MonitorRule {
+                uuid: Uuid::parse_str("2f1d6c8e-5a4b-4c3d-9e8f-7a6b5c4d3e2f").unwrap(),
+                filter: FilterRule { sender_id: Some(1001), chat_id: None, keywords: vec![] },
+                forward_to: 42,
+                sender_name: Some("Alice".to_string()),
+                chat_title: None,
+            },
+            MonitorRule {
+                uuid: Uuid::parse_str("8a7b6c5d-4e3f-4a1b-8c9d-0e1f2a3b4c5d").unwrap(),
+                filter: FilterRule { sender_id: None, chat_id: Some(-100200), keywords: vec!["hello".to_string()] },
+                forward_to: 42,
+                sender_name: None,
+                chat_title: Some("Group".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn legacy_rules_are_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("monitor_rules.json"), LEGACY_RULES).unwrap();
+        let storage = Storage::new(dir.path());
+
+        let rules = storage.load::<MonitorRulesDocument>().unwrap().unwrap();
+        assert_eq!(rules, expected_rules());
+
+        let saved: Value = serde_json::from_slice(&std::fs::read(dir.path().join("store/monitor_rules.json")).unwrap()).unwrap();
+        assert_eq!(saved["version"], 1);
+        assert_eq!(saved["data"][0]["sender_name"], "Alice");
+        assert!(saved["data"][0].get("user_nickname").is_none());
+        assert_eq!(storage.load::<MonitorRulesDocument>().unwrap().unwrap(), expected_rules());
+    }
+
+    #[test]
+    fn legacy_rules_are_read_by_the_alias() {
+        let rules: Vec<MonitorRule> = serde_json::from_str(LEGACY_RULES).unwrap();
+        assert_eq!(rules, expected_rules());
+    }
+}
@@ -5,10 +5,11 @@ use frankenstein::methods::CopyMessageParams;
 use frankenstein::types::{ChatType, Message};
 use futures::future::BoxFuture;
 
-use crate::helper::param_builders;
+use crate::helper::{bot_actions, param_builders};
 use crate::handler::HandlerResult;
 use crate::context::Context;
 use crate::helper::log::LogOp;
+use crate::i18n::{chat_locale, tr};
 
 /// This is a monitor handler, will always return Continue
 pub fn monitor_interceptor(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
@@ -22,7 +23,8 @@ async fn monitor_interceptor_impl(ctx: Arc<Context>, msg: Arc<Message>) -> Handl
     if msg.chat.type_field == ChatType::Private {
         return Ok(std::ops::ControlFlow::Continue(()));
     }
-    tokio::spawn(async move {
+    let tasks = ctx.tasks.clone();
+    tasks.spawn(async move {
         monitor_interceptor_worker(ctx, msg).await
     });
 
@@ -36,7 +38,8 @@ async fn monitor_interceptor_worker(ctx: Arc<Context>, msg: Arc<Message>) {
     for chat_id in forward_to {
         let ctx = ctx.clone();
         let msg = msg.clone();
-        tokio::spawn(async move {
+        let tasks = ctx.tasks.clone();
+        tasks.spawn(async move {
 
             let param = CopyMessageParams::builder()
                 .chat_id(chat_id)
@@ -45,9 +48,26 @@ async fn monitor_interceptor_worker(ctx: Arc<Context>, msg: Arc<Message>) {
                 .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
                 .build();
 
-            if let Err(e) = ctx.bot.copy_message(&param).await {
+            let copied = match ctx.bot.copy_message(&param).await {
+                Ok(response) => response.result,
+                Err(e) => {
+                    log::warn!(
+                        target: "monitor_forward_worker", "{} Failed to make a portal message: {e}", 
+                        LogOp(&msg)
+                    );
+                    return;
+                }
+            };
+
+            // Mark the copy, so it won't be mistaken as a new message
+            if msg.edit_date.is_some()
+                && let Err(e) = bot_actions::send_reply_message(
+                    &ctx.bot, chat_id, tr(chat_locale(&ctx, chat_id), "monitor.edited_copy"),
+                    copied.message_id, None
+                ).await
+            {
                 log::warn!(
-                    target: "monitor_forward_worker", "{} Failed to make a portal message: {e}", 
+                    target: "monitor_forward_worker", "{} Failed to mark the edited message: {e}", 
                     LogOp(&msg)
                 );
             }
@@ -9,23 +9,28 @@ pub use interceptor::monitor_interceptor;
 
 use std::sync::Arc;
 
-use frankenstein::methods::{GetChatMemberParams, SendMessageParams};
+use frankenstein::methods::SendMessageParams;
 use frankenstein::AsyncTelegramApi;
-use frankenstein::types::{ChatMember, ChatType, Message};
+use frankenstein::types::{CallbackQuery, ChatType, Message, ReplyMarkup};
 use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::callback::{CallbackAnswer, CallbackData, callback_button, callback_message, remove_callback_button};
 use crate::helper::{bot_actions, param_builders};
 use crate::helper::message_utils::get_command;
-use crate::handler::{HandlerResult, ModalHandlerResult};
-use crate::context::Context;
+use crate::helper::permission::{check_admin_right, check_member_admin_right};
+use crate::handler::{CallbackHandlerResult, Handler, HandlerResult, ModalHandlerResult, UpdateKind};
+use crate::context::{Context, ModalState};
+use crate::i18n::{callback_locale, message_locale, tr, tr_args};
 use crate::helper::log::LogOp;
 use crate::monitor::add_rule::{ChatInfo, SenderInfo, add_rule_modal_handler, into_add_rule_forawrd_modal, into_add_rule_modal, into_add_rule_reply_modal};
+use crate::monitor::context::MonitorRulesDocument;
 use crate::monitor::parser::parse_monitor_command;
 
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MonitorModalState {
     WaitForward,
     WaitReply,
@@ -34,6 +39,63 @@ pub enum MonitorModalState {
     WaitKeyword(Option<SenderInfo>, Option<ChatInfo>)
 }
 
+pub const COMMAND_LIST: &[(&str, &str)] = &[
+    ("monitor", "cmd.monitor"),
+];
+
+/// Copies matched messages, runs prior to any handlers and never stops the dispatch.
+/// Enabled by the same `monitor` key as the commands, messages of denied users and chats are monitored too
+pub struct MonitorInterceptor;
+
+impl Handler for MonitorInterceptor {
+    fn name(&self) -> &'static str { "monitor" }
+
+    fn priority(&self) -> i32 { -100 }
+
+    fn access_controlled(&self) -> bool { false }
+
+    fn update_kinds(&self) -> &'static [UpdateKind] {
+        &[UpdateKind::Message, UpdateKind::EditedMessage, UpdateKind::ChannelPost, UpdateKind::EditedChannelPost]
+    }
+
+    fn handle_message(&self, ctx: Arc<Context>, msg: Arc<Message>, _kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        monitor_interceptor(ctx, msg)
+    }
+}
+
+pub struct MonitorHandler;
+
+impl Handler for MonitorHandler {
+    fn name(&self) -> &'static str { "monitor" }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] { COMMAND_LIST }
+
+    fn help(&self) -> Option<&'static str> { Some("help.monitor") }
+
+    fn handle_message(&self, ctx: Arc<Context>, msg: Arc<Message>, _kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        monitor_command_handler(ctx, msg)
+    }
+
+    fn owns_modal_state(&self, state: &ModalState) -> bool {
+        matches!(state, ModalState::Monitor(_))
+    }
+
+    fn handle_modal(&self, ctx: Arc<Context>, msg: Arc<Message>, state: ModalState) -> BoxFuture<'static, ModalHandlerResult> {
+        Box::pin(async move {
+            let ModalState::Monitor(state) = state else {
+                return Ok(());
+            };
+            monitor_modal_handler(ctx, msg, state).await
+        })
+    }
+
+    fn callback_namespace(&self) -> Option<&'static str> { Some(CALLBACK_NAMESPACE) }
+
+    fn handle_callback(&self, ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+        monitor_callback_handler(ctx, query, data)
+    }
+}
+
 pub fn monitor_command_handler(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
     let fut = monitor_command_handler_impl(ctx, msg);
     return Box::pin(fut);
@@ -54,7 +116,7 @@ async fn monitor_command_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> H
         if !admin_right {
             bot_actions::send_reply_message(
                 &ctx.bot, msg.chat.id, 
-                "在群組中，這個指令只能由管理員執行。",
+                tr(message_locale(&ctx, &msg), "common.admin_only_command"),
                 msg.message_id, None
             ).await?;
             return Ok(std::ops::ControlFlow::Break(()));
@@ -85,14 +147,14 @@ async fn monitor_command_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> H
                 } else {
                     bot_actions::send_reply_message(
                         &ctx.bot, msg.chat.id, 
-                        "UUID 的格式似乎不太對呢……",
+                        tr(message_locale(&ctx, &msg), "monitor.invalid_uuid"),
                         msg.message_id, None
                     ).await?;
                 }
             } else {
                 bot_actions::send_reply_message(
                     &ctx.bot, msg.chat.id, 
-                    "請在 /monitor remove 指令後面加上要刪除的規則的 UUID 哦——",
+                    tr(message_locale(&ctx, &msg), "monitor.remove_missing_uuid"),
                     msg.message_id, None
                 ).await?;
             }
@@ -111,62 +173,43 @@ async fn monitor_command_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> H
     Ok(std::ops::ControlFlow::Continue(()))
 }
 
-async fn check_admin_right(ctx: &Context, msg: &Message) -> anyhow::Result<bool> {
-    let Some(user_id) = msg.from.as_ref().map(|u| u.id) else {
-        return Ok(false);
-    };
-
-    log::debug!(
-        target: "monitor_command",
-        "{} Checking member admin right ({} at {})",
-        LogOp(msg), user_id, msg.chat.id
-    );
-
-    let get_member = ctx.bot.get_chat_member(&GetChatMemberParams::builder()
-        .chat_id(msg.chat.id)
-        .user_id(user_id)
-        .build()
-    ).await?;
-
-    let result = match get_member.result {
-        ChatMember::Administrator(_) |
-        ChatMember::Creator(_) 
-            => true,
-        _ => false
-    };
-
-    Ok(result)
-}
-
 pub async fn list_rules(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
     
     // For private chat, chat ID = user ID
     // Monitor command supports groups too (by administrators)
     let receiver_id = msg.chat.id;
+    let locale = message_locale(&ctx, &msg);
 
     let rules = ctx.monitor.ruleset.get_receiver_rules(receiver_id);
 
     let mut lines: Vec<String> = vec![];
-    lines.push(format!("你有 {} 條監視規則哦：", rules.len()));
+    lines.push(tr_args(locale, "monitor.rule_count", &[&rules.len()]));
     
+    // One remove button for each rule
+    let buttons: Vec<Vec<_>> = rules.iter()
+        .map(|rule| {
+            let uuid = rule.uuid.to_string();
+            let text = tr_args(locale, "monitor.remove_button", &[&uuid.split('-').next().unwrap_or(&uuid)]);
+            vec![callback_button(text, CALLBACK_NAMESPACE, "rm", &[&uuid])]
+        })
+        .collect();
+
     for rule in rules {
         lines.push("".to_string());
         lines.push(format!("<code>{}</code>", rule.uuid));
         if let Some(id) = rule.filter.sender_id {
-            let nickname_suffix = match rule.sender_name.as_ref() {
-                Some(nickname) => format!(" ({})", nickname),
-                None => "".to_string(),
-            };
-            lines.push(format!(" - 用戶: {}{}", id, nickname_suffix));
+            match rule.sender_name.as_ref() {
+                Some(nickname) => lines.push(tr_args(locale, "monitor.rule_user_named", &[&id, nickname])),
+                None => lines.push(tr_args(locale, "monitor.rule_user", &[&id])),
+            }
         }
         if let Some(id) = rule.filter.chat_id {
-            let nickname_suffix = match rule.chat_title.as_ref() {
-                Some(title) => format!(" ({})", title),
-                None => "".to_string(),
-            };
-            lines.push(format!(" - 群組: {}{}", id, nickname_suffix));
+            match rule.chat_title.as_ref() {
+                Some(title) => lines.push(tr_args(locale, "monitor.rule_chat_named", &[&id, title])),
+                None => lines.push(tr_args(locale, "monitor.rule_chat", &[&id])),
+            }
         }
-        lines.push(format!(" - 關鍵詞: {}", rule.filter.keywords.join(", ")));
+        lines.push(tr_args(locale, "monitor.rule_keywords", &[&rule.filter.keywords.join(", ")]));
     }
 
     let params = SendMessageParams::builder()
@@ -174,6 +217,7 @@ pub async fn list_rules(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<
         .reply_parameters(param_builders::reply_parameters(msg.message_id, None))
         .parse_mode(frankenstein::ParseMode::Html)
         .text(lines.join("\n"))
+        .reply_markup(ReplyMarkup::InlineKeyboardMarkup(param_builders::inline_keyboard(buttons)))
         .build();
     ctx.bot.send_message(&params).await?;
 
@@ -189,7 +233,7 @@ pub async fn remove_rule(ctx: Arc<Context>, msg: Arc<Message>, uuid: Uuid) -> an
     if !exist_and_authorized {
         bot_actions::send_reply_message(
             &ctx.bot, msg.chat.id, 
-            "好像找不到這條規則呢……",
+            tr(message_locale(&ctx, &msg), "monitor.rule_not_found"),
             msg.message_id, None
         ).await?;
         return Ok(())
@@ -199,18 +243,11 @@ pub async fn remove_rule(ctx: Arc<Context>, msg: Arc<Message>, uuid: Uuid) -> an
     ctx.monitor.ruleset.remove_rule(&uuid);
     bot_actions::send_reply_message(
         &ctx.bot, msg.chat.id, 
-        format!("刪除了一條 UUID 為 {uuid} 的規則——"),
+        tr_args(message_locale(&ctx, &msg), "monitor.rule_removed", &[&uuid]),
         msg.message_id, None
     ).await?;
 
-    let ctx_cloned = ctx.clone();
-    tokio::task::spawn_blocking(move || {
-        if let Err(e) = ctx_cloned.monitor.ruleset.write_file(ctx_cloned.data_root_path.join("monitor_rules.json")) {
-            log::warn!(
-                target: "monitor_filesave", "Failed to save monitor rule file{e}"
-            );
-        }
-    });
+    save_rules(&ctx);
 
     Ok(())
 }
@@ -220,27 +257,80 @@ pub async fn remove_all_rules(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::R
     let rules = ctx.monitor.ruleset.get_receiver_rules(msg.chat.id);
     let rule_len = rules.len();
 
-    let ctx_cloned = ctx.clone();
-    tokio::task::spawn_blocking(move || {
+    for rule in rules {
+        ctx.monitor.ruleset.remove_rule(&rule.uuid);
+    }
+    save_rules(&ctx);
 
-        for rule in rules {
-            ctx_cloned.monitor.ruleset.remove_rule(&rule.uuid);
-        }
+    bot_actions::send_reply_message(
+        &ctx.bot, msg.chat.id, 
+        tr_args(message_locale(&ctx, &msg), "monitor.rules_removed", &[&rule_len]),
+        msg.message_id, None
+    ).await?;
+
+    Ok(())
+}
 
-        if let Err(e) = ctx_cloned.monitor.ruleset.write_file(ctx_cloned.data_root_path.join("monitor_rules.json")) {
+/// Save the ruleset in background, the write is tracked so it is waited on shutdown
+pub fn save_rules(ctx: &Arc<Context>) {
+    let ctx_cloned = ctx.clone();
+    ctx.tasks.spawn(async move {
+        let result = ctx_cloned.storage.save::<MonitorRulesDocument>(|| ctx_cloned.monitor.ruleset.snapshot()).await;
+        if let Err(e) = result {
             log::warn!(
-                target: "monitor_filesave", "Failed to save monitor rule file{e}"
+                target: "monitor_filesave", "Failed to save monitor rules: {e}"
             );
         }
     });
+}
 
-    bot_actions::send_reply_message(
-        &ctx.bot, msg.chat.id, 
-        format!("刪除了 {} 條規則——", rule_len),
-        msg.message_id, None
-    ).await?;
+pub const CALLBACK_NAMESPACE: &str = "mon";
 
-    Ok(())
+pub fn monitor_callback_handler(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+    let fut = monitor_callback_handler_impl(ctx, query, data);
+    Box::pin(fut)
+}
+
+async fn monitor_callback_handler_impl(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> CallbackHandlerResult {
+    let locale = callback_locale(&ctx, &query);
+    let Some(msg) = callback_message(&query) else {
+        return Ok(CallbackAnswer::Alert(tr(locale, "common.message_too_old").to_string()));
+    };
+
+    match data.action.as_str() {
+        "rm" => {
+            let Some(Ok(uuid)) = data.arg(0).map(Uuid::parse_str) else {
+                return Ok(CallbackAnswer::Alert(tr(locale, "monitor.invalid_uuid").to_string()));
+            };
+
+            // Same as commands, only administrators can manage rules in groups
+            if msg.chat.type_field != ChatType::Private &&
+                !check_member_admin_right(&ctx, msg.chat.id, query.from.id).await? {
+                return Ok(CallbackAnswer::Alert(tr(locale, "common.admin_only_action").to_string()));
+            }
+
+            let exist_and_authorized = ctx.monitor.ruleset.get_rule(&uuid)
+                .is_some_and(|rule| rule.forward_to == msg.chat.id);
+            if !exist_and_authorized {
+                return Ok(CallbackAnswer::Alert(tr(locale, "monitor.rule_not_found").to_string()));
+            }
+
+            log::info!(
+                target: "monitor_callback",
+                "{} Removing monitor rule {} by button", 
+                LogOp(msg), uuid
+            );
+
+            ctx.monitor.ruleset.remove_rule(&uuid);
+            save_rules(&ctx);
+
+            let callback_data = CallbackData::encode(CALLBACK_NAMESPACE, "rm", &[&uuid.to_string()]);
+            remove_callback_button(&ctx, msg, &callback_data).await?;
+
+            Ok(CallbackAnswer::Notice(tr_args(locale, "monitor.rule_removed", &[&uuid])))
+        }
+        _ => Ok(CallbackAnswer::Silent)
+    }
 }
 
 pub async fn monitor_modal_handler(
@@ -253,30 +343,6 @@ pub async fn monitor_modal_handler(
 }
 
 async fn show_help(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
-    const HELP_MSG : &'static str = 
-r"/monitor <b>指令幫助</b>
-<blockquote expandable> - 指令別名: /mon 或 /monitor
- - 使用方法: /mon &lt;command&gt; [args]
-
-<b>添加規則指令</b>:
- - 指令: <code>add</code>, <code>a</code>
- - <code>add</code>: 交互式選擇用戶和群組、並設置關鍵詞
- - <code>add forward</code>: 通過轉發消息選擇用戶
-   - <code>add f</code>: 簡略指令
- - <code>add reply</code>: 通過在其他聊天中回覆，選擇用戶和群組
-   - <code>add r</code>: 簡略指令
-
-<b>列出所有規則</b>:
- - 指令: <code>list</code>, <code>ls</code>, <code>rules</code>
-
-<b>刪除規則</b>:
- - 指令: <code>remove</code>, <code>rm</code>
- - <code>remove &lt;uuid&gt;</code>: 刪除指定 UUID 的規則
-
-<b>刪除所有規則</b>:
- - 指令: <code>removeall</code>, <code>rmall</code>
-</blockquote>
-";
-    bot_actions::send_html_message(&ctx.bot, msg.chat.id, HELP_MSG).await?;
+    bot_actions::send_html_message(&ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "monitor.help")).await?;
     Ok(())
 }
\ No newline at end of file
@@ -10,8 +10,13 @@ pub struct PixivConfig {
     pub spoiler_nsfw: bool,
     #[serde(default = "default_spoiler_r18g")]
     pub spoiler_r18g: bool,
+    /// Chat for uploading inline query media, inline mode is disabled if not set
+    pub inline_cache_chat_id: Option<i64>,
+    #[serde(default = "default_inline_page_limit")]
+    pub inline_page_limit: u64,
 }
 
 fn default_enable_pixiv_link_detection() -> bool { false }
 fn default_spoiler_nsfw() -> bool { true }
-fn default_spoiler_r18g() -> bool { true }
\ No newline at end of file
+fn default_spoiler_r18g() -> bool { true }
+fn default_inline_page_limit() -> u64 { 10 }
\ No newline at end of file
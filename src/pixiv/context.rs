@@ -1,26 +1,24 @@
-use std::time::Duration;
-
+use dashmap::DashMap;
 use reqwest::Client;
 
-use crate::pixiv::config::PixivConfig;
+use crate::config::BotConfig;
+use crate::helper::http_client::module_client;
 
 #[derive(Debug)]
 pub struct PixivContext {
     pub client: Client,
+    /// The latest inline query ID of each user, earlier queries still being typed are dropped
+    pub inline_queries: DashMap<u64, String>,
 }
 
 impl PixivContext {
-    pub fn from_config(config: &PixivConfig) -> anyhow::Result<PixivContext> {
-        let client = reqwest::Client::builder();
-        let client = if let Some(ua) = config.client_user_agent.as_ref() { 
-            client.user_agent(ua) 
-        } else {client };
-        let client = client
-            .timeout(Duration::from_secs(10))
-            .build()?;
+    /// The client uses the `pixiv` HTTP profile
+    pub fn from_config(config: &BotConfig) -> anyhow::Result<PixivContext> {
+        let client = module_client(config, "pixiv")?;
 
         return Ok(PixivContext {
             client,
+            inline_queries: DashMap::new(),
         })
     }
 }
@@ -1,5 +1,10 @@
+use std::path::PathBuf;
+
+use reqwest::header::REFERER;
+
+use crate::helper::download::DownloadTask;
 use crate::pixiv::config::PixivConfig;
-use crate::pixiv::types::IllustInfo;
+use crate::pixiv::types::{IllustInfo, IllustRequest, SendMode};
 
 pub fn have_spoiler(pixiv_config :&PixivConfig, info: &IllustInfo) -> bool {
     let nsfw = info.tags.contains_tag("R-18");
@@ -62,4 +67,49 @@ let mut text: String = String::new();
     text.push_str(&format!("Source: {}\n", source_url));
 
     return text;
-}
\ No newline at end of file
+}
+/// i.pximg.net refuses requests without the Referer of pixiv
+pub fn pixiv_download_task(url: impl Into<String>, save_path: impl Into<PathBuf>) -> DownloadTask {
+    DownloadTask::new(url, save_path).header(REFERER, "https://www.pixiv.net/")
+}
+
+/// Key in the upload cache of a page sent as photo or file
+pub fn pixiv_page_key(id: u64, page: u64, send_mode: &SendMode) -> String {
+    format!("pixiv:{id}:p{page}:{}", send_mode.as_str())
+}
+
+/// Key in the upload cache of the archive parts
+pub fn pixiv_archive_key(id: u64) -> String {
+    format!("pixiv:{id}:archive")
+}
+
+/// Key in the upload cache of the encoded ugoira, for both photos and files mode
+pub fn pixiv_video_key(id: u64) -> String {
+    format!("pixiv:{id}:video")
+}
+
+/// Key in the upload cache of the ugoira uploaded as animation for inline queries
+pub fn pixiv_animation_key(id: u64) -> String {
+    format!("pixiv:{id}:animation")
+}
+
+/// Keys in the upload cache of the request, pages are cached one by one, archives and videos as a whole
+pub fn pixiv_upload_keys(illust_request: &IllustRequest, page_limit: u64, is_ugoira: bool) -> Vec<String> {
+    let id = illust_request.id;
+    match (&illust_request.send_mode, is_ugoira) {
+        (SendMode::Archive, _) => vec![pixiv_archive_key(id)],
+        (_, true) => vec![pixiv_video_key(id)],
+        (send_mode, false) => (0..page_limit).map(|page| pixiv_page_key(id, page, send_mode)).collect(),
+    }
+}
+
+/// Key of the request in flight, requests with the same key send the same files
+pub fn pixiv_flight_key(illust_request: &IllustRequest, is_ugoira: bool) -> String {
+    let id = illust_request.id;
+    match (&illust_request.send_mode, is_ugoira, illust_request.no_page_limit) {
+        (SendMode::Archive, _, _) => pixiv_archive_key(id),
+        (_, true, _) => pixiv_video_key(id),
+        (send_mode, false, true) => format!("pixiv:{id}:{}:all", send_mode.as_str()),
+        (send_mode, false, false) => format!("pixiv:{id}:{}", send_mode.as_str()),
+    }
+}
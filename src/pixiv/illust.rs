@@ -1,25 +1,41 @@
-use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
 
+use anyhow::Context as _;
 use frankenstein::AsyncTelegramApi;
+use frankenstein::input_file::FileUpload;
 use frankenstein::input_media::{InputMediaDocument, InputMediaPhoto, MediaGroupInputMedia};
-use frankenstein::methods::{SendDocumentParams, SendMediaGroupParams, SendMessageParams};
-use frankenstein::types::{LinkPreviewOptions, Message};
-use reqwest::Client;
+use frankenstein::methods::{SendMediaGroupParams, SendMessageParams};
+use frankenstein::types::{LinkPreviewOptions, Message, ReplyMarkup};
 use serde::Deserialize;
 use tempfile::TempDir;
-use tokio::sync::Mutex;
-use zip::CompressionMethod;
-use zip::write::SimpleFileOptions;
+use tokio::sync::watch;
 
+use crate::callback::callback_button;
 use crate::helper::{bot_actions, param_builders};
+use crate::helper::download::{DownloadProgress, DownloadTask};
+use crate::helper::make_archive::{ArchiveEntry, make_archives, send_archives};
+use crate::helper::progress::ProgressReporter;
+use crate::helper::upload_cache::{is_stale_file_id, message_file_id};
 use crate::context::Context;
-use crate::pixiv::helper::{have_spoiler, illust_caption, illust_caption_detailed};
+use crate::error::{BotError, ErrorKind};
+use crate::i18n::{message_locale, tr, tr_args};
+use crate::jobs::{start_job, wait_in_flight};
+use crate::jobs::in_flight::Joined;
+use crate::jobs::janitor::job_temp_dir;
+use crate::jobs::scheduler::{JobGuard, JobKind};
+use crate::pixiv::CALLBACK_NAMESPACE;
+use crate::pixiv::helper::{
+    have_spoiler, illust_caption, illust_caption_detailed, pixiv_archive_key, pixiv_download_task, pixiv_flight_key,
+    pixiv_page_key, pixiv_upload_keys
+};
 use crate::pixiv::types::{IllustInfo, IllustRequest, PixivResponse, SendMode};
-use crate::pixiv::ugoira::pixiv_ugoira_handler;
-use crate::pixiv::download::download_to_path;
+use crate::pixiv::ugoira::{pixiv_ugoira_handler, pixiv_ugoira_send_video};
+
+/// Rough size of an original page, to check the temp quota before downloading
+const ESTIMATED_PAGE_SIZE: u64 = 4_000_000;
+/// Rough size of the frames and the encoded video of an animation
+pub(crate) const ESTIMATED_UGOIRA_SIZE: u64 = 50_000_000;
 
 pub async fn pixiv_illust_handler(
     ctx: Arc<Context>, 
@@ -28,57 +44,35 @@ pub async fn pixiv_illust_handler(
 ) -> anyhow::Result<()> {
     
     let id = illust_request.id;
+    let locale = message_locale(&ctx, &msg);
 
     log::info!(
         target: "pixiv_illust",
         "[Pixiv: {id}] Requested pixiv illust download with options: {illust_request:?}",
     );
     
-    let info_url = format!("https://www.pixiv.net/ajax/illust/{}", id);
-    log::info!(
-        target: "pixiv_illust",
-        "[Pixiv: {id}] Requesting pixiv API: {}",
-        info_url
-    );
-
-    let request = ctx.pixiv.client.get(info_url);
-    // Add cookie
-    let request = if let Some(php_sessid) = ctx.config.pixiv.php_sessid.as_ref() {
-        request.header("Cookie", format!("PHPSESSID={}", php_sessid))
-    } else {
-        request
-    };
-
-    let response: PixivResponse = request.send().await?.json().await?;
+    let response = request_illust_info(&ctx, id).await?;
 
     // Check response successful
     if response.error {
         if response.body.as_array().is_some_and(|array| array.is_empty()) {
             bot_actions::send_reply_message(
-                &ctx.bot, msg.chat.id, "沒有找到這個 pixiv 畫廊呢……",
+                &ctx.bot, msg.chat.id, tr(locale, "pixiv.illust_not_found"),
                 msg.message_id, None
             ).await?;
         } else {
-            log::error!(
-                target: "pixiv_illust",
-                "[Pixiv: {id}] pixiv returned error: {}",
-                response.message
-            )
+            // Other errors are mostly about restricted works
+            return Err(BotError::new(
+                ErrorKind::UpstreamBlocked,
+                format!("[Pixiv: {id}] pixiv returned error: {}", response.message)
+            ).into());
         }
         return Ok(());
     }
 
     // Get the basic informations
-    let info = match IllustInfo::deserialize(response.body) {
-        Ok(info) => info,
-        Err(e) => {
-            log::error!(
-                target: "pixiv_illust",
-                "[Pixiv: {id}] Failed to extract illustration info from response: {e:?}"
-            );
-            return Ok(());
-        }
-    };
+    let info = IllustInfo::deserialize(response.body)
+        .with_context(|| format!("[Pixiv: {id}] Failed to extract illustration info from response"))?;
 
     if illust_request.metadata_only {
         pixiv_illust_send_metadata(ctx, msg, id, info).await?;
@@ -94,16 +88,55 @@ pub async fn pixiv_illust_handler(
 
     // Ugoira if "ugoira0" is present in the original link
     let Some(original_url) = info.urls.original.as_ref() else {
-        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, "圖源的鏈接被屏蔽了呢……", msg.message_id, None).await?;
+        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, tr(locale, "pixiv.source_blocked"), msg.message_id, None).await?;
+        return Ok(());
+    };
+    let is_ugoira = original_url.contains("ugoira0.jpg");
+
+    // Works sent before are resent by the file IDs without waiting for a slot,
+    // identical requests in flight are waited for and resend the file IDs of the first one
+    let flight = loop {
+        if pixiv_illust_send_cached(&ctx, &msg, &illust_request, &info, page_limit, is_ugoira).await? {
+            return Ok(());
+        }
+        let waiter = match ctx.in_flight.join(&pixiv_flight_key(&illust_request, is_ugoira)) {
+            Joined::First(flight) => break flight,
+            Joined::Waiter(waiter) => waiter,
+        };
+        log::info!(
+            target: "pixiv_illust",
+            "[Pixiv: {id}] Waiting for the identical request in flight"
+        );
+        if let Some(file_ids) = wait_in_flight(&ctx, &msg, waiter).await?
+            && pixiv_illust_send_file_ids(&ctx, &msg, &illust_request, &info, page_limit, is_ugoira, file_ids).await? {
+            return Ok(());
+        }
+    };
+
+    // Everything below downloads, wait for a free slot first
+    let job_kind = if is_ugoira { JobKind::Ugoira } else { JobKind::Pixiv };
+    let expected_size = if is_ugoira {
+        ESTIMATED_UGOIRA_SIZE
+    } else {
+        // Archives keep both the pages and the zip file
+        let copies = if illust_request.send_mode == SendMode::Archive { 2 } else { 1 };
+        page_limit as u64 * ESTIMATED_PAGE_SIZE * copies
+    };
+    let Some(job) = start_job(&ctx, &msg, job_kind, id.to_string(), expected_size).await? else {
         return Ok(());
     };
-    if original_url.contains("ugoira0.jpg") {
+
+    if is_ugoira {
         log::info!(
             target: "pixiv_illust",
             "[Pixiv: {id}] Animation detected, go to animation processing"
         );
         
-        pixiv_ugoira_handler(ctx, msg, illust_request, info).await?;
+        let file_ids = pixiv_ugoira_handler(ctx.clone(), msg, illust_request, info, &job).await?;
+        ctx.upload_cache.save().await;
+        if let Some(file_ids) = file_ids {
+            flight.finish(file_ids);
+        }
 
         return Ok(());
     }
@@ -116,7 +149,7 @@ pub async fn pixiv_illust_handler(
         false => info.urls.regular.as_ref(),
     };
     let Some(ref_url) = ref_url else {
-        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, "圖源的鏈接被屏蔽了呢……", msg.message_id, None).await?;
+        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, tr(locale, "pixiv.source_blocked"), msg.message_id, None).await?;
         return Ok(());
     };
 
@@ -125,7 +158,7 @@ pub async fn pixiv_illust_handler(
             target: "pixiv_illust",
             "[Pixiv: {id}] Failed to get base url from url {ref_url}"
         );
-        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, "圖源的鏈接好像有點問題呢……？", msg.message_id, None).await?;
+        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, tr(locale, "pixiv.source_invalid"), msg.message_id, None).await?;
         return Ok(());
     };
 
@@ -138,70 +171,33 @@ pub async fn pixiv_illust_handler(
     bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::Typing).await?;
 
     // Create tempfile start download all files
-    let temp_dir = tempfile::tempdir_in(&ctx.temp_root_path)?;
+    let temp_dir = job_temp_dir(&ctx, &job)?;
 
-    let task_queue: VecDeque<PixivDownloadTask> = (0..page_limit)
-        .map(|page| PixivDownloadTask { 
-            page: page,
-            file_name: ref_file_name.replace("p0", &format!("p{}", page))
+    let tasks: Vec<DownloadTask> = (0..page_limit)
+        .map(|page| {
+            let file_name = ref_file_name.replace("p0", &format!("p{}", page));
+            pixiv_download_task(format!("{base_url}/{file_name}"), temp_dir.path().join(&file_name))
         })
         .collect();
-    let task_queue: Arc<Mutex<VecDeque<PixivDownloadTask>>> = Arc::new(Mutex::new(task_queue));
-    let completed: Arc<Mutex<Vec<PixivDownloadFile>>> = Arc::new(Mutex::new(Vec::new()));
-    
-    let mut join_handle_list = vec![];
-    const WORKER_COUNT: u64 = 4;
-    let client = ctx.pixiv.client.clone();
-    for worker_id in 0..u64::min(WORKER_COUNT, page_limit) {
-        let client_cloned = client.clone();
-        let queue_cloned = task_queue.clone();
-        let base_url = base_url.to_string();
-        let completed_clone = completed.clone();
-        let path = temp_dir.path().to_path_buf();
-        join_handle_list.push(tokio::task::spawn(async move {
-            pixiv_illust_download_worker(
-                worker_id,
-                client_cloned,
-                base_url,
-                path,
-                queue_cloned,
-                completed_clone
-            ).await;
-        }));
-    }
 
-    if page_limit >= 6 {
-        let mut progress_text = format!("開始下載插畫…… (共 {} 頁）", page_limit);
-        let progress_message = bot_actions::send_reply_message(
-            &ctx.bot, msg.chat.id, &progress_text, msg.message_id, None
-        ).await?;
-        loop {
-            if join_handle_list.iter().map(|h| h.is_finished()).all(|s| s == true) {
-                break;
-            }
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            let count = completed.lock().await.len();
-
-            log::info!(
-                target: "pixiv_illust",
-                "[Pixiv: {id}] Downloading gallery ({}/{})", 
-                count, page_limit
-            );
-
-            let new_text = format!("正在下載插畫…… ({}/{})", count, page_limit);
-            if new_text != progress_text {
-                progress_text = new_text;
-                bot_actions::edit_message_text(&ctx.bot, msg.chat.id, progress_message.message_id, &progress_text).await?;
-            }
-        }
-        bot_actions::delete_message(&ctx.bot, progress_message.chat.id, progress_message.message_id).await?;
-    }
-    for task in join_handle_list { task.await? }
-
-    let mut files = completed.lock().await.clone();
-    files.sort_by(|a, b| {
-        a.page.cmp(&b.page)
-    });
+    const WORKER_COUNT: usize = 4;
+    let (progress_sender, mut progress) = watch::channel(DownloadProgress::default());
+    let on_progress = |progress| { progress_sender.send_replace(progress); };
+    let download = ctx.downloader.download_all(&ctx.pixiv.client, &tasks, WORKER_COUNT, job.token(), &on_progress);
+
+    let mut reporter = ProgressReporter::new(ctx.clone(), &msg, &job, tr(locale, "pixiv.download_title"));
+    let report = reporter.track(download, &mut progress).await;
+    reporter.finish().await;
+    job.check()?;
+    report.log_failures("pixiv_illust");
+
+    let files: Vec<PixivDownloadFile> = report.completed.into_iter()
+        .map(|download| PixivDownloadFile {
+            file_name: tasks[download.index].label(),
+            save_path: download.save_path,
+            page: download.index as u64,
+        })
+        .collect();
 
     if files.len() != page_limit as usize {
         let fail_count = page_limit as usize - files.len();
@@ -211,33 +207,144 @@ pub async fn pixiv_illust_handler(
             files.len(), page_limit, fail_count
         );
         bot_actions::send_reply_message(
-            &ctx.bot, msg.chat.id, format!("畫廊下載完成了，但似乎有 {} 頁插畫下載失敗了呢……", fail_count), msg.message_id, None
+            &ctx.bot, msg.chat.id, tr_args(locale, "pixiv.download_partial", &[&fail_count]), msg.message_id, None
         ).await?;
     }
 
-    match illust_request.send_mode {
-        SendMode::Photos =>     { pixiv_illust_send_photos(ctx, msg, id, page_limit, illust_request, info, files).await? }
-        SendMode::Files =>      { pixiv_illust_send_files(ctx, msg, id, info, files).await? }
-        SendMode::Archive =>    { pixiv_illust_send_archive(ctx, msg, id, info, files, temp_dir).await? }
+    let file_ids = match illust_request.send_mode {
+        SendMode::Photos => {
+            let uploads = files.into_iter().map(PixivUpload::from).collect();
+            let file_ids = pixiv_illust_send_photos(&ctx, &msg, page_limit, &illust_request, &info, uploads, Some(&job)).await?;
+            (file_ids.len() as u64 == page_limit).then_some(file_ids)
+        }
+        SendMode::Files => {
+            let uploads = files.into_iter().map(PixivUpload::from).collect();
+            let file_ids = pixiv_illust_send_files(&ctx, &msg, id, &info, uploads, Some(&job)).await?;
+            (file_ids.len() as u64 == page_limit).then_some(file_ids)
+        }
+        SendMode::Archive => pixiv_illust_send_archive(&ctx, &msg, id, &info, files, temp_dir, &job).await?,
+    };
+    ctx.upload_cache.save().await;
+
+    // Waiters send all pages or nothing
+    if let Some(file_ids) = file_ids {
+        flight.finish(file_ids);
     }
 
     Ok(())
 }
 
+pub async fn request_illust_info(ctx: &Context, id: u64) -> anyhow::Result<PixivResponse> {
+    let info_url = format!("https://www.pixiv.net/ajax/illust/{}", id);
+    log::info!(
+        target: "pixiv_illust",
+        "[Pixiv: {id}] Requesting pixiv API: {}",
+        info_url
+    );
+
+    let request = ctx.pixiv.client.get(info_url);
+    // Add cookie
+    let request = if let Some(php_sessid) = ctx.config.pixiv.php_sessid.as_ref() {
+        request.header("Cookie", format!("PHPSESSID={}", php_sessid))
+    } else {
+        request
+    };
+
+    Ok(request.send().await?.json().await?)
+}
+
+/// Resend the work by the file IDs of the last upload, returns false if it is not cached.
+/// File IDs rejected by Telegram are forgotten, and the work is downloaded again
+async fn pixiv_illust_send_cached(
+    ctx: &Context,
+    msg: &Message,
+    illust_request: &IllustRequest,
+    info: &IllustInfo,
+    page_limit: u64,
+    is_ugoira: bool,
+) -> anyhow::Result<bool> {
+
+    let keys = pixiv_upload_keys(illust_request, page_limit, is_ugoira);
+    let Some(file_ids) = ctx.upload_cache.get_all(&keys) else {
+        return Ok(false);
+    };
+
+    log::info!(
+        target: "pixiv_illust",
+        "[Pixiv: {}] Sending {} cached files", illust_request.id, file_ids.len()
+    );
+    let sent = pixiv_illust_send_file_ids(ctx, msg, illust_request, info, page_limit, is_ugoira, file_ids).await?;
+    if !sent {
+        ctx.upload_cache.remove(&keys);
+    }
+    Ok(sent)
+}
+
+/// Send the work by the file IDs sent before, in the order of the keys from `pixiv_upload_keys`,
+/// returns false if Telegram rejects the file IDs
+async fn pixiv_illust_send_file_ids(
+    ctx: &Context,
+    msg: &Message,
+    illust_request: &IllustRequest,
+    info: &IllustInfo,
+    page_limit: u64,
+    is_ugoira: bool,
+    file_ids: Vec<String>,
+) -> anyhow::Result<bool> {
+
+    let id = illust_request.id;
+    let result = match (&illust_request.send_mode, is_ugoira) {
+        (SendMode::Archive, _) => {
+            send_archives(ctx, msg, &file_ids, Some(&illust_caption(info, None)), None).await.map(|_| ())
+        }
+        (_, true) => {
+            let Some(file_id) = file_ids.into_iter().next() else {
+                return Ok(false);
+            };
+            pixiv_ugoira_send_video(ctx, msg, illust_request, info, file_id.into()).await.map(|_| ())
+        }
+        (SendMode::Photos, false) => {
+            let uploads = cached_uploads(file_ids);
+            pixiv_illust_send_photos(ctx, msg, page_limit, illust_request, info, uploads, None).await.map(|_| ())
+        }
+        (SendMode::Files, false) => {
+            let uploads = cached_uploads(file_ids);
+            pixiv_illust_send_files(ctx, msg, id, info, uploads, None).await.map(|_| ())
+        }
+    };
+
+    match result {
+        Ok(()) => Ok(true),
+        Err(e) if is_stale_file_id(&e) => {
+            log::warn!(
+                target: "pixiv_illust",
+                "[Pixiv: {id}] Sent file IDs are rejected, downloading again: {e}"
+            );
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
 async fn pixiv_illust_send_files(
-    ctx: Arc<Context>, 
-    msg: Arc<Message>,
+    ctx: &Context,
+    msg: &Message,
     id: u64,
-    info: IllustInfo,
-    files: Vec<PixivDownloadFile>,
-) -> anyhow::Result<()> {
+    info: &IllustInfo,
+    uploads: Vec<PixivUpload>,
+    job: Option<&JobGuard>,
+) -> anyhow::Result<Vec<String>> {
 
     bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::UploadDocument).await?;
 
-    let chunks = files.chunks(10);
+    let mut file_ids = Vec::with_capacity(uploads.len());
+    let chunks = uploads.chunks(10);
     let chunk_count = chunks.len();
 
     for (chunk_i, chunk) in chunks.enumerate() {
+        if let Some(job) = job {
+            job.check()?;
+        }
         log::info!(
             target: "pixiv_illust",
             "[Pixiv: {id}] Uploading gallery ({}/{})", 
@@ -245,9 +352,9 @@ async fn pixiv_illust_send_files(
         );
         let media_list: Vec<MediaGroupInputMedia> = chunk.into_iter().map(|result| {
             let doc = InputMediaDocument::builder()
-                .media(result.save_path.clone())
+                .media(result.media.clone())
                 .parse_mode(frankenstein::ParseMode::Html)
-                .caption(illust_caption(&info, Some(result.page + 1)))
+                .caption(illust_caption(info, Some(result.page + 1)))
                 .build();
             MediaGroupInputMedia::Document(doc)
         }).collect();
@@ -256,7 +363,8 @@ async fn pixiv_illust_send_files(
             .media(media_list)
             .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
             .build();
-        ctx.bot.send_media_group(&send_media_group_param).await?;
+        let messages = ctx.bot.send_media_group(&send_media_group_param).await?.result;
+        file_ids.extend(cache_page_uploads(ctx, id, &SendMode::Files, chunk, &messages));
         // let send_document_param = SendDocumentParams::builder()
         //     .chat_id(msg.chat.id)
         //     .document(file.save_path)
@@ -267,93 +375,73 @@ async fn pixiv_illust_send_files(
         // ctx.bot.send_document(&send_document_param).await?;
     }
 
-    Ok(())
+    Ok(file_ids)
 }
 
 async fn pixiv_illust_send_archive(
-    ctx: Arc<Context>, 
-    msg: Arc<Message>,
+    ctx: &Context,
+    msg: &Message,
     id: u64,
-    info: IllustInfo,
+    info: &IllustInfo,
     files: Vec<PixivDownloadFile>,
-    temp_dir: TempDir
-) -> anyhow::Result<()> {
-
-    let archive_file_name = format!("{}.zip", info.id);
-    let archive_path = temp_dir.path().join(&archive_file_name);
-    let archive_path_clone = archive_path.clone();
-    // Blocking zip and write operation
-    let archiving_task = tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
-        let archive_file = std::fs::File::create(archive_path_clone)?;
-        let mut archive = zip::ZipWriter::new(archive_file);
-        let options = SimpleFileOptions::default()
-            .compression_method(CompressionMethod::Stored)
-            .unix_permissions(0o755);
-        for download_file in files {
-            let mut file = match std::fs::File::open(&download_file.save_path) {
-                Ok(f) => f,
-                Err(e) => {
-                    log::warn!(
-                        target: "pixiv_illust archiver",
-                        "[Pixiv: {id}] Failed to open downloaded file {} for archiving: {}", 
-                        download_file.save_path.to_string_lossy(), e
-                    );
-                    continue;
-                }
-            };
-
-            archive.start_file(download_file.file_name, options)?;
-            std::io::copy(&mut file, &mut archive)?;
-        }
-        archive.finish()?;
-        Ok(())
-    });
+    temp_dir: TempDir,
+    job: &JobGuard,
+) -> anyhow::Result<Option<Vec<String>>> {
 
-    // Notice, JoinError is passed up by "?"
-    if let Err(e) = archiving_task.await? {
-        log::warn!(
-            target: "pixiv_illust",
-            "[Pixiv: {id}] Failed to archive file {}: {}", 
-            archive_file_name, e
-        );
-        return Ok(())
+    let complete = files.len() as u64 == info.page_count;
+    let entries = files.into_iter()
+        .map(|file| ArchiveEntry::new(file.file_name, file.save_path))
+        .collect();
+    let parts = make_archives(entries, temp_dir.path(), &info.id, ctx.config.archive.part_size_bytes(&ctx.config.telegram)).await
+        .with_context(|| format!("[Pixiv: {id}] Failed to archive files"))?;
+    if !parts.split.is_empty() {
+        bot_actions::send_reply_message(
+            &ctx.bot, msg.chat.id,
+            tr_args(message_locale(ctx, msg), "archive.split", &[&parts.split.len()]),
+            msg.message_id, None
+        ).await?;
     }
 
     log::info!(
         target: "pixiv_illust",
-        "[Pixiv: {id}] Upolading archive"
+        "[Pixiv: {id}] Upolading archive ({} parts)", parts.paths.len()
     );
+    let messages = send_archives(ctx, msg, &parts.paths, Some(&illust_caption(info, None)), Some(job)).await?;
 
-    bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::UploadDocument).await?;
-
-    let send_document_param = SendDocumentParams::builder()
-        .chat_id(msg.chat.id)
-        .document(archive_path)
-        .parse_mode(frankenstein::ParseMode::Html)
-        .caption(illust_caption(&info, None))
-        .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
-        .build();
-    ctx.bot.send_document(&send_document_param).await?;
-
-    Ok(())
+    // Archives missing some pages are not cached
+    if !complete {
+        return Ok(None);
+    }
+    let file_ids: Option<Vec<String>> = messages.iter().map(message_file_id).collect();
+    if let Some(file_ids) = file_ids.as_ref() {
+        ctx.upload_cache.insert(pixiv_archive_key(id), file_ids.clone());
+    }
+    Ok(file_ids)
 }
 
 async fn pixiv_illust_send_photos(
-    ctx: Arc<Context>, 
-    msg: Arc<Message>,
-    id: u64,
+    ctx: &Context,
+    msg: &Message,
     page_limit: u64,
-    illust_request: IllustRequest,
-    info: IllustInfo,
-    files: Vec<PixivDownloadFile>
-) -> anyhow::Result<()> {
+    illust_request: &IllustRequest,
+    info: &IllustInfo,
+    uploads: Vec<PixivUpload>,
+    job: Option<&JobGuard>,
+) -> anyhow::Result<Vec<String>> {
+
+    let id = illust_request.id;
 
     bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::UploadPhoto).await?;
 
-    let chunks = files.chunks(10);
+    let spoiler = have_spoiler(&ctx.settings.pixiv_config(msg.chat.id, &ctx.config.pixiv), info);
+    let mut file_ids = Vec::with_capacity(uploads.len());
+    let chunks = uploads.chunks(10);
     let chunk_count = chunks.len();
 
     for (chunk_i, chunk) in chunks.enumerate() {
+        if let Some(job) = job {
+            job.check()?;
+        }
         log::info!(
             target: "pixiv_illust",
             "[Pixiv: {id}] Uploading gallery ({}/{})", 
@@ -361,17 +449,17 @@ async fn pixiv_illust_send_photos(
         );
         let media_list: Vec<MediaGroupInputMedia> = chunk.into_iter().map(|result| {
             let photo = InputMediaPhoto::builder()
-                .media(result.save_path.clone())
+                .media(result.media.clone())
                 .parse_mode(frankenstein::ParseMode::Html)
-                .has_spoiler(have_spoiler(&ctx.config.pixiv, &info));
+                .has_spoiler(spoiler);
             let photo = if illust_request.detailed_caption {
                 if result.page == 0 {
-                    photo.caption(illust_caption_detailed(&info))
+                    photo.caption(illust_caption_detailed(info))
                 } else {
                     photo.caption("")
                 }
             } else {
-                photo.caption(illust_caption(&info, if info.page_count == 1 { None } else { Some(result.page + 1) }))
+                photo.caption(illust_caption(info, if info.page_count == 1 { None } else { Some(result.page + 1) }))
             };
             MediaGroupInputMedia::Photo(photo.build())
         }).collect();
@@ -380,17 +468,18 @@ async fn pixiv_illust_send_photos(
             .media(media_list)
             .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
             .build();
-        ctx.bot.send_media_group(&send_media_group_param).await?;
+        let messages = ctx.bot.send_media_group(&send_media_group_param).await?.result;
+        file_ids.extend(cache_page_uploads(ctx, id, &SendMode::Photos, chunk, &messages));
     }
 
     if info.page_count > page_limit {
         bot_actions::send_message(
             &ctx.bot, msg.chat.id,
-            format!("原畫廊共有 {} 頁，此處僅展示前 10 頁。\n如果要發送整個畫廊，請使用 nolim 參數。", info.page_count)
+            tr_args(message_locale(ctx, msg), "pixiv.page_limited", &[&info.page_count])
         ).await?;
     }
 
-    Ok(())
+    Ok(file_ids)
 }
 
 async fn pixiv_illust_send_metadata(
@@ -405,12 +494,22 @@ async fn pixiv_illust_send_metadata(
         "[Pixiv: {id}] Sending illust metadata"
     );
 
+    // Buttons to download the illust after reading the metadata
+    let locale = message_locale(&ctx, &msg);
+    let id_str = id.to_string();
+    let buttons = vec![vec![
+        callback_button(tr(locale, "pixiv.send_photos"), CALLBACK_NAMESPACE, "dl", &[&id_str, SendMode::Photos.as_str()]),
+        callback_button(tr(locale, "pixiv.send_files"), CALLBACK_NAMESPACE, "dl", &[&id_str, SendMode::Files.as_str()]),
+        callback_button(tr(locale, "pixiv.send_archive"), CALLBACK_NAMESPACE, "dl", &[&id_str, SendMode::Archive.as_str()]),
+    ]];
+
     let params = SendMessageParams::builder()
         .chat_id(msg.chat.id)
         .parse_mode(frankenstein::ParseMode::Html)
         .text(illust_caption_detailed(&info))
         .reply_parameters(param_builders::reply_parameters(msg.message_id, None))
         .link_preview_options(LinkPreviewOptions::builder().is_disabled(true).build())
+        .reply_markup(ReplyMarkup::InlineKeyboardMarkup(param_builders::inline_keyboard(buttons)))
         .build();
     
     ctx.bot.send_message(&params).await?;
@@ -418,11 +517,26 @@ async fn pixiv_illust_send_metadata(
     Ok(())
 }
 
-#[derive(Debug, Clone)]
-struct PixivDownloadTask {
-    file_name: String,
-    page: u64,
+/// Record the file IDs of the sent pages, media groups keep the order of the pages
+fn cache_page_uploads(ctx: &Context, id: u64, send_mode: &SendMode, uploads: &[PixivUpload], messages: &[Message]) -> Vec<String> {
+    let mut file_ids = Vec::with_capacity(messages.len());
+    for (upload, message) in uploads.iter().zip(messages) {
+        if let Some(file_id) = message_file_id(message) {
+            ctx.upload_cache.insert(pixiv_page_key(id, upload.page, send_mode), vec![file_id.clone()]);
+            file_ids.push(file_id);
+        }
+    }
+    file_ids
 }
+
+/// Cached file IDs are in the order of the pages
+fn cached_uploads(file_ids: Vec<String>) -> Vec<PixivUpload> {
+    file_ids.into_iter()
+        .enumerate()
+        .map(|(page, file_id)| PixivUpload { page: page as u64, media: file_id.into() })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 struct PixivDownloadFile {
     file_name: String,
@@ -430,50 +544,15 @@ struct PixivDownloadFile {
     page: u64
 }
 
-async fn pixiv_illust_download_worker(
-    worker_id: u64,
-    client: Client,
-    base_url: String,
-    save_dir_path: PathBuf,
-    queue: Arc<Mutex<VecDeque<PixivDownloadTask>>>,
-    completed: Arc<Mutex<Vec<PixivDownloadFile>>>
-) {
-    loop {
-        let task = {
-            let mut guard = queue.lock().await;
-            guard.pop_front()
-        };
-        
-        let Some(task) = task else {
-            // Tasks are empty now
-            return;
-        };
-
-        let url = format!("{base_url}/{}", task.file_name);
-        let save_path = save_dir_path.join(&task.file_name);
-
-        log::debug!(
-            target: &format!("pixiv_illust download worker#{}", worker_id),
-            "Downloading {} from {}",
-            task.file_name, url
-        );
-        
-        if let Err(e) = download_to_path(Some(client.clone()), &url, &save_path).await {
-            log::warn!(
-                target: &format!("pixiv_illust download worker#{}", worker_id),
-                "Failed to download illust file {} from {}: {}",
-                task.file_name, url, e
-            );
-            continue;
-        }
+/// A page to send, the downloaded file or the file ID of the last upload
+#[derive(Debug, Clone)]
+struct PixivUpload {
+    page: u64,
+    media: FileUpload,
+}
 
-        {
-            let mut guard = completed.lock().await;
-            guard.push(PixivDownloadFile {
-                page: task.page,
-                save_path: save_path,
-                file_name: task.file_name
-            });
-        }
+impl From<PixivDownloadFile> for PixivUpload {
+    fn from(file: PixivDownloadFile) -> Self {
+        PixivUpload { page: file.page, media: file.save_path.into() }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,293 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use frankenstein::AsyncTelegramApi;
+use frankenstein::inline_mode::{InlineQuery, InlineQueryResult, InlineQueryResultArticle, InlineQueryResultCachedMpeg4Gif, InlineQueryResultCachedPhoto, InputMessageContent, InputTextMessageContent};
+use frankenstein::input_media::{InputMediaPhoto, MediaGroupInputMedia};
+use frankenstein::methods::{AnswerInlineQueryParams, SendAnimationParams, SendMediaGroupParams};
+use frankenstein::types::LinkPreviewOptions;
+use serde::Deserialize;
+
+use crate::context::Context;
+use crate::helper::download::DownloadTask;
+use crate::helper::upload_cache::{is_stale_file_id, message_file_id};
+use crate::jobs::start_user_job;
+use crate::jobs::janitor::job_temp_dir;
+use crate::jobs::scheduler::JobKind;
+use crate::pixiv::helper::{have_spoiler, illust_caption, pixiv_animation_key, pixiv_download_task, pixiv_page_key};
+use crate::pixiv::illust::{ESTIMATED_UGOIRA_SIZE, request_illust_info};
+use crate::pixiv::parser::parse_pixiv_id;
+use crate::pixiv::types::{IllustInfo, SendMode, UgoiraMeta};
+use crate::pixiv::ugoira::{encode_ugoira_video, request_ugoira_meta};
+
+const INLINE_CACHE_TIME: u32 = 300;
+/// Telegram sends a query on every keystroke, only the one the user stops at is answered,
+/// so the prefixes of an ID being typed do not upload unrelated illusts to the cache chat
+const INLINE_DEBOUNCE: Duration = Duration::from_millis(800);
+/// Rough size of a regular size page, to check the temp quota before downloading
+const ESTIMATED_REGULAR_PAGE_SIZE: u64 = 1_000_000;
+
+/// Answer inline queries like `@bot 12345678` or `@bot https://www.pixiv.net/artworks/12345678`
+pub async fn pixiv_inline_handler(ctx: Arc<Context>, query: Arc<InlineQuery>) -> anyhow::Result<()> {
+    let Some(id) = parse_pixiv_id(&query.query) else {
+        return Ok(());
+    };
+    // Media can only be sent by file_id, as Telegram can not fetch from i.pximg.net directly
+    let Some(cache_chat_id) = ctx.config.pixiv.inline_cache_chat_id else {
+        log::debug!(target: "pixiv_inline", "[Pixiv: {id}] Inline mode is disabled, no cache chat is set");
+        return Ok(());
+    };
+
+    let user_id = query.from.id;
+    ctx.pixiv.inline_queries.insert(user_id, query.id.clone());
+    tokio::time::sleep(INLINE_DEBOUNCE).await;
+    let latest = ctx.pixiv.inline_queries.remove_if(&user_id, |_, latest| *latest == query.id).is_some();
+    if !latest {
+        log::debug!(target: "pixiv_inline", "[Pixiv: {id}] Inline query superseded by a newer one");
+        return Ok(());
+    }
+
+    log::info!(
+        target: "pixiv_inline",
+        "[Pixiv: {id}] Inline query from {}",
+        query.from.id
+    );
+
+    let response = request_illust_info(&ctx, id).await?;
+    let mut cache_keys = vec![];
+    let results = if response.error {
+        log::info!(
+            target: "pixiv_inline",
+            "[Pixiv: {id}] pixiv returned error: {}",
+            response.message
+        );
+        vec![]
+    } else {
+        let info = IllustInfo::deserialize(response.body)?;
+        if have_spoiler(&ctx.config.pixiv, &info) {
+            // Inline results do not support spoilers, only send the caption
+            vec![caption_result(&info)]
+        } else {
+            match info.urls.original.as_ref() {
+                Some(url) if url.contains("ugoira0.jpg") => {
+                    cache_keys.push(pixiv_animation_key(id));
+                    ugoira_results(&ctx, cache_chat_id, user_id, id, &info).await?
+                }
+                Some(_) => {
+                    cache_keys = photo_cache_keys(&ctx, id, &info);
+                    photo_results(&ctx, cache_chat_id, user_id, id, &info, &cache_keys).await?
+                }
+                None => vec![caption_result(&info)]
+            }
+        }
+    };
+
+    let param = AnswerInlineQueryParams::builder()
+        .inline_query_id(&query.id)
+        .results(results)
+        .cache_time(INLINE_CACHE_TIME)
+        .build();
+    if let Err(e) = ctx.bot.answer_inline_query(&param).await {
+        let e = anyhow::Error::from(e);
+        // Uploaded again on the next query
+        if is_stale_file_id(&e) {
+            log::info!(target: "pixiv_inline", "[Pixiv: {id}] Cached file IDs are rejected, dropping them");
+            ctx.upload_cache.remove(&cache_keys);
+        }
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Pages shown inline, media group is limited to 10 items
+fn photo_page_limit(ctx: &Context, info: &IllustInfo) -> u64 {
+    u64::min(u64::min(info.page_count, ctx.config.pixiv.inline_page_limit), 10)
+}
+
+/// Shared with the photos mode of the pixiv command, both upload the regular size
+fn photo_cache_keys(ctx: &Context, id: u64, info: &IllustInfo) -> Vec<String> {
+    (0..photo_page_limit(ctx, info)).map(|page| pixiv_page_key(id, page, &SendMode::Photos)).collect()
+}
+
+fn caption_result(info: &IllustInfo) -> InlineQueryResult {
+    let content = InputTextMessageContent::builder()
+        .message_text(illust_caption(info, None))
+        .parse_mode(frankenstein::ParseMode::Html)
+        .link_preview_options(LinkPreviewOptions::builder().is_disabled(true).build())
+        .build();
+    InlineQueryResultArticle::builder()
+        .id(info.id.clone())
+        .title(info.title.clone())
+        .description(info.author_name.clone())
+        .input_message_content(InputMessageContent::Text(content))
+        .build()
+        .into()
+}
+
+/// Return the pages as cached photos, uploaded to cache chat first unless sent before
+async fn photo_results(
+    ctx: &Context,
+    cache_chat_id: i64,
+    user_id: u64,
+    id: u64,
+    info: &IllustInfo,
+    cache_keys: &[String]
+) -> anyhow::Result<Vec<InlineQueryResult>> {
+    let pages = match ctx.upload_cache.get_all(cache_keys) {
+        Some(file_ids) => {
+            log::info!(target: "pixiv_inline", "[Pixiv: {id}] Answering with cached file IDs");
+            (0..).zip(file_ids).collect()
+        }
+        None => upload_photos(ctx, cache_chat_id, user_id, id, info).await?,
+    };
+    if pages.is_empty() {
+        return Ok(vec![caption_result(info)]);
+    }
+
+    let results = pages.into_iter().map(|(page, file_id)| {
+        let page_num = if info.page_count == 1 { None } else { Some(page + 1) };
+        InlineQueryResultCachedPhoto::builder()
+            .id(format!("{}_p{}", info.id, page))
+            .photo_file_id(file_id)
+            .title(info.title.clone())
+            .caption(illust_caption(info, page_num))
+            .parse_mode(frankenstein::ParseMode::Html)
+            .build()
+            .into()
+    }).collect();
+
+    Ok(results)
+}
+
+/// Upload the pages to cache chat as a job of the user, returns the file IDs by page
+async fn upload_photos(
+    ctx: &Context,
+    cache_chat_id: i64,
+    user_id: u64,
+    id: u64,
+    info: &IllustInfo
+) -> anyhow::Result<Vec<(u64, String)>> {
+    let Some(ref_url) = info.urls.regular.as_ref() else {
+        return Ok(vec![]);
+    };
+    let Some((base_url, ref_file_name)) = ref_url.rsplit_once("/") else {
+        return Ok(vec![]);
+    };
+    let page_limit = photo_page_limit(ctx, info);
+
+    let expected_size = page_limit * ESTIMATED_REGULAR_PAGE_SIZE;
+    let Some(job) = start_user_job(ctx, JobKind::Pixiv, user_id as i64, id.to_string(), expected_size).await else {
+        return Ok(vec![]);
+    };
+
+    let temp_dir = job_temp_dir(ctx, &job)?;
+    let tasks: Vec<DownloadTask> = (0..page_limit).map(|page| {
+        let file_name = ref_file_name.replace("p0", &format!("p{}", page));
+        pixiv_download_task(format!("{base_url}/{file_name}"), temp_dir.path().join(&file_name))
+    }).collect();
+    let report = ctx.downloader.download_all(&ctx.pixiv.client, &tasks, tasks.len(), job.token(), &|_| {}).await;
+    job.check()?;
+    if !report.failed.is_empty() {
+        log::warn!(target: "pixiv_inline", "[Pixiv: {id}] Failed to download {} illust files", report.failed.len());
+        report.log_failures("pixiv_inline");
+    }
+    let files: Vec<_> = report.completed.into_iter()
+        .map(|download| (download.index as u64, download.save_path))
+        .collect();
+    if files.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let media_list: Vec<MediaGroupInputMedia> = files.iter().map(|(_, path)| {
+        MediaGroupInputMedia::Photo(InputMediaPhoto::builder().media(path.clone()).build())
+    }).collect();
+    let param = SendMediaGroupParams::builder()
+        .chat_id(cache_chat_id)
+        .media(media_list)
+        .build();
+    let messages = ctx.bot.send_media_group(&param).await?.result;
+
+    let pages: Vec<(u64, String)> = files.iter().zip(messages.iter())
+        .filter_map(|((page, _), message)| Some((*page, message_file_id(message)?)))
+        .collect();
+    for (page, file_id) in pages.iter() {
+        ctx.upload_cache.insert(pixiv_page_key(id, *page, &SendMode::Photos), vec![file_id.clone()]);
+    }
+    Ok(pages)
+}
+
+/// Return the ugoira as cached animation, encoded and uploaded to cache chat first unless sent before
+async fn ugoira_results(
+    ctx: &Context,
+    cache_chat_id: i64,
+    user_id: u64,
+    id: u64,
+    info: &IllustInfo
+) -> anyhow::Result<Vec<InlineQueryResult>> {
+    let cache_key = pixiv_animation_key(id);
+    let file_id = match ctx.upload_cache.get_all(std::slice::from_ref(&cache_key)) {
+        Some(file_ids) if !file_ids.is_empty() => file_ids[0].clone(),
+        _ => match upload_ugoira(ctx, cache_chat_id, user_id, id, info).await? {
+            Some(file_id) => {
+                ctx.upload_cache.insert(cache_key, vec![file_id.clone()]);
+                file_id
+            }
+            None => return Ok(vec![caption_result(info)]),
+        }
+    };
+
+    let result = InlineQueryResultCachedMpeg4Gif::builder()
+        .id(info.id.clone())
+        .mpeg4_file_id(file_id)
+        .title(info.title.clone())
+        .caption(illust_caption(info, None))
+        .parse_mode(frankenstein::ParseMode::Html)
+        .build();
+
+    Ok(vec![result.into()])
+}
+
+/// Encode the ugoira and upload it to cache chat as animation as a job of the user, returns the file ID
+async fn upload_ugoira(
+    ctx: &Context,
+    cache_chat_id: i64,
+    user_id: u64,
+    id: u64,
+    info: &IllustInfo
+) -> anyhow::Result<Option<String>> {
+    let response = request_ugoira_meta(ctx, id).await?;
+    if response.error {
+        return Ok(None);
+    }
+    let ugoira_meta = UgoiraMeta::deserialize(response.body)?;
+    let Some((_, zip_name)) = ugoira_meta.original_src.rsplit_once("/") else {
+        return Ok(None);
+    };
+
+    let Some(job) = start_user_job(ctx, JobKind::Ugoira, user_id as i64, id.to_string(), ESTIMATED_UGOIRA_SIZE).await else {
+        return Ok(None);
+    };
+
+    let temp_dir = job_temp_dir(ctx, &job)?;
+    let zip_path = temp_dir.path().join(zip_name);
+    let tasks = [pixiv_download_task(&ugoira_meta.original_src, &zip_path)];
+    let report = ctx.downloader.download_all(&ctx.pixiv.client, &tasks, 1, job.token(), &|_| {}).await;
+    job.check()?;
+    if report.completed.is_empty() {
+        report.log_failures("pixiv_inline");
+        return Ok(None);
+    }
+
+    let file_name = format!("{}.mp4", info.id);
+    let Some(video_path) = encode_ugoira_video(id, &ugoira_meta, temp_dir.path(), &zip_path, &file_name, job.token()).await? else {
+        return Ok(None);
+    };
+
+    let param = SendAnimationParams::builder()
+        .chat_id(cache_chat_id)
+        .animation(video_path)
+        .build();
+    let message = ctx.bot.send_animation(&param).await?.result;
+    Ok(message.animation.map(|animation| animation.file_id))
+}
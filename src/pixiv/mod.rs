@@ -1,29 +1,67 @@
 pub mod config;
 pub mod context;
 mod types;
-mod download;
 mod illust;
 mod ugoira;
 mod helper;
 mod parser;
+mod inline;
 
+use std::str::FromStr;
 use std::sync::Arc;
 
-use frankenstein::types::Message;
+use frankenstein::inline_mode::InlineQuery;
+use frankenstein::types::{CallbackQuery, Message};
 use futures::future::BoxFuture;
 
+use crate::callback::{CallbackAnswer, CallbackData, callback_message};
 use crate::pixiv::illust::pixiv_illust_handler;
+use crate::pixiv::inline::pixiv_inline_handler;
 use crate::pixiv::parser::{parse_pixiv_command, parse_pixiv_link};
-use crate::pixiv::types::IllustRequest;
-use crate::handler::HandlerResult;
+use crate::pixiv::types::{IllustRequest, SendMode};
+use crate::handler::{CallbackHandlerResult, Handler, HandlerResult, UpdateKind};
 use crate::helper::message_utils::get_command;
 use crate::helper::bot_actions;
 use crate::context::Context;
+use crate::error::report_error;
+use crate::i18n::{callback_locale, message_locale, tr};
 
 pub const COMMAND_LIST: &[(&'static str, &'static str)] = &[
-    ("pixiv", "從 Pixiv 下載插畫"),
+    ("pixiv", "cmd.pixiv"),
 ];
 
+pub struct PixivHandler;
+
+impl Handler for PixivHandler {
+    fn name(&self) -> &'static str { "pixiv" }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] { COMMAND_LIST }
+
+    fn help(&self) -> Option<&'static str> { Some("help.pixiv") }
+
+    fn update_kinds(&self) -> &'static [UpdateKind] {
+        &[UpdateKind::Message, UpdateKind::EditedMessage, UpdateKind::ChannelPost, UpdateKind::EditedChannelPost, UpdateKind::InlineQuery]
+    }
+
+    fn handle_message(&self, ctx: Arc<Context>, msg: Arc<Message>, kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        match kind {
+            UpdateKind::Message => pixiv_handler(ctx, msg),
+            // Only link detection for edited messages and channel posts
+            _ => pixiv_link_handler(ctx, msg)
+        }
+    }
+
+    fn callback_namespace(&self) -> Option<&'static str> { Some(CALLBACK_NAMESPACE) }
+
+    fn handle_callback(&self, ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+        pixiv_callback_handler(ctx, query, data)
+    }
+
+    fn handle_inline_query(&self, ctx: Arc<Context>, query: Arc<InlineQuery>) -> BoxFuture<'static, anyhow::Result<()>> {
+        Box::pin(pixiv_inline_handler(ctx, query))
+    }
+}
+
 pub fn pixiv_handler(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
     let fut = pixiv_handler_impl(ctx, msg);
     return Box::pin(fut);
@@ -45,7 +83,7 @@ async fn pixiv_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerResu
                         pixiv_illust_handler(ctx, msg, req).await?;
                     }
                     parser::PixivCommandParseResult::InvalidId => {
-                        bot_actions::send_message(&ctx.bot, msg.chat.id, "似乎沒有識別到正確的 pixiv ID 呢……").await?;
+                        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "pixiv.invalid_id")).await?;
                     }
                     parser::PixivCommandParseResult::ShowHelp => {
                         send_pixiv_command_help(ctx, msg).await?;
@@ -56,17 +94,36 @@ async fn pixiv_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerResu
             _ => return Ok(std::ops::ControlFlow::Continue(()))
         }
     }
-    
+
+    pixiv_link_handler_impl(ctx, msg).await
+}
+
+/// Link detection only, also used for edited messages and channel posts
+pub fn pixiv_link_handler(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
+    let fut = pixiv_link_handler_impl(ctx, msg);
+    Box::pin(fut)
+}
+
+async fn pixiv_link_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerResult {
+
+    let Some(text) = msg.text.as_ref() else {
+        return Ok(std::ops::ControlFlow::Continue(()))
+    };
+
     // Link detection for pixiv
-    if ctx.config.pixiv.enable_pixiv_link_detection {
+    if ctx.settings.pixiv_config(msg.chat.id, &ctx.config.pixiv).enable_pixiv_link_detection {
         match parse_pixiv_link(&text) {
             parser::PixivLinkParseResult::Success(id) => {
+                // Edits of the message keep the link, it is only handled once
+                if !ctx.handled_links.insert(msg.chat.id, msg.message_id, format!("pixiv:{id}")) {
+                    return Ok(std::ops::ControlFlow::Break(()));
+                }
                 let req = IllustRequest::link_default(id);
                 pixiv_illust_handler(ctx, msg, req).await?;
                 return Ok(std::ops::ControlFlow::Break(()))
             }
             parser::PixivLinkParseResult::InvalidId => {
-                bot_actions::send_message(&ctx.bot, msg.chat.id, "似乎沒有識別到正確的 pixiv ID 呢……").await?;
+                bot_actions::send_message(&ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "pixiv.invalid_id")).await?;
                 return Ok(std::ops::ControlFlow::Break(()));
             }
             parser::PixivLinkParseResult::NotMatch => {},
@@ -76,18 +133,46 @@ async fn pixiv_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerResu
     Ok(std::ops::ControlFlow::Continue(()))
 }
 
+pub const CALLBACK_NAMESPACE: &str = "px";
+
+pub fn pixiv_callback_handler(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+    let fut = pixiv_callback_handler_impl(ctx, query, data);
+    Box::pin(fut)
+}
+
+async fn pixiv_callback_handler_impl(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> CallbackHandlerResult {
+    let Some(msg) = callback_message(&query) else {
+        return Ok(CallbackAnswer::Alert(tr(callback_locale(&ctx, &query), "common.message_too_old").to_string()));
+    };
+
+    match data.action.as_str() {
+        "dl" => {
+            let id = data.arg(0).and_then(|s| s.parse::<u64>().ok());
+            let send_mode = data.arg(1).and_then(|s| SendMode::from_str(s).ok());
+            let (Some(id), Some(send_mode)) = (id, send_mode) else {
+                return Ok(CallbackAnswer::Silent);
+            };
+
+            let mut req = IllustRequest::link_default(id);
+            req.send_mode = send_mode;
+
+            // Downloading takes longer than the callback query timeout, answer first
+            let msg = Arc::new(msg.clone());
+            let ctx_cloned = ctx.clone();
+            ctx.tasks.spawn(async move {
+                if let Err(e) = pixiv_illust_handler(ctx_cloned.clone(), msg.clone(), req).await {
+                    log::warn!(target: "pixiv_callback", "[Pixiv: {id}] Download from button failed");
+                    report_error(&ctx_cloned, &msg, &e).await;
+                }
+            });
+
+            Ok(CallbackAnswer::Notice(tr(callback_locale(&ctx, &query), "pixiv.download_started").to_string()))
+        }
+        _ => Ok(CallbackAnswer::Silent)
+    }
+}
+
 async fn send_pixiv_command_help(ctx: Arc<Context>, msg: Arc<Message>) -> anyhow::Result<()> {
-    const HELP_MSG : &'static str = 
-        "/pixiv 指令幫助\n\
-        - 使用方法：/pixiv <id> [nolim|files|archive|detail|metaonly]\n\
-        \n\
-        參數說明：\n\
-        - nolim: 允許 10 頁插畫以上的畫廊\n\
-        - files: 發送插畫文件\n\
-        - archive: 發送畫廊的 zip 歸檔\n\
-        - detail: 詳細插畫描述信息\n\
-        - metaonly: 只發送元數據（插畫描述信息）\n\
-        ";
-    bot_actions::send_message(&ctx.bot, msg.chat.id, HELP_MSG).await?;
+    bot_actions::send_message(&ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "pixiv.help")).await?;
     Ok(())
 }
\ No newline at end of file
@@ -64,6 +64,12 @@ pub fn parse_pixiv_command(text: &str) -> PixivCommandParseResult {
     return PixivCommandParseResult::Success(req)
 }
 
+/// Parse a pixiv ID or a pixiv illust link
+pub fn parse_pixiv_id(text: &str) -> Option<u64> {
+    let (_, [id_str]) = PIXIV_LINK_ID_REGEX.captures(text.trim()).map(|c| c.extract())?;
+    id_str.parse::<u64>().ok()
+}
+
 /* Pixiv Link Parser */
 
 static PIXIV_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| 
@@ -85,4 +91,40 @@ pub fn parse_pixiv_link(text: &str) -> PixivLinkParseResult {
         return PixivLinkParseResult::InvalidId;
     };
     return PixivLinkParseResult::Success(id);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link_id(text: &str) -> Option<u64> {
+        match parse_pixiv_link(text) {
+            PixivLinkParseResult::Success(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn links_are_parsed() {
+        assert_eq!(link_id("https://www.pixiv.net/artworks/12345678"), Some(12345678));
+        assert_eq!(link_id("https://www.pixiv.net/en/artworks/12345678#1"), Some(12345678));
+        assert_eq!(link_id("pixiv.net/i/123"), Some(123));
+        assert_eq!(link_id("https://www.pixiv.net/member_illust.php?illust_id=42"), Some(42));
+    }
+
+    #[test]
+    fn bare_ids_are_not_links() {
+        assert_eq!(link_id("12345678"), None);
+        assert_eq!(link_id("https://www.pixiv.net/users/12345678"), None);
+    }
+
+    /// Inline queries accept both
+    #[test]
+    fn ids_and_links_are_parsed_as_id() {
+        assert_eq!(parse_pixiv_id("12345678"), Some(12345678));
+        assert_eq!(parse_pixiv_id(" 12345678 "), Some(12345678));
+        assert_eq!(parse_pixiv_id("https://www.pixiv.net/artworks/12345678"), Some(12345678));
+        assert_eq!(parse_pixiv_id("https://www.pixiv.net/users/12345678"), None);
+        assert_eq!(parse_pixiv_id("abc"), None);
+    }
+}
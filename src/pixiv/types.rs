@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 
 use serde::Deserialize;
 use serde_json::Value;
@@ -12,6 +13,29 @@ pub enum SendMode {
     Archive
 }
 
+impl SendMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SendMode::Photos => "photos",
+            SendMode::Files => "files",
+            SendMode::Archive => "archive",
+        }
+    }
+}
+
+impl FromStr for SendMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "photos" => Ok(SendMode::Photos),
+            "files" => Ok(SendMode::Files),
+            "archive" => Ok(SendMode::Archive),
+            _ => Err(())
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct IllustRequest {
     pub id: u64,
@@ -1,41 +1,38 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
 
+use anyhow::Context as _;
 use frankenstein::AsyncTelegramApi;
+use frankenstein::input_file::FileUpload;
 use frankenstein::methods::{SendDocumentParams, SendVideoParams};
 use frankenstein::types::Message;
 use serde::Deserialize;
 use serde_json::Value;
 use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
 
 use crate::helper::{bot_actions, param_builders};
-use crate::pixiv::download::download_to_path;
-use crate::pixiv::helper::{have_spoiler, illust_caption, illust_caption_detailed};
+use crate::helper::upload_cache::message_file_id;
+use crate::pixiv::helper::{have_spoiler, illust_caption, illust_caption_detailed, pixiv_archive_key, pixiv_download_task, pixiv_video_key};
 use crate::pixiv::types::{IllustInfo, IllustRequest, SendMode, UgoiraMeta};
 use crate::context::Context;
+use crate::error::{BotError, ErrorKind};
+use crate::i18n::{message_locale, tr};
+use crate::jobs::janitor::job_temp_dir;
+use crate::jobs::scheduler::{JobCancelled, JobGuard};
 
 // https://www.pixiv.net/ajax/illust/134231396/ugoira_meta?lang=en
 
 #[derive(Clone, Debug, Deserialize)]
-struct PixivUgoiraResponse {
-    error: bool,
-    message: String,
-    body: Value
+pub struct PixivUgoiraResponse {
+    pub error: bool,
+    pub message: String,
+    pub body: Value
 }
 
-pub async fn pixiv_ugoira_handler(
-    ctx: Arc<Context>, 
-    msg: Arc<Message>,
-    illust_request: IllustRequest,
-    info: IllustInfo,
-) -> anyhow::Result<()> {
-
-    let id = illust_request.id;
-
-    // The previous part is similar to PixivIllust
-    
-    let meta_url = format!("https://www.pixiv.net/ajax/illust/{}/ugoira_meta", info.id);
+pub async fn request_ugoira_meta(ctx: &Context, id: u64) -> anyhow::Result<PixivUgoiraResponse> {
+    let meta_url = format!("https://www.pixiv.net/ajax/illust/{}/ugoira_meta", id);
     log::info!(
         target: "pixiv_ugoira",
         "[Pixiv: {id}] Requesting pixiv API: {meta_url}"
@@ -49,36 +46,44 @@ pub async fn pixiv_ugoira_handler(
         request
     };
 
-    let response: PixivUgoiraResponse = request.send().await?.json().await?;
+    Ok(request.send().await?.json().await?)
+}
+
+/// Returns the file IDs of the sent video or archive, which are also cached
+pub async fn pixiv_ugoira_handler(
+    ctx: Arc<Context>, 
+    msg: Arc<Message>,
+    illust_request: IllustRequest,
+    info: IllustInfo,
+    job: &JobGuard,
+) -> anyhow::Result<Option<Vec<String>>> {
+
+    let id = illust_request.id;
+
+    // The previous part is similar to PixivIllust
+    
+    let response = request_ugoira_meta(&ctx, id).await?;
 
     // Check response successful
     if response.error {
         if response.body.as_array().is_some_and(|array| array.is_empty()) {
             bot_actions::send_reply_message(
-                &ctx.bot, msg.chat.id, "沒有找到這個 pixiv 动图呢……",
+                &ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "pixiv.ugoira_not_found"),
                 msg.message_id, None
             ).await?;
         } else {
-            log::error!(
-                target: "pixiv_ugoira",
-                "[Pixiv: {id}] pixiv returned error: {}", 
-                response.message
-            )
+            // Other errors are mostly about restricted works
+            return Err(BotError::new(
+                ErrorKind::UpstreamBlocked,
+                format!("[Pixiv: {id}] pixiv returned error: {}", response.message)
+            ).into());
         }
-        return Ok(());
+        return Ok(None);
     }
 
     // Get the basic informations
-    let ugoira_meta = match UgoiraMeta::deserialize(response.body) {
-        Ok(meta) => meta,
-        Err(e) => {
-            log::error!(
-                target: "pixiv_ugoira",
-                "[Pixiv: {id}] Failed to extract illustration info from response: {e:?}"
-            );
-            return Ok(());
-        }
-    };
+    let ugoira_meta = UgoiraMeta::deserialize(response.body)
+        .with_context(|| format!("[Pixiv: {id}] Failed to extract ugoira info from response"))?;
 
     let ugoira_url = ugoira_meta.original_src.as_str();
     let Some((_, file_name)) = ugoira_url.rsplit_once("/") else {
@@ -86,14 +91,14 @@ pub async fn pixiv_ugoira_handler(
             target: "pixiv_ugoira",
             "[Pixiv: {id}] Failed to get base url from url {ugoira_url}"
         );
-        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, "圖源的鏈接好像有點問題呢……？", msg.message_id, None).await?;
-        return Ok(());
+        bot_actions::send_reply_message(&ctx.bot, msg.chat.id, tr(message_locale(&ctx, &msg), "pixiv.source_invalid"), msg.message_id, None).await?;
+        return Ok(None);
     };
 
     // About to download, send a typing status
     bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::Typing).await?;
 
-    let temp_dir = tempfile::tempdir_in(&ctx.temp_root_path)?;
+    let temp_dir = job_temp_dir(&ctx, job)?;
     let ugoira_zip_path = temp_dir.path().join(file_name);
     
     log::info!(
@@ -101,33 +106,45 @@ pub async fn pixiv_ugoira_handler(
         "[Pixiv: {id}] Downloading animation zip file from {ugoira_url}",
     );
 
-    if let Err(e) = download_to_path(Some(ctx.pixiv.client.clone()), &ugoira_url, &ugoira_zip_path).await {
+    let task = pixiv_download_task(ugoira_url, &ugoira_zip_path);
+    let result = tokio::select! {
+        result = ctx.downloader.download(&ctx.pixiv.client, &task) => result,
+        _ = job.token().cancelled() => return Err(JobCancelled.into()),
+    };
+    if let Err(e) = result {
         log::warn!(
             target: "pixiv_ugoira",
             "[Pixiv: {id}] Failed to download animation zip file from {ugoira_url} : {e}"
         );
     }
 
-    match illust_request.send_mode {
+    let (key, message) = match illust_request.send_mode {
         SendMode::Photos |
         SendMode::Files => {
-            pixiv_ugoira_send_encoded_video(ctx, msg, illust_request, id, info, ugoira_meta, temp_dir, ugoira_zip_path).await?;
+            let message = pixiv_ugoira_send_encoded_video(&ctx, &msg, &illust_request, &info, ugoira_meta, temp_dir, ugoira_zip_path, job).await?;
+            (pixiv_video_key(id), message)
         }
         SendMode::Archive => {
-            pixiv_ugoira_send_archive(ctx, msg, id, info, ugoira_zip_path).await?;
+            job.check()?;
+            let message = pixiv_ugoira_send_archive(&ctx, &msg, id, &info, ugoira_zip_path).await?;
+            (pixiv_archive_key(id), Some(message))
         }
-    }
+    };
 
-    Ok(())
+    let file_ids: Option<Vec<String>> = message.as_ref().and_then(message_file_id).map(|file_id| vec![file_id]);
+    if let Some(file_ids) = file_ids.as_ref() {
+        ctx.upload_cache.insert(key, file_ids.clone());
+    }
+    Ok(file_ids)
 }
 
 pub async fn pixiv_ugoira_send_archive(
-    ctx: Arc<Context>, 
-    msg: Arc<Message>,
+    ctx: &Context,
+    msg: &Message,
     id: u64,
-    info: IllustInfo,
+    info: &IllustInfo,
     ugoira_zip_path: PathBuf
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Message> {
 
     log::info!(
         target: "pixiv_ugoira",
@@ -140,27 +157,79 @@ pub async fn pixiv_ugoira_send_archive(
         .chat_id(msg.chat.id)
         .document(ugoira_zip_path)
         .parse_mode(frankenstein::ParseMode::Html)
-        .caption(illust_caption(&info, None))
+        .caption(illust_caption(info, None))
         .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
         .build();
-    ctx.bot.send_document(&send_document_param).await?;
 
-    Ok(())
+    Ok(ctx.bot.send_document(&send_document_param).await?.result)
 }
 
+/// Returns None if the encoding failed
 pub async fn pixiv_ugoira_send_encoded_video(
-    ctx: Arc<Context>, 
-    msg: Arc<Message>,
-    illust_request: IllustRequest,
-    id: u64,
-    info: IllustInfo,
+    ctx: &Context,
+    msg: &Message,
+    illust_request: &IllustRequest,
+    info: &IllustInfo,
     ugoira_meta: UgoiraMeta,
     temp_dir: TempDir,
-    ugoira_zip_path: PathBuf
-) -> anyhow::Result<()> {
+    ugoira_zip_path: PathBuf,
+    job: &JobGuard,
+) -> anyhow::Result<Option<Message>> {
+
+    let id = illust_request.id;
+    let file_name = format!("{}.mp4", info.id);
+    let Some(output_path) = encode_ugoira_video(id, &ugoira_meta, temp_dir.path(), &ugoira_zip_path, &file_name, job.token()).await? else {
+        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(message_locale(ctx, msg), "common.transcode_failed")).await?;
+        return Ok(None)
+    };
+    job.check()?;
+
+    log::info!(
+        target: "pixiv_ugoira",
+        "[Pixiv: {id}] Uploading video {file_name}", 
+    );
+
+    Ok(Some(pixiv_ugoira_send_video(ctx, msg, illust_request, info, output_path.into()).await?))
+}
+
+/// Send the encoded video, or the file ID of the video sent before
+pub async fn pixiv_ugoira_send_video(
+    ctx: &Context,
+    msg: &Message,
+    illust_request: &IllustRequest,
+    info: &IllustInfo,
+    video: FileUpload,
+) -> anyhow::Result<Message> {
+
+    bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::UploadVideo).await?;
+
+    let param = SendVideoParams::builder()
+        .chat_id(msg.chat.id)
+        .video(video)
+        .parse_mode(frankenstein::ParseMode::Html)
+        .caption(
+            if illust_request.detailed_caption { illust_caption_detailed(info) } else{ illust_caption(info, None) }
+        )
+        .has_spoiler(have_spoiler(&ctx.settings.pixiv_config(msg.chat.id, &ctx.config.pixiv), info))
+        .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
+        .build();
+
+    Ok(ctx.bot.send_video(&param).await?.result)
+}
 
-    let extract_dir = temp_dir.path().to_path_buf();
-    let zip_path = ugoira_zip_path.clone();
+/// Extract the frames and encode them to an mp4 video in the temp dir, returns None if failed,
+/// ffmpeg is killed if cancelled
+pub async fn encode_ugoira_video(
+    id: u64,
+    ugoira_meta: &UgoiraMeta,
+    temp_dir_path: &Path,
+    ugoira_zip_path: &Path,
+    file_name: &str,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<PathBuf>> {
+
+    let extract_dir = temp_dir_path.to_path_buf();
+    let zip_path = ugoira_zip_path.to_path_buf();
     let unzip_task = tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
         let zip_file = std::fs::File::open(zip_path)?;
         let mut archive = zip::ZipArchive::new(zip_file)?;
@@ -174,13 +243,12 @@ pub async fn pixiv_ugoira_send_encoded_video(
             "[Pixiv: {id}] Failed to extract archive file {} : {e}", 
             ugoira_zip_path.to_string_lossy()
         );
-        return Ok(())
+        return Ok(None)
     }
 
-    let input_glob = format!("{}", temp_dir.path().join("*.jpg").to_string_lossy());
+    let input_glob = format!("{}", temp_dir_path.join("*.jpg").to_string_lossy());
 
-    let file_name = format!("{}.mp4", info.id);
-    let output_path = temp_dir.path().join(&file_name);
+    let output_path = temp_dir_path.join(file_name);
     let output_path_str = output_path.to_string_lossy();
 
     // Calculate framerate 
@@ -209,36 +277,19 @@ pub async fn pixiv_ugoira_send_encoded_video(
         "[Pixiv: {id}] ffmpeg converting image series to {file_name}", 
     );
 
-    let conversion = tokio::process::Command::new("ffmpeg")
+    let mut ffmpeg = tokio::process::Command::new("ffmpeg")
         .args(ffmpeg_args)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
-        .spawn()?
-        .wait().await?;
+        .kill_on_drop(true)
+        .spawn()?;
+    let conversion = tokio::select! {
+        status = ffmpeg.wait() => status?,
+        _ = cancel.cancelled() => return Err(JobCancelled.into()),
+    };
     if !conversion.success() {
-        bot_actions::send_message(&ctx.bot, msg.chat.id, "文件轉碼失敗惹……").await?;
-        return Ok(())
+        return Ok(None)
     }
 
-    log::info!(
-        target: "pixiv_ugoira",
-        "[Pixiv: {id}] Uploading video {file_name}", 
-    );
-
-    bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::UploadVideo).await?;
-
-    let param = SendVideoParams::builder()
-        .chat_id(msg.chat.id)
-        .video(output_path)
-        .parse_mode(frankenstein::ParseMode::Html)
-        .caption(
-            if illust_request.detailed_caption { illust_caption_detailed(&info) } else{ illust_caption(&info, None) }
-        )
-        .has_spoiler(have_spoiler(&ctx.config.pixiv, &info))
-        .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
-        .build();
-
-    ctx.bot.send_video(&param).await?;
-
-    Ok(())
+    Ok(Some(output_path))
 }
\ No newline at end of file
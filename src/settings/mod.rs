@@ -0,0 +1,189 @@
+pub mod store;
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use frankenstein::AsyncTelegramApi;
+use frankenstein::methods::EditMessageTextParams;
+use frankenstein::types::{CallbackQuery, ChatType, InlineKeyboardMarkup, Message, ReplyMarkup};
+use futures::future::BoxFuture;
+
+use crate::callback::{CallbackAnswer, CallbackData, callback_button, callback_message};
+use crate::context::Context;
+use crate::handler::{CallbackHandlerResult, Handler, HandlerResult, UpdateKind};
+use crate::helper::{bot_actions, param_builders};
+use crate::helper::log::LogOp;
+use crate::helper::message_utils::get_command;
+use crate::helper::permission::{check_admin_right, check_member_admin_right};
+use crate::i18n::{Locale, callback_locale, message_locale, tr, tr_args};
+use crate::settings::store::{ChatSettings, SettingKey};
+
+pub const COMMAND_LIST: &[(&str, &str)] = &[
+    ("settings", "cmd.settings"),
+];
+
+pub const CALLBACK_NAMESPACE: &str = "set";
+
+pub struct SettingsHandler;
+
+impl Handler for SettingsHandler {
+    fn name(&self) -> &'static str { "settings" }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] { COMMAND_LIST }
+
+    fn help(&self) -> Option<&'static str> { Some("help.settings") }
+
+    fn handle_message(&self, ctx: Arc<Context>, msg: Arc<Message>, _kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        settings_handler(ctx, msg)
+    }
+
+    fn callback_namespace(&self) -> Option<&'static str> { Some(CALLBACK_NAMESPACE) }
+
+    fn handle_callback(&self, ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+        settings_callback_handler(ctx, query, data)
+    }
+}
+
+pub fn settings_handler(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
+    let fut = settings_handler_impl(ctx, msg);
+    Box::pin(fut)
+}
+
+async fn settings_handler_impl(ctx: Arc<Context>, msg: Arc<Message>) -> HandlerResult {
+    if get_command(&msg).is_none_or(|command| command != "settings") {
+        return Ok(std::ops::ControlFlow::Continue(()));
+    }
+    let locale = message_locale(&ctx, &msg);
+
+    // Same as monitor, only administrators can change settings in groups
+    if msg.chat.type_field != ChatType::Private && !check_admin_right(&ctx, &msg).await? {
+        bot_actions::send_reply_message(
+            &ctx.bot, msg.chat.id,
+            tr(locale, "common.admin_only_command"),
+            msg.message_id, None
+        ).await?;
+        return Ok(std::ops::ControlFlow::Break(()));
+    }
+
+    let settings = ctx.settings.get(msg.chat.id);
+    bot_actions::send_message_with_markup(
+        &ctx.bot, msg.chat.id,
+        settings_text(&ctx, &settings, locale),
+        ReplyMarkup::InlineKeyboardMarkup(settings_keyboard(&ctx, &settings, locale))
+    ).await?;
+
+    Ok(std::ops::ControlFlow::Break(()))
+}
+
+pub fn settings_callback_handler(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+    let fut = settings_callback_handler_impl(ctx, query, data);
+    Box::pin(fut)
+}
+
+async fn settings_callback_handler_impl(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> CallbackHandlerResult {
+    let locale = callback_locale(&ctx, &query);
+    let Some(msg) = callback_message(&query) else {
+        return Ok(CallbackAnswer::Alert(tr(locale, "common.message_too_old").to_string()));
+    };
+
+    if msg.chat.type_field != ChatType::Private &&
+        !check_member_admin_right(&ctx, msg.chat.id, query.from.id).await? {
+        return Ok(CallbackAnswer::Alert(tr(locale, "common.admin_only_action").to_string()));
+    }
+
+    let settings = match data.action.as_str() {
+        "tg" => {
+            let Some(Ok(key)) = data.arg(0).map(SettingKey::from_str) else {
+                return Ok(CallbackAnswer::Silent);
+            };
+            let global = key.global_value(&ctx.config);
+            ctx.settings.update(msg.chat.id, |settings| {
+                let value = !settings.get(key).unwrap_or(global);
+                // Back to default if same as config.json
+                settings.set(key, (value != global).then_some(value));
+            }).await
+        }
+        "lang" => {
+            // "auto" follows the language of each user
+            let language = data.arg(0).and_then(|arg| Locale::from_str(arg).ok());
+            ctx.settings.update(msg.chat.id, |settings| settings.language = language).await
+        }
+        "reset" => {
+            if ctx.settings.get(msg.chat.id).is_empty() {
+                return Ok(CallbackAnswer::Notice(tr(locale, "settings.already_default").to_string()));
+            }
+            ctx.settings.update(msg.chat.id, |settings| *settings = ChatSettings::default()).await
+        }
+        _ => return Ok(CallbackAnswer::Silent)
+    };
+
+    log::info!(
+        target: "chat_settings",
+        "{} Chat settings changed by {}: {:?}",
+        LogOp(msg), query.from.id, settings
+    );
+
+    // The language may be changed just now
+    let locale = settings.language.unwrap_or(locale);
+    let param = EditMessageTextParams::builder()
+        .chat_id(msg.chat.id)
+        .message_id(msg.message_id)
+        .text(settings_text(&ctx, &settings, locale))
+        .reply_markup(settings_keyboard(&ctx, &settings, locale))
+        .build();
+    ctx.bot.edit_message_text(&param).await?;
+
+    Ok(CallbackAnswer::Notice(tr(locale, "settings.updated").to_string()))
+}
+
+fn value_text(ctx: &Context, settings: &ChatSettings, key: SettingKey, locale: Locale) -> String {
+    let value = settings.get(key);
+    let state = if value.unwrap_or(key.global_value(&ctx.config)) {
+        tr(locale, "settings.on")
+    } else {
+        tr(locale, "settings.off")
+    };
+    if value.is_some() { state.to_string() } else { tr_args(locale, "settings.default_value", &[&state]) }
+}
+
+fn language_text(settings: &ChatSettings, locale: Locale) -> &'static str {
+    match settings.language {
+        Some(language) => language.name(),
+        None => tr(locale, "settings.language_auto")
+    }
+}
+
+fn settings_text(ctx: &Context, settings: &ChatSettings, locale: Locale) -> String {
+    let mut lines = vec![tr(locale, "settings.title").to_string()];
+    for key in SettingKey::ALL {
+        lines.push(format!(" - {}: {}", tr(locale, key.label()), value_text(ctx, settings, *key, locale)));
+    }
+    lines.push(format!(" - {}: {}", tr(locale, "settings.language"), language_text(settings, locale)));
+    lines.join("\n")
+}
+
+/// The language after the current one, cycling through automatic and all locales
+fn next_language(settings: &ChatSettings) -> &'static str {
+    let position = settings.language
+        .and_then(|language| Locale::ALL.iter().position(|locale| *locale == language));
+    let next = match position {
+        None => Locale::ALL.first(),
+        Some(position) => Locale::ALL.get(position + 1),
+    };
+    next.map(|locale| locale.as_str()).unwrap_or("auto")
+}
+
+fn settings_keyboard(ctx: &Context, settings: &ChatSettings, locale: Locale) -> InlineKeyboardMarkup {
+    let mut rows: Vec<_> = SettingKey::ALL.iter()
+        .map(|key| vec![callback_button(
+            format!("{}: {}", tr(locale, key.label()), value_text(ctx, settings, *key, locale)),
+            CALLBACK_NAMESPACE, "tg", &[key.as_str()]
+        )])
+        .collect();
+    rows.push(vec![callback_button(
+        format!("{}: {}", tr(locale, "settings.language"), language_text(settings, locale)),
+        CALLBACK_NAMESPACE, "lang", &[next_language(settings)]
+    )]);
+    rows.push(vec![callback_button(tr(locale, "settings.reset"), CALLBACK_NAMESPACE, "reset", &[])]);
+    param_builders::inline_keyboard(rows)
+}
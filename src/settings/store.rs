@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{BotConfig, ProgressConfig};
+use crate::i18n::Locale;
+use crate::kemono::config::KemonoConfig;
+use crate::pixiv::config::PixivConfig;
+use crate::storage::{Document, Migration, Storage, unchanged};
+
+struct ChatSettingsDocument;
+
+impl Document for ChatSettingsDocument {
+    type Data = HashMap<i64, ChatSettings>;
+
+    const NAME: &'static str = "chat_settings.json";
+    const MIGRATIONS: &'static [Migration] = &[unchanged];
+    const LEGACY_FILE: Option<&'static str> = Some("chat_settings.json");
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingKey {
+    PixivLinkDetection,
+    SpoilerNsfw,
+    SpoilerR18g,
+    KemonoLinkDetection,
+    FanboxLinkDetection,
+    ProgressReactions,
+}
+
+impl SettingKey {
+    pub const ALL: &[SettingKey] = &[
+        SettingKey::PixivLinkDetection,
+        SettingKey::SpoilerNsfw,
+        SettingKey::SpoilerR18g,
+        SettingKey::KemonoLinkDetection,
+        SettingKey::FanboxLinkDetection,
+        SettingKey::ProgressReactions,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettingKey::PixivLinkDetection => "pixiv_link",
+            SettingKey::SpoilerNsfw => "spoiler_nsfw",
+            SettingKey::SpoilerR18g => "spoiler_r18g",
+            SettingKey::KemonoLinkDetection => "kemono_link",
+            SettingKey::FanboxLinkDetection => "fanbox_link",
+            SettingKey::ProgressReactions => "progress_reactions",
+        }
+    }
+
+    /// Message key of the label
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingKey::PixivLinkDetection => "settings.pixiv_link",
+            SettingKey::SpoilerNsfw => "settings.spoiler_nsfw",
+            SettingKey::SpoilerR18g => "settings.spoiler_r18g",
+            SettingKey::KemonoLinkDetection => "settings.kemono_link",
+            SettingKey::FanboxLinkDetection => "settings.fanbox_link",
+            SettingKey::ProgressReactions => "settings.progress_reactions",
+        }
+    }
+
+    /// The value in config.json
+    pub fn global_value(&self, config: &BotConfig) -> bool {
+        match self {
+            SettingKey::PixivLinkDetection => config.pixiv.enable_pixiv_link_detection,
+            SettingKey::SpoilerNsfw => config.pixiv.spoiler_nsfw,
+            SettingKey::SpoilerR18g => config.pixiv.spoiler_r18g,
+            SettingKey::KemonoLinkDetection => config.kemono.enable_kemono_link_detection,
+            SettingKey::FanboxLinkDetection => config.kemono.enable_fanbox_link_detection,
+            SettingKey::ProgressReactions => config.progress.reactions,
+        }
+    }
+}
+
+impl FromStr for SettingKey {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SettingKey::ALL.iter()
+            .find(|key| key.as_str() == s)
+            .copied()
+            .ok_or(())
+    }
+}
+
+/// Overrides of a chat, None means following config.json
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixiv_link_detection: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spoiler_nsfw: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spoiler_r18g: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kemono_link_detection: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fanbox_link_detection: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress_reactions: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<Locale>,
+}
+
+impl ChatSettings {
+    pub fn get(&self, key: SettingKey) -> Option<bool> {
+        match key {
+            SettingKey::PixivLinkDetection => self.pixiv_link_detection,
+            SettingKey::SpoilerNsfw => self.spoiler_nsfw,
+            SettingKey::SpoilerR18g => self.spoiler_r18g,
+            SettingKey::KemonoLinkDetection => self.kemono_link_detection,
+            SettingKey::FanboxLinkDetection => self.fanbox_link_detection,
+            SettingKey::ProgressReactions => self.progress_reactions,
+        }
+    }
+
+    pub fn set(&mut self, key: SettingKey, value: Option<bool>) {
+        let field = match key {
+            SettingKey::PixivLinkDetection => &mut self.pixiv_link_detection,
+            SettingKey::SpoilerNsfw => &mut self.spoiler_nsfw,
+            SettingKey::SpoilerR18g => &mut self.spoiler_r18g,
+            SettingKey::KemonoLinkDetection => &mut self.kemono_link_detection,
+            SettingKey::FanboxLinkDetection => &mut self.fanbox_link_detection,
+            SettingKey::ProgressReactions => &mut self.progress_reactions,
+        };
+        *field = value;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == ChatSettings::default()
+    }
+
+    pub fn apply_pixiv(&self, config: &PixivConfig) -> PixivConfig {
+        let mut config = config.clone();
+        config.enable_pixiv_link_detection = self.pixiv_link_detection.unwrap_or(config.enable_pixiv_link_detection);
+        config.spoiler_nsfw = self.spoiler_nsfw.unwrap_or(config.spoiler_nsfw);
+        config.spoiler_r18g = self.spoiler_r18g.unwrap_or(config.spoiler_r18g);
+        config
+    }
+
+    pub fn apply_kemono(&self, config: &KemonoConfig) -> KemonoConfig {
+        let mut config = config.clone();
+        config.enable_kemono_link_detection = self.kemono_link_detection.unwrap_or(config.enable_kemono_link_detection);
+        config.enable_fanbox_link_detection = self.fanbox_link_detection.unwrap_or(config.enable_fanbox_link_detection);
+        config
+    }
+}
+
+/// Per-chat settings, saved in the store
+#[derive(Debug)]
+pub struct SettingsStore {
+    map: DashMap<i64, ChatSettings>,
+    storage: Arc<Storage>,
+}
+
+impl SettingsStore {
+    pub fn load(storage: Arc<Storage>) -> SettingsStore {
+        let mut map = DashMap::new();
+        match storage.load::<ChatSettingsDocument>() {
+            Ok(saved) => map.extend(saved.unwrap_or_default()),
+            Err(e) => log::warn!(target: "chat_settings", "Failed to load chat settings: {e}"),
+        }
+        SettingsStore { map, storage }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn get(&self, chat_id: i64) -> ChatSettings {
+        self.map.get(&chat_id).map(|settings| settings.clone()).unwrap_or_default()
+    }
+
+    /// Update the settings of the chat and save to file
+    pub async fn update(&self, chat_id: i64, f: impl FnOnce(&mut ChatSettings)) -> ChatSettings {
+        let settings = {
+            let mut settings = self.map.entry(chat_id).or_default();
+            f(&mut settings);
+            settings.clone()
+        };
+        if settings.is_empty() {
+            self.map.remove_if(&chat_id, |_, settings| settings.is_empty());
+        }
+        self.save().await;
+        settings
+    }
+
+    /// PixivConfig with the overrides of the chat
+    pub fn pixiv_config(&self, chat_id: i64, config: &PixivConfig) -> PixivConfig {
+        self.get(chat_id).apply_pixiv(config)
+    }
+
+    /// KemonoConfig with the overrides of the chat
+    pub fn kemono_config(&self, chat_id: i64, config: &KemonoConfig) -> KemonoConfig {
+        self.get(chat_id).apply_kemono(config)
+    }
+
+    /// Whether the chat shows job progress as reactions
+    pub fn progress_reactions(&self, chat_id: i64, config: &ProgressConfig) -> bool {
+        self.get(chat_id).progress_reactions.unwrap_or(config.reactions)
+    }
+
+    async fn save(&self) {
+        let snapshot = || self.map.iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        if let Err(e) = self.storage.save::<ChatSettingsDocument>(snapshot).await {
+            log::warn!(target: "chat_settings", "Failed to save chat settings: {e}");
+        }
+    }
+}
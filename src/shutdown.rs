@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::context::Context;
+use crate::jobs::janitor::clear_temp_dir;
+use crate::monitor::context::MonitorRulesDocument;
+
+/// Time for cancelled jobs to stop after the shutdown deadline
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Wait for SIGINT or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!(target: "shutdown", "Failed to listen for SIGINT: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => { signal.recv().await; }
+            Err(e) => {
+                log::error!(target: "shutdown", "Failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Wait for running jobs within the configured deadline, then flush state and clean up temp files
+pub async fn graceful_shutdown(ctx: Arc<Context>) {
+    ctx.tasks.close();
+    log::info!(target: "shutdown", "Waiting for {} running tasks to finish...", ctx.tasks.len());
+
+    let deadline = Duration::from_secs(ctx.config.shutdown_timeout);
+    let mut finished = tokio::time::timeout(deadline, ctx.tasks.wait()).await.is_ok();
+    if !finished {
+        let cancelled = ctx.jobs.cancel(|_| true);
+        log::warn!(
+            target: "shutdown",
+            "{} tasks are still running after {} seconds, {} jobs cancelled",
+            ctx.tasks.len(), ctx.config.shutdown_timeout, cancelled
+        );
+        finished = tokio::time::timeout(CANCEL_GRACE_PERIOD, ctx.tasks.wait()).await.is_ok();
+    }
+
+    if let Err(e) = ctx.storage.save::<MonitorRulesDocument>(|| ctx.monitor.ruleset.snapshot()).await {
+        log::error!(target: "shutdown", "Failed to save monitor rules: {e}");
+    }
+
+    ctx.modal_states.save().await;
+    ctx.upload_cache.save().await;
+
+    // Jobs still writing into temp would fail under their feet, the leftovers are cleared on the next start
+    if finished {
+        clear_temp_dir(&ctx.temp_root_path);
+    } else {
+        log::warn!(target: "shutdown", "{} tasks are still running, temp directory is kept", ctx.tasks.len());
+    }
+    log::info!(target: "shutdown", "Bot stopped");
+}
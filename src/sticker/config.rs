@@ -1,9 +1,19 @@
 use serde::Deserialize;
 
+use crate::config::TelegramConfig;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct StickerConfig {
-    #[serde(default = "size_limit_kb_default")]
-    pub size_limit_kb: u64
+    /// Largest file to be converted, 16 MiB if absent, or the download limit with a local Bot API server
+    pub size_limit_kb: Option<u64>
 }
 
-fn size_limit_kb_default() -> u64 { 16384 }
\ No newline at end of file
+impl StickerConfig {
+    pub fn size_limit_kb(&self, telegram: &TelegramConfig) -> u64 {
+        let default = match telegram.local_mode {
+            true => telegram.download_limit() / 1024,
+            false => 16384,
+        };
+        self.size_limit_kb.unwrap_or(default)
+    }
+}
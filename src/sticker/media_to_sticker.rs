@@ -9,6 +9,7 @@ use crate::helper::download::{download_telegram_file_to_path, get_telegram_file_
 use crate::helper::log::LogOp;
 use crate::helper::{bot_actions, param_builders};
 use crate::context::Context;
+use crate::i18n::{message_locale, tr, tr_args};
 use crate::types::FileName;
 
 pub async fn document_to_sticker_processor(
@@ -57,7 +58,7 @@ pub async fn photo_to_sticker_processor(
             "{} Failed to select a photo", 
             LogOp(&msg)
         );
-        bot_actions::send_message(&ctx.bot, msg.chat.id, "似乎沒有看到有圖片或動圖呢……").await?;
+        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(message_locale(&ctx, msg), "sticker.no_media")).await?;
     }
 
     Ok(())
@@ -103,11 +104,12 @@ async fn file_to_sticker_processor(
     file_id: String,
     media_file_name: Option<String>,
 ) -> anyhow::Result<()> {
+    let locale = message_locale(&ctx, msg);
 
     let file = match get_telegram_file_info(&ctx.bot, &file_id).await {
         Ok(Some(x)) => x,
         Ok(None) => {
-            bot_actions::send_message(&ctx.bot, msg.chat.id, "好像找不到那個文件呢……").await?;
+            bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.file_not_found")).await?;
             return Ok(());
         }
         Err(e) => {
@@ -116,7 +118,7 @@ async fn file_to_sticker_processor(
                 "Failed to get file info with file_id {}: {e}", 
                 file_id
             );
-            bot_actions::send_message(&ctx.bot, msg.chat.id, "獲取文件信息失敗惹……").await?;
+            bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.file_info_failed")).await?;
             return Ok(());
         }
     };
@@ -137,22 +139,19 @@ async fn file_to_sticker_processor(
         false
     } else {
         bot_actions::send_message(&ctx.bot, msg.chat.id, 
-            format!(
-                "目前只支援 {} 格式的圖片和 {} 格式的動圖呢……", 
-                STATIC_SOURCE_FORMAT.join(" "),
-                VIDEO_SOURCE_FORMAT.join(" ")
-            )
+            tr_args(locale, "sticker.unsupported_format", &[
+                &STATIC_SOURCE_FORMAT.join(" "),
+                &VIDEO_SOURCE_FORMAT.join(" ")
+            ])
         ).await?;
         return Ok(());
     };
 
     // Size limit
-    if file.file_size > ctx.config.sticker.size_limit_kb * 1024 {
+    let size_limit_kb = ctx.config.sticker.size_limit_kb(&ctx.config.telegram);
+    if file.file_size > size_limit_kb * 1024 {
         bot_actions::send_message(&ctx.bot, msg.chat.id, 
-            format!(
-                "目前只支持最大 {} KiB 的文件呢……", 
-                ctx.config.sticker.size_limit_kb
-            )
+            tr_args(locale, "sticker.size_limit", &[&size_limit_kb])
         ).await?;
         return Ok(());
     }
@@ -170,7 +169,7 @@ async fn file_to_sticker_processor(
             "Failed to download file from path {}: {e}", 
             file.file_path
         );
-        bot_actions::send_message(&ctx.bot, msg.chat.id, "下載文件失敗惹……").await?;
+        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "common.download_failed")).await?;
         return Ok(());
     }
 
@@ -206,7 +205,7 @@ async fn file_to_sticker_processor(
         .spawn()?
         .wait().await?;
     if !conversion.success() {
-        bot_actions::send_message(&ctx.bot, msg.chat.id, "文件轉碼失敗惹……").await?;
+        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "common.transcode_failed")).await?;
         return Ok(())
     }
 
@@ -224,7 +223,7 @@ async fn file_to_sticker_processor(
         .build();
     ctx.bot.send_sticker(&send_sticker_param).await?;
 
-    bot_actions::send_message(&ctx.bot, msg.chat.id, "轉換完成啦～\n您可以繼續發送要轉換的貼紙～\n如果要退出，請點擊指令 -> /exit").await?;
+    bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.convert_done")).await?;
     
     Ok(())
 }
\ No newline at end of file
@@ -7,22 +7,25 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use frankenstein::methods::SendChatActionParams;
-use frankenstein::types::ChatType;
+use frankenstein::types::{CallbackQuery, ChatType, ReplyMarkup};
 use frankenstein::types::Message;
 use frankenstein::AsyncTelegramApi;
 use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
 
-use crate::handler::HandlerResult;
+use crate::callback::{CallbackAnswer, CallbackData, callback_button, callback_message, remove_callback_button};
+use crate::handler::{CallbackHandlerResult, Handler, HandlerResult, UpdateKind};
 use crate::handler::ModalHandlerResult;
-use crate::helper::bot_actions;
+use crate::helper::{bot_actions, param_builders};
 use crate::helper::log::LogOp;
 use crate::helper::message_utils;
 use crate::context::{Context, ModalState};
+use crate::i18n::{Locale, callback_locale, message_locale, tr};
 use crate::sticker::media_to_sticker::{animation_to_sticker_processor, document_to_sticker_processor, photo_to_sticker_processor, video_to_sticker_processor};
 use crate::sticker::sticker_set_download::sticker_set_download_processor;
 use crate::sticker::sticker_to_media::sticker_to_media_processor;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum StickerModalState {
     StickerConvert,
     StickerSetDownload
@@ -47,10 +50,43 @@ impl FromStr for StickerCommand {
 }
 
 pub const COMMAND_LIST: &[(&'static str, &'static str)] = &[
-    ("sticker_convert", "轉換貼紙、圖片和動圖"),
-    ("sticker_set_download", "下載貼紙包")
+    ("sticker_convert", "cmd.sticker_convert"),
+    ("sticker_set_download", "cmd.sticker_set_download")
 ];
 
+pub struct StickerHandler;
+
+impl Handler for StickerHandler {
+    fn name(&self) -> &'static str { "sticker" }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] { COMMAND_LIST }
+
+    fn help(&self) -> Option<&'static str> { Some("help.sticker") }
+
+    fn handle_message(&self, ctx: Arc<Context>, msg: Arc<Message>, _kind: UpdateKind) -> BoxFuture<'static, HandlerResult> {
+        sticker_handler(ctx, msg)
+    }
+
+    fn owns_modal_state(&self, state: &ModalState) -> bool {
+        matches!(state, ModalState::Sticker(_))
+    }
+
+    fn handle_modal(&self, ctx: Arc<Context>, msg: Arc<Message>, state: ModalState) -> BoxFuture<'static, ModalHandlerResult> {
+        Box::pin(async move {
+            let ModalState::Sticker(state) = state else {
+                return Ok(());
+            };
+            sticker_modal_handler(ctx, msg, state).await
+        })
+    }
+
+    fn callback_namespace(&self) -> Option<&'static str> { Some(CALLBACK_NAMESPACE) }
+
+    fn handle_callback(&self, ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+        sticker_callback_handler(ctx, query, data)
+    }
+}
+
 pub fn sticker_handler(ctx: Arc<Context>, msg: Arc<Message>) -> BoxFuture<'static, HandlerResult> {
     let fut = sticker_handler_impl(ctx, msg);
     return Box::pin(fut);
@@ -68,9 +104,10 @@ async fn sticker_handler_impl(
     let Ok(command) = StickerCommand::from_str(&command) else {
         return Ok(std::ops::ControlFlow::Continue(()));
     };
+    let locale = message_locale(&ctx, &msg);
 
     if msg.chat.type_field != ChatType::Private {
-        bot_actions::send_message(&ctx.bot, msg.chat.id, "貼紙指令只能在私聊中使用哦——").await?;
+        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.private_only")).await?;
         return Ok(std::ops::ControlFlow::Break(()));
     }
 
@@ -87,7 +124,11 @@ async fn sticker_handler_impl(
                 LogOp(&msg)
             );
 
-            bot_actions::send_message(&ctx.bot, msg.chat.id, "請發送想要轉換的貼紙、圖片或動圖～\n如果要退出，請點擊指令 -> /exit").await?;
+            bot_actions::send_message_with_markup(
+                &ctx.bot, msg.chat.id, 
+                tr(locale, "sticker.convert_prompt"),
+                exit_button_markup(locale)
+            ).await?;
         }
         StickerCommand::StickerSetDownload => {
             ctx.modal_states.set_state(
@@ -101,18 +142,61 @@ async fn sticker_handler_impl(
                 LogOp(&msg)
             );
 
-            bot_actions::send_message(&ctx.bot, msg.chat.id, "請發送一張想要下載的貼紙包中的貼紙～\n如果要退出，請點擊指令 -> /exit").await?;
+            bot_actions::send_message_with_markup(
+                &ctx.bot, msg.chat.id, 
+                tr(locale, "sticker.set_download_prompt"),
+                exit_button_markup(locale)
+            ).await?;
         }
     }
 
     return Ok(std::ops::ControlFlow::Break(()));
 }
 
+pub const CALLBACK_NAMESPACE: &str = "stk";
+
+fn exit_button_markup(locale: Locale) -> ReplyMarkup {
+    let button = callback_button(tr(locale, "sticker.exit_button"), CALLBACK_NAMESPACE, "exit", &[]);
+    ReplyMarkup::InlineKeyboardMarkup(param_builders::inline_keyboard(vec![vec![button]]))
+}
+
+pub fn sticker_callback_handler(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> BoxFuture<'static, CallbackHandlerResult> {
+    let fut = sticker_callback_handler_impl(ctx, query, data);
+    Box::pin(fut)
+}
+
+async fn sticker_callback_handler_impl(ctx: Arc<Context>, query: Arc<CallbackQuery>, data: CallbackData) -> CallbackHandlerResult {
+    let Some(msg) = callback_message(&query) else {
+        return Ok(CallbackAnswer::Silent);
+    };
+
+    match data.action.as_str() {
+        "exit" => {
+            // Sticker commands are private chat only, the sender is the user pressing the button
+            let state = ctx.modal_states.release_state((msg.chat.id, query.from.id as i64)).await;
+            remove_callback_button(&ctx, msg, &CallbackData::encode(CALLBACK_NAMESPACE, "exit", &[])).await?;
+
+            if matches!(state, Some(ModalState::Sticker(_))) {
+                log::info!(
+                    target: "sticker_command",
+                    "{} Exited sticker mode by button", 
+                    LogOp(msg)
+                );
+                Ok(CallbackAnswer::Notice(tr(callback_locale(&ctx, &query), "sticker.exited").to_string()))
+            } else {
+                Ok(CallbackAnswer::Silent)
+            }
+        }
+        _ => Ok(CallbackAnswer::Silent)
+    }
+}
+
 pub async fn sticker_modal_handler(
     ctx: Arc<Context>,
     msg: Arc<Message>,
     state: StickerModalState
 ) -> ModalHandlerResult {
+    let locale = message_locale(&ctx, &msg);
     match state {
         StickerModalState::StickerConvert => {
             bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::Typing).await?;
@@ -129,14 +213,14 @@ pub async fn sticker_modal_handler(
             } else if let Some(video) = msg.video.as_ref() {
                 video_to_sticker_processor(ctx.clone(), &msg, video).await?;
             } else {
-                bot_actions::send_message(&ctx.bot, msg.chat.id, "請發送想要轉換的貼紙、圖片或動圖～\n如果要退出，請點擊指令 -> /exit").await?;
+                bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.convert_prompt")).await?;
             }
         },
         StickerModalState::StickerSetDownload => {
             if let Some(sticker) = msg.sticker.as_ref() {
                 sticker_set_download_processor(ctx.clone(), &msg, sticker).await?;
             } else {
-                bot_actions::send_message(&ctx.bot, msg.chat.id, "請發送一張想要下載的貼紙包中的貼紙～\n如果要退出，請點擊指令 -> /exit").await?;
+                bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.set_download_prompt")).await?;
             }
         },
     }
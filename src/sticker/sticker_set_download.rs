@@ -1,32 +1,34 @@
-use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
 
 use frankenstein::AsyncTelegramApi;
 use frankenstein::stickers::Sticker;
 use frankenstein::types::Message;
-use frankenstein::methods::{GetStickerSetParams, SendDocumentParams};
-use tokio::sync::Mutex;
-use zip::write::SimpleFileOptions;
-use zip::CompressionMethod;
+use frankenstein::methods::GetStickerSetParams;
+use futures::StreamExt;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
 
-use crate::helper::download::{download_telegram_file_to_path, get_telegram_file_info};
+use crate::helper::download::{DownloadProgress, DownloadTask, get_telegram_file_info, telegram_file_task};
 use crate::helper::log::LogOp;
-use crate::helper::{bot_actions, param_builders};
+use crate::helper::make_archive::{ArchiveEntry, make_archives, send_archives};
+use crate::helper::progress::ProgressReporter;
+use crate::helper::bot_actions;
 use crate::context::Context;
+use crate::i18n::{message_locale, tr, tr_args};
+use crate::jobs::start_job;
+use crate::jobs::janitor::job_temp_dir;
+use crate::jobs::scheduler::JobKind;
 use crate::types::FileName;
 
+/// Rough size of a sticker file, to check the temp quota before downloading
+const ESTIMATED_STICKER_SIZE: u64 = 300_000;
+
 #[derive(Debug, Clone)]
 struct StickerDownloadTask {
     name_suffix: String,
     file_id: String,
 }
-#[derive(Debug, Clone)]
-struct StickerDownloadResult {
-    file_name: String,
-    path: PathBuf
-}
 
 pub async fn sticker_set_download_processor(
     ctx: Arc<Context>,
@@ -38,10 +40,11 @@ pub async fn sticker_set_download_processor(
         "{} Requested sticker set download", 
         LogOp(&msg)
     );
+    let locale = message_locale(&ctx, msg);
     let set_name = match sticker.set_name.clone() {
         Some(x) => x,
         None => {
-            bot_actions::send_message(&ctx.bot, msg.chat.id, "這張貼紙不屬於任何貼紙包呢……").await?;
+            bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.not_in_set")).await?;
             return Ok(());
         }
     };
@@ -55,7 +58,7 @@ pub async fn sticker_set_download_processor(
         Ok(set) => set.result,
         Err(e) => {
             log::warn!("Get sticker set failed: {}", e);
-            bot_actions::send_message(&ctx.bot, msg.chat.id, "似乎找不到那個貼紙包呢……").await?;
+            bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.set_not_found")).await?;
             return Ok(());
         }
     };
@@ -66,9 +69,15 @@ pub async fn sticker_set_download_processor(
         LogOp(&msg), set.name
     );
 
-    let temp_dir = tempfile::tempdir_in(&ctx.temp_root_path)?;
-    // Allocate mission queue
-    let mut task_queue: VecDeque<StickerDownloadTask> = set.stickers
+    // The converted stickers and the zip file are kept besides the downloads
+    let expected_size = set.stickers.len() as u64 * ESTIMATED_STICKER_SIZE * 3;
+    let Some(job) = start_job(&ctx, msg, JobKind::StickerSet, set.name.clone(), expected_size).await? else {
+        return Ok(());
+    };
+
+    let temp_dir = job_temp_dir(&ctx, &job)?;
+    // Allocate mission list
+    let mut stickers: Vec<StickerDownloadTask> = set.stickers
         .into_iter()
         .enumerate()
         .map(|(i, sticker)| StickerDownloadTask { 
@@ -76,76 +85,49 @@ pub async fn sticker_set_download_processor(
         })
         .collect();
     if let Some(thumbnail) = set.thumbnail {
-        task_queue.push_back(StickerDownloadTask { 
+        stickers.push(StickerDownloadTask { 
             name_suffix: "thumbnail".to_string(), 
             file_id: thumbnail.file_id }
         );
     }
-    let sticker_count = task_queue.len();
-    let task_queue: Arc<Mutex<VecDeque<StickerDownloadTask>>> = Arc::new(Mutex::new(task_queue));
-    let completed = Arc::new(Mutex::new(Vec::<StickerDownloadResult>::new()));
-    // Concurrent download stickers
+    let sticker_count = stickers.len();
 
-    let mut join_handle_list = vec![];
-    const WORKER_COUNT: usize = 8;
-    for worker_id in 0..WORKER_COUNT {
-        let ctx_cloned = ctx.clone();
-        let queue_cloned = task_queue.clone();
-        let completed_cloned = completed.clone();
-        let set_name_cloned = set_name.clone();
-        let path = temp_dir.path().to_path_buf();
-        join_handle_list.push(tokio::spawn(async move {
-            sticker_download_worker(
-                ctx_cloned,
-                worker_id,
-                path,
-                set_name_cloned,
-                queue_cloned,
-                completed_cloned,
-            ).await;
-        }))
-    }
-
-
-    let mut progress_text = format!("開始下載貼紙…… (共 {} 張）", sticker_count);
-    let progress_message = bot_actions::send_message(&ctx.bot, msg.chat.id, &progress_text).await?;
-    loop {
-        if join_handle_list.iter().map(|h| h.is_finished()).all(|s| s == true) {
-            break;
-        }
-        tokio::time::sleep(Duration::from_secs(1)).await;
-        let count = completed.lock().await.len();
+    let mut reporter = ProgressReporter::new(ctx.clone(), msg, &job, tr(locale, "sticker.set_download_title"));
 
-        log::info!(
-            target: "sticker_set_download",
-            "{} Downloading {} ({}/{})", 
-            LogOp(&msg), set.name, count, sticker_count
-        );
-
-        let new_text = format!("正在下載貼紙…… ({}/{})", count, sticker_count);
-        if new_text != progress_text {
-            progress_text = new_text;
-            bot_actions::edit_message_text(&ctx.bot, msg.chat.id, progress_message.message_id, &progress_text).await?;
-        }
-    }
+    // Resolve the file paths, the file extension is only known from them
+    const WORKER_COUNT: usize = 8;
+    let tasks: Vec<Option<DownloadTask>> = futures::stream::iter(0..sticker_count)
+        .map(|index| sticker_download_task(&ctx, temp_dir.path(), &set_name, &stickers[index], job.token()))
+        .buffered(WORKER_COUNT)
+        .collect()
+        .await;
+    let tasks: Vec<DownloadTask> = tasks.into_iter().flatten().collect();
+    job.check()?;
 
-    let completed = completed.lock().await.clone();
+    // Concurrent download stickers
+    let client = &ctx.http.telegram_files;
+    let (progress_sender, mut progress) = watch::channel(DownloadProgress::default());
+    let on_progress = |progress| { progress_sender.send_replace(progress); };
+    let download = ctx.downloader.download_all(client, &tasks, WORKER_COUNT, job.token(), &on_progress);
+    let report = reporter.track(download, &mut progress).await;
+    reporter.finish().await;
+    job.check()?;
+
+    report.log_failures("sticker_set_download");
+    let completed: Vec<ArchiveEntry> = report.completed.into_iter()
+        .map(|download| ArchiveEntry::new(tasks[download.index].label(), download.save_path))
+        .collect();
 
-    if completed.len() == sticker_count {
-        bot_actions::edit_message_text(
-            &ctx.bot, msg.chat.id, progress_message.message_id, 
-            "貼紙下載完成了～"
-        ).await?;
-    } else {
+    if completed.len() != sticker_count {
         let fail_count = sticker_count - completed.len();
         log::warn!(
             target: "sticker_set_download",
             "{} Incomplete sticker set {} download: {}/{} downloaded, {} failed", 
             LogOp(&msg), set.name, completed.len(), sticker_count, fail_count
         );
-        bot_actions::edit_message_text(
-            &ctx.bot, msg.chat.id, progress_message.message_id, 
-            format!("貼紙下載完成了～ ({} 張貼紙下載失敗)", fail_count)
+        bot_actions::send_reply_message(
+            &ctx.bot, msg.chat.id, tr_args(locale, "sticker.set_download_partial", &[&fail_count]),
+            msg.message_id, None
         ).await?;
     }
 
@@ -155,127 +137,57 @@ pub async fn sticker_set_download_processor(
         LogOp(&msg), set.name
     );
 
-    let archive_file_name = format!("{}.zip", set_name);
-    let archive_path = temp_dir.path().join(&archive_file_name);
-    let archive_path_clone = archive_path.clone();
-    // Blocking zip and write operation
-    let archiving_task = tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
-        let archive_file = std::fs::File::create(archive_path_clone)?;
-        let mut archive = zip::ZipWriter::new(archive_file);
-        let options = SimpleFileOptions::default()
-            .compression_method(CompressionMethod::Stored)
-            .unix_permissions(0o755);
-        for result in completed {
-            let mut source_file = match std::fs::File::open(&result.path) {
-                Ok(f) => f,
-                Err(e) => {
-                    log::warn!(
-                        target: "sticker_set_download archiver",
-                        "Failed to open downloaded file {} for archiving: {}", 
-                        result.path.to_string_lossy(), e
-                    );
-                    continue;
-                }
-            };
-
-            archive.start_file(result.file_name, options)?;
-            std::io::copy(&mut source_file, &mut archive)?;
-        }
-        archive.finish()?;
-        Ok(())
-    });
-    
-    // Notice, JoinError is passed up by "?"
-    if let Err(e) = archiving_task.await? {
-        log::warn!(
-            target: "sticker_set_download",
-            "{} Failed to archive file {}: {}", 
-            LogOp(&msg), archive_file_name, e
-        );
-        return Ok(())
+    let parts = make_archives(completed, temp_dir.path(), &set_name, ctx.config.archive.part_size_bytes(&ctx.config.telegram)).await?;
+    if !parts.split.is_empty() {
+        bot_actions::send_reply_message(
+            &ctx.bot, msg.chat.id, tr_args(locale, "archive.split", &[&parts.split.len()]), msg.message_id, None
+        ).await?;
     }
 
     log::info!(
         target: "sticker_set_download",
-        "{} Sticker set name: {}, upolading archive...", 
-        LogOp(&msg), set.name
+        "{} Sticker set name: {}, upolading archive ({} parts)...", 
+        LogOp(&msg), set.name, parts.paths.len()
     );
+    send_archives(&ctx, msg, &parts.paths, None, Some(&job)).await?;
 
-    bot_actions::sent_chat_action(&ctx.bot, msg.chat.id, frankenstein::types::ChatAction::UploadDocument).await?;
-
-    let send_document_param = SendDocumentParams::builder()
-        .chat_id(msg.chat.id)
-        .document(archive_path)
-        .reply_parameters(param_builders::reply_parameters(msg.message_id, Some(msg.chat.id)))
-        .build();
-    ctx.bot.send_document(&send_document_param).await?;
-
-    bot_actions::send_message(&ctx.bot, msg.chat.id, "下載完成啦～\n您可以繼續發送要下載的貼紙包～\n如果要退出，請點擊指令 -> /exit").await?;
+    bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.set_download_done")).await?;
 
     Ok(())
 }
 
-async fn sticker_download_worker(
-    ctx: Arc<Context>,
-    worker_id: usize,
-    save_dir_path: PathBuf,
-    set_name: String,
-    queue: Arc<Mutex<VecDeque<StickerDownloadTask>>>,
-    completed: Arc<Mutex<Vec<StickerDownloadResult>>>
-) {
-    loop {
-        let task = {
-            let mut guard = queue.lock().await;
-            guard.pop_front()
-        };
-        
-        let Some(task) = task else {
-            // Tasks are empty now
-            return;
-        };
-        
-        let file = match get_telegram_file_info(&ctx.bot, &task.file_id).await {
-            Ok(Some(x)) => x,
-            Ok(None) => {
-                log::warn!(
-                    target: &format!("sticker_set_download worker#{}", worker_id),
-                    "Sticker file info is empty, #{} (file_id: {})",
-                    task.name_suffix, task.file_id
-                );
-                continue;
-            }
-            Err(e) => {
-                log::warn!(
-                    target: &format!("sticker_set_download worker#{}", worker_id),
-                    "Failed to get sticker file info #{} (file_id: {}): {}",
-                    task.name_suffix, task.file_id, e
-                );
-                continue;
-            }
-        };
-
-        let file_name = format!("{}_{}.{}", set_name, task.name_suffix, FileName::from(file.file_name).extension_str());
-        let save_path = save_dir_path.join(&file_name);
-        
-        if let Err(e) = download_telegram_file_to_path(
-            &ctx,
-            &file.file_path, 
-            &save_path,
-        ).await {
+/// Build the download of the sticker, None if the file info is not available or cancelled
+async fn sticker_download_task(
+    ctx: &Context,
+    save_dir_path: &Path,
+    set_name: &str,
+    task: &StickerDownloadTask,
+    cancel: &CancellationToken,
+) -> Option<DownloadTask> {
+    let result = tokio::select! {
+        result = get_telegram_file_info(&ctx.bot, &task.file_id) => result,
+        _ = cancel.cancelled() => return None,
+    };
+    let file = match result {
+        Ok(Some(x)) => x,
+        Ok(None) => {
             log::warn!(
-                target: &format!("sticker_set_download worker#{}", worker_id),
-                "Failed to download sticker file #{} (file_id: {}) to {}: {}",
-                task.name_suffix, task.file_id, save_path.to_string_lossy(), e
+                target: "sticker_set_download",
+                "Sticker file info is empty, #{} (file_id: {})",
+                task.name_suffix, task.file_id
             );
-            continue;
+            return None;
         }
-
-        {
-            let mut guard = completed.lock().await;
-            guard.push(StickerDownloadResult {
-                file_name,
-                path: save_path
-            });
+        Err(e) => {
+            log::warn!(
+                target: "sticker_set_download",
+                "Failed to get sticker file info #{} (file_id: {}): {}",
+                task.name_suffix, task.file_id, e
+            );
+            return None;
         }
-    }
-}
\ No newline at end of file
+    };
+
+    let file_name = format!("{}_{}.{}", set_name, task.name_suffix, FileName::from(file.file_name).extension_str());
+    Some(telegram_file_task(ctx, &file.file_path, save_dir_path.join(file_name)))
+}
@@ -13,6 +13,7 @@ use crate::helper::download::get_telegram_file_info;
 use crate::helper::log::LogOp;
 use crate::helper::{bot_actions, param_builders};
 use crate::context::Context;
+use crate::i18n::{message_locale, tr};
 use crate::types::FileName;
 
 pub async fn sticker_to_media_processor(
@@ -26,11 +27,12 @@ pub async fn sticker_to_media_processor(
         "{} Requested sticker to media conversion", 
         LogOp(&msg)
     );
+    let locale = message_locale(&ctx, msg);
 
     let file = match get_telegram_file_info(&ctx.bot, &sticker.file_id).await {
         Ok(Some(x)) => x,
         Ok(None) => {
-            bot_actions::send_message(&ctx.bot, msg.chat.id, "好像找不到那個文件呢……").await?;
+            bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.file_not_found")).await?;
             return Ok(())
         }
         Err(e) => {
@@ -39,7 +41,7 @@ pub async fn sticker_to_media_processor(
                 "Failed to get file info with file_id {}: {e}", 
                 sticker.file_id
             );
-            bot_actions::send_message(&ctx.bot, msg.chat.id, "獲取文件信息失敗惹……").await?;
+            bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.file_info_failed")).await?;
             return Ok(());
         }
     };
@@ -51,7 +53,7 @@ pub async fn sticker_to_media_processor(
     } else if base_ext.extension_str().to_lowercase() == "webp" {
         false
     } else {
-        bot_actions::send_message(&ctx.bot, msg.chat.id, "現在還不支援 WebP 和 WebM 格式外的貼紙哦……").await?;
+        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.unsupported_sticker")).await?;
         return Ok(());
     };
 
@@ -71,7 +73,7 @@ pub async fn sticker_to_media_processor(
             "Failed to download file from path {}: {e}", 
             file.file_path
         );
-        bot_actions::send_message(&ctx.bot, msg.chat.id, "下載文件失敗惹……").await?;
+        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "common.download_failed")).await?;
         return Ok(());
     }
 
@@ -99,7 +101,7 @@ pub async fn sticker_to_media_processor(
         .spawn()?
         .wait().await?;
     if !conversion.success() {
-        bot_actions::send_message(&ctx.bot, msg.chat.id, "文件轉碼失敗惹……").await?;
+        bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "common.transcode_failed")).await?;
         return Ok(())
     }
 
@@ -169,7 +171,7 @@ pub async fn sticker_to_media_processor(
         ctx.bot.send_photo(&send_photo_param).await?;
     }
     
-    bot_actions::send_message(&ctx.bot, msg.chat.id, "轉換完成啦～\n您可以繼續發送要轉換的貼紙～\n如果要退出，請點擊指令 -> /exit").await?;
+    bot_actions::send_message(&ctx.bot, msg.chat.id, tr(locale, "sticker.convert_done")).await?;
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,292 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Documents are kept in this directory under the data directory
+const STORE_DIR_NAME: &str = "store";
+
+/// Upgrades the data of a document by one version
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// A JSON document in the store, saved as `{"version": 2, "data": ...}`
+pub trait Document {
+    type Data: Serialize + DeserializeOwned;
+
+    /// File name in the store directory
+    const NAME: &'static str;
+    /// The migration at index `n` upgrades version `n` to `n + 1`, the current version is the count.
+    /// Version 0 is the format before the store, imported from `LEGACY_FILE`
+    const MIGRATIONS: &'static [Migration];
+    /// File in the data directory imported once if the document does not exist
+    const LEGACY_FILE: Option<&'static str> = None;
+
+    fn version() -> u32 {
+        Self::MIGRATIONS.len() as u32
+    }
+}
+
+/// The data is unchanged, e.g. the first version only adds the envelope
+pub fn unchanged(value: Value) -> Result<Value, String> {
+    Ok(value)
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    IoError(std::io::Error),
+    JsonError(serde_json::Error),
+    /// Written by a newer version of the bot
+    UnknownVersion { name: &'static str, version: u64 },
+    MigrationFailed { name: &'static str, version: u32, reason: String },
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(value: std::io::Error) -> Self {
+        StorageError::IoError(value)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(value: serde_json::Error) -> Self {
+        StorageError::JsonError(value)
+    }
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::IoError(e) => write!(f, "IO Error: {e}"),
+            StorageError::JsonError(e) => write!(f, "JSON Error: {e}"),
+            StorageError::UnknownVersion { name, version } => write!(f, "{name} has unknown version {version}"),
+            StorageError::MigrationFailed { name, version, reason } => {
+                write!(f, "Failed to migrate {name} from version {version}: {reason}")
+            }
+        }
+    }
+}
+
+impl Error for StorageError {}
+
+/// Versioned documents in the data directory. A write goes to a temp file which replaces the document,
+/// writes of a document are serialized so an older snapshot never overwrites a newer one
+#[derive(Debug)]
+pub struct Storage {
+    dir: PathBuf,
+    data_root_path: PathBuf,
+    locks: DashMap<&'static str, Arc<Mutex<()>>>,
+}
+
+impl Storage {
+    pub fn new(data_root_path: &Path) -> Storage {
+        Storage {
+            dir: data_root_path.join(STORE_DIR_NAME),
+            data_root_path: data_root_path.to_path_buf(),
+            locks: DashMap::new(),
+        }
+    }
+
+    fn path<D: Document>(&self) -> PathBuf {
+        self.dir.join(D::NAME)
+    }
+
+    /// Read the document, migrated to the current version, or imported from the legacy file.
+    /// Returns None if neither exists
+    pub fn load<D: Document>(&self) -> Result<Option<D::Data>, StorageError> {
+        let (value, version, legacy_path) = match std::fs::read(self.path::<D>()) {
+            Ok(content) => {
+                let (value, version) = open_envelope::<D>(serde_json::from_slice(&content)?)?;
+                (value, version, None)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let Some(legacy_path) = D::LEGACY_FILE.map(|file| self.data_root_path.join(file)) else {
+                    return Ok(None);
+                };
+                match std::fs::read(&legacy_path) {
+                    Ok(content) => (serde_json::from_slice(&content)?, 0, Some(legacy_path)),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut value = value;
+        for (index, migration) in D::MIGRATIONS.iter().enumerate().skip(version as usize) {
+            value = migration(value).map_err(|reason| StorageError::MigrationFailed {
+                name: D::NAME, version: index as u32, reason
+            })?;
+        }
+        let data = serde_json::from_value(value)?;
+
+        if version < D::version() {
+            log::info!(target: "storage", "Migrated {} from version {version} to {}", D::NAME, D::version());
+            write_atomic_blocking(&self.dir, &self.path::<D>(), &envelope::<D>(&data)?)?;
+        }
+        // Kept for reference, the document is used from now on
+        if let Some(legacy_path) = legacy_path {
+            let mut imported = legacy_path.clone().into_os_string();
+            imported.push(".imported");
+            std::fs::rename(&legacy_path, imported)?;
+            log::info!(target: "storage", "Imported {} into the store", legacy_path.to_string_lossy());
+        }
+        Ok(Some(data))
+    }
+
+    /// Write the document, the snapshot is taken after the previous write of the document finishes
+    pub async fn save<D: Document>(&self, snapshot: impl FnOnce() -> D::Data) -> Result<(), StorageError> {
+        let lock = self.locks.entry(D::NAME).or_default().clone();
+        let _guard = lock.lock().await;
+        let content = envelope::<D>(&snapshot())?;
+        write_atomic(&self.dir, &self.path::<D>(), &content).await
+    }
+}
+
+fn envelope<D: Document>(data: &D::Data) -> Result<Vec<u8>, StorageError> {
+    Ok(serde_json::to_vec(&json!({ "version": D::version(), "data": data }))?)
+}
+
+/// Files without the envelope are version 0
+fn open_envelope<D: Document>(value: Value) -> Result<(Value, u32), StorageError> {
+    let Value::Object(mut object) = value else {
+        return Ok((value, 0));
+    };
+    let (Some(version), true) = (object.get("version").and_then(Value::as_u64), object.contains_key("data")) else {
+        return Ok((Value::Object(object), 0));
+    };
+    if version > D::version() as u64 {
+        return Err(StorageError::UnknownVersion { name: D::NAME, version });
+    }
+    Ok((object.remove("data").unwrap_or_default(), version as u32))
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut temp = path.to_path_buf().into_os_string();
+    temp.push(".tmp");
+    PathBuf::from(temp)
+}
+
+async fn write_atomic(dir: &Path, path: &Path, content: &[u8]) -> Result<(), StorageError> {
+    tokio::fs::create_dir_all(dir).await?;
+    let temp = temp_path(path);
+    let mut file = tokio::fs::File::create(&temp).await?;
+    file.write_all(content).await?;
+    file.sync_all().await?;
+    tokio::fs::rename(&temp, path).await?;
+    Ok(())
+}
+
+fn write_atomic_blocking(dir: &Path, path: &Path, content: &[u8]) -> Result<(), StorageError> {
+    std::fs::create_dir_all(dir)?;
+    let temp = temp_path(path);
+    let mut file = std::fs::File::create(&temp)?;
+    std::io::Write::write_all(&mut file, content)?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        count: u64,
+    }
+
+    struct CounterDocument;
+
+    /// Version 1 renames `value` to `count`
+    fn rename_value(value: Value) -> Result<Value, String> {
+        let Value::Object(mut object) = value else {
+            return Err("not an object".to_string());
+        };
+        if let Some(count) = object.remove("value") {
+            object.insert("count".to_string(), count);
+        }
+        Ok(Value::Object(object))
+    }
+
+    impl Document for CounterDocument {
+        type Data = Counter;
+
+        const NAME: &'static str = "counter.json";
+        const MIGRATIONS: &'static [Migration] = &[unchanged, rename_value];
+        const LEGACY_FILE: Option<&'static str> = Some("counter.json");
+    }
+
+    fn read_json(path: &Path) -> Value {
+        serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn missing_document() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Storage::new(dir.path()).load::<CounterDocument>().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path());
+        storage.save::<CounterDocument>(|| Counter { count: 3 }).await.unwrap();
+
+        let path = dir.path().join(STORE_DIR_NAME).join(CounterDocument::NAME);
+        assert_eq!(read_json(&path), json!({ "version": 2, "data": { "count": 3 } }));
+        assert!(!temp_path(&path).exists());
+        assert_eq!(storage.load::<CounterDocument>().unwrap(), Some(Counter { count: 3 }));
+    }
+
+    #[test]
+    fn imports_and_migrates_legacy_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("counter.json"), r#"{"value": 7}"#).unwrap();
+        let storage = Storage::new(dir.path());
+        assert_eq!(storage.load::<CounterDocument>().unwrap(), Some(Counter { count: 7 }));
+
+        // Kept for reference, and never imported again
+        assert!(!dir.path().join("counter.json").exists());
+        assert!(dir.path().join("counter.json.imported").exists());
+        let path = dir.path().join(STORE_DIR_NAME).join(CounterDocument::NAME);
+        assert_eq!(read_json(&path), json!({ "version": 2, "data": { "count": 7 } }));
+    }
+
+    #[test]
+    fn migrates_from_stored_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let store_dir = dir.path().join(STORE_DIR_NAME);
+        std::fs::create_dir_all(&store_dir).unwrap();
+        std::fs::write(store_dir.join("counter.json"), r#"{"version": 1, "data": {"value": 5}}"#).unwrap();
+        assert_eq!(Storage::new(dir.path()).load::<CounterDocument>().unwrap(), Some(Counter { count: 5 }));
+        assert_eq!(read_json(&store_dir.join("counter.json"))["version"], 2);
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let store_dir = dir.path().join(STORE_DIR_NAME);
+        std::fs::create_dir_all(&store_dir).unwrap();
+        std::fs::write(store_dir.join("counter.json"), r#"{"version": 3, "data": {"count": 1}}"#).unwrap();
+        let result = Storage::new(dir.path()).load::<CounterDocument>();
+        assert!(matches!(result, Err(StorageError::UnknownVersion { version: 3, .. })));
+    }
+
+    #[test]
+    fn reports_failed_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("counter.json"), "[1, 2]").unwrap();
+        let result = Storage::new(dir.path()).load::<CounterDocument>();
+        assert!(matches!(result, Err(StorageError::MigrationFailed { version: 1, .. })));
+        // Not imported, so it can be fixed by hand
+        assert!(dir.path().join("counter.json").exists());
+    }
+}
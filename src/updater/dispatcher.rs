@@ -0,0 +1,231 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use frankenstein::updates::{Update, UpdateContent};
+use tokio::sync::{mpsc, oneshot};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::{sleep, Duration};
+use tokio_util::task::TaskTracker;
+
+use crate::callback::callback_message;
+use crate::context::Context;
+use crate::handle_update;
+use crate::helper::message_utils::get_chat_sender;
+use crate::types::ChatSender;
+
+/// Workers exit after being idle for this long, and are spawned again on the next update
+const WORKER_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+tokio::task_local! {
+    /// Set while an update runs on a worker, taken by `release_worker`
+    static WORKER_RELEASE: RefCell<Option<oneshot::Sender<()>>>;
+}
+
+/// Let the worker go on with the next updates of the ChatSender while this update keeps running,
+/// called by long handlers such as download jobs once the request is accepted. Does nothing outside a worker
+pub fn release_worker() {
+    let _ = WORKER_RELEASE.try_with(|release| {
+        if let Some(release) = release.borrow_mut().take() {
+            let _ = release.send(());
+        }
+    });
+}
+
+/// Processes updates from the same ChatSender one by one, in the order they are received.
+/// Updates of different ChatSenders still run concurrently, and so do updates released by `release_worker`.
+#[derive(Clone)]
+pub struct Dispatcher {
+    ctx: Arc<Context>,
+    queues: Arc<DashMap<ChatSender, mpsc::Sender<Update>>>,
+}
+
+impl Dispatcher {
+    pub fn new(ctx: Arc<Context>) -> Dispatcher {
+        Dispatcher {
+            ctx,
+            queues: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Enqueue the update, waits if the queue of the ChatSender is full
+    pub async fn dispatch(&self, update: Update) {
+        let Some(key) = update_chat_sender(&update) else {
+            // Nothing to keep in order
+            let ctx = self.ctx.clone();
+            self.ctx.tasks.spawn(async move {
+                handle_update(ctx, update).await;
+            });
+            return;
+        };
+
+        let mut update = update;
+        loop {
+            let sender = {
+                // Enqueue under the map lock, so the worker can not exit in between
+                let entry = self.queues.entry(key).or_insert_with(|| self.spawn_worker(key));
+                match entry.try_send(update) {
+                    Ok(()) => return,
+                    Err(TrySendError::Full(returned)) => {
+                        update = returned;
+                        entry.clone()
+                    }
+                    Err(TrySendError::Closed(returned)) => {
+                        update = returned;
+                        drop(entry);
+                        self.queues.remove(&key);
+                        continue;
+                    }
+                }
+            };
+
+            log::debug!(
+                target: "dispatcher",
+                "Update queue of {}:{} is full, waiting", key.chat_id, key.sender_id
+            );
+            match sender.send(update).await {
+                Ok(()) => return,
+                // The worker exited while waiting, retry with a new one
+                Err(mpsc::error::SendError(returned)) => update = returned,
+            }
+        }
+    }
+
+    fn spawn_worker(&self, key: ChatSender) -> mpsc::Sender<Update> {
+        let (sender, receiver) = mpsc::channel(self.ctx.config.telegram.update_queue_size);
+        let ctx = self.ctx.clone();
+        let queues = self.queues.clone();
+        self.ctx.tasks.spawn(async move {
+            dispatch_worker(ctx, queues, key, receiver).await;
+        });
+        sender
+    }
+}
+
+async fn dispatch_worker(
+    ctx: Arc<Context>,
+    queues: Arc<DashMap<ChatSender, mpsc::Sender<Update>>>,
+    key: ChatSender,
+    mut receiver: mpsc::Receiver<Update>,
+) {
+    loop {
+        let update = tokio::select! {
+            biased;
+            update = receiver.recv() => update,
+            _ = ctx.shutdown.cancelled() => None,
+            _ = sleep(WORKER_IDLE_TIMEOUT) => None,
+        };
+        match update {
+            Some(update) => run_in_order(&ctx.tasks, handle_update(ctx.clone(), update)).await,
+            None => {
+                // Checked under the map lock, updates may be queued right before
+                if queues.remove_if(&key, |_, _| receiver.is_empty()).is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Updates sent by waiting senders before the queue is closed
+    receiver.close();
+    while let Ok(update) = receiver.try_recv() {
+        run_in_order(&ctx.tasks, handle_update(ctx.clone(), update)).await;
+    }
+}
+
+/// Run the update on the tracker, returns when it finishes or calls `release_worker`
+async fn run_in_order<F>(tasks: &TaskTracker, update: F)
+where
+    F: Future<Output = ()> + Send + 'static
+{
+    let (release, released) = oneshot::channel();
+    tasks.spawn(WORKER_RELEASE.scope(RefCell::new(Some(release)), update));
+    // Err if the update finishes, or panics, without releasing the worker
+    let _ = released.await;
+}
+
+/// The ChatSender an update belongs to, None if it does not need to be kept in order
+fn update_chat_sender(update: &Update) -> Option<ChatSender> {
+    match &update.content {
+        UpdateContent::Message(msg) |
+        UpdateContent::EditedMessage(msg) |
+        UpdateContent::ChannelPost(msg) |
+        UpdateContent::EditedChannelPost(msg) => Some(get_chat_sender(msg)),
+        UpdateContent::CallbackQuery(query) => {
+            let msg = callback_message(query)?;
+            Some((msg.chat.id, query.from.id as i64).into())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::config::JobsConfig;
+    use crate::jobs::scheduler::{JobKind, JobScheduler};
+
+    #[tokio::test]
+    async fn short_updates_run_in_order() {
+        let tasks = TaskTracker::new();
+        let order = Arc::new(Mutex::new(vec![]));
+        for index in 0..3 {
+            let order = order.clone();
+            run_in_order(&tasks, async move {
+                // The first update is the slowest, it still finishes first
+                sleep(Duration::from_millis(30 - index * 10)).await;
+                order.lock().unwrap().push(index);
+            }).await;
+        }
+        tasks.close();
+        tasks.wait().await;
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn released_update_does_not_block_the_worker() {
+        let tasks = TaskTracker::new();
+        let (done_sender, done) = oneshot::channel();
+        run_in_order(&tasks, async move {
+            release_worker();
+            let _ = done.await;
+        }).await;
+        // Would never return if the worker waited for the first update
+        tokio::time::timeout(Duration::from_secs(1), run_in_order(&tasks, async move {
+            let _ = done_sender.send(());
+        })).await.unwrap();
+        tasks.close();
+        tasks.wait().await;
+    }
+
+    #[tokio::test]
+    async fn cancel_reaches_running_job() {
+        let tasks = TaskTracker::new();
+        let jobs = Arc::new(JobScheduler::new(JobsConfig::default()));
+        let (cancelled_sender, cancelled) = oneshot::channel();
+
+        // A download job, released like `start_job` does
+        let job_jobs = jobs.clone();
+        run_in_order(&tasks, async move {
+            release_worker();
+            let (guard, queue_position) = job_jobs.submit(JobKind::Pixiv, 1, Some(2), "123".to_string());
+            assert!(queue_position.is_none());
+            guard.token().cancelled().await;
+            let _ = cancelled_sender.send(guard.check().is_err());
+        }).await;
+
+        // /cancel of the same sender, queued behind the job
+        let cancel_jobs = jobs.clone();
+        run_in_order(&tasks, async move {
+            assert_eq!(cancel_jobs.cancel(|job| job.chat_id == 1 && job.user_id == Some(2)), 1);
+        }).await;
+
+        let cancelled = tokio::time::timeout(Duration::from_secs(1), cancelled).await.unwrap().unwrap();
+        assert!(cancelled);
+        tasks.close();
+        tasks.wait().await;
+        assert!(jobs.snapshot().0.is_empty());
+    }
+}
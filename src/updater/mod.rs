@@ -0,0 +1,67 @@
+pub mod dispatcher;
+mod offset;
+mod polling;
+mod webhook;
+
+use std::sync::Arc;
+
+use frankenstein::AsyncTelegramApi;
+use frankenstein::methods::{DeleteWebhookParams, SetWebhookParams};
+use frankenstein::updates::{Update, UpdateContent};
+
+use crate::config::BacklogMode;
+use crate::context::Context;
+use crate::updater::dispatcher::Dispatcher;
+
+/// Receive updates by webhook if configured, otherwise by long polling
+pub async fn run_updater(ctx: Arc<Context>) -> anyhow::Result<()> {
+    let dispatcher = Dispatcher::new(ctx.clone());
+    match ctx.config.telegram.webhook.clone() {
+        Some(webhook_config) => {
+            // Telegram keeps the pending updates, drop them here in skip mode
+            let drop_pending_updates = webhook_config.drop_pending_updates ||
+                ctx.config.telegram.backlog.mode == BacklogMode::Skip;
+            let param = SetWebhookParams::builder()
+                .url(&webhook_config.url)
+                .maybe_secret_token(webhook_config.secret_token.clone())
+                .maybe_max_connections(webhook_config.max_connections)
+                .drop_pending_updates(drop_pending_updates)
+                .build();
+            ctx.bot.set_webhook(&param).await?;
+            log::info!(target: "updater", "Webhook set to {}", webhook_config.url);
+
+            webhook::webhook_server(ctx, dispatcher, webhook_config).await
+        }
+        None => {
+            // Polling is rejected by Telegram while a webhook is set
+            if let Err(e) = ctx.bot.delete_webhook(&DeleteWebhookParams::builder().build()).await {
+                log::warn!(target: "updater", "Failed to delete webhook: {e}");
+            }
+
+            polling::polling_loop(ctx, dispatcher).await
+        }
+    }
+}
+
+/// Time when the update is sent, only available for message updates
+fn update_date(update: &Update) -> Option<u64> {
+    match &update.content {
+        UpdateContent::Message(msg) |
+        UpdateContent::ChannelPost(msg) => Some(msg.date),
+        UpdateContent::EditedMessage(msg) |
+        UpdateContent::EditedChannelPost(msg) => Some(msg.edit_date.unwrap_or(msg.date)),
+        _ => None,
+    }
+}
+
+/// Check if the update is older than the configured maximum age
+fn is_stale(ctx: &Context, update: &Update) -> bool {
+    let Some(max_age) = ctx.config.telegram.backlog.max_age else {
+        return false;
+    };
+    let Some(date) = update_date(update) else {
+        return false;
+    };
+    let now = chrono::Utc::now().timestamp() as u64;
+    now.saturating_sub(date) > max_age
+}
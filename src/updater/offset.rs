@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{Document, Migration, Storage, unchanged};
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+struct SavedOffset {
+    offset: i64,
+}
+
+struct UpdateOffsetDocument;
+
+impl Document for UpdateOffsetDocument {
+    type Data = SavedOffset;
+
+    const NAME: &'static str = "update_offset.json";
+    const MIGRATIONS: &'static [Migration] = &[unchanged];
+    const LEGACY_FILE: Option<&'static str> = Some("update_offset.json");
+}
+
+/// Persisted offset of the last confirmed update in polling mode
+#[derive(Debug)]
+pub struct OffsetStore {
+    storage: Arc<Storage>,
+    saved: i64,
+}
+
+impl OffsetStore {
+    pub fn load(storage: Arc<Storage>) -> OffsetStore {
+        let saved = match storage.load::<UpdateOffsetDocument>() {
+            Ok(saved) => saved.unwrap_or_default().offset,
+            Err(e) => {
+                log::warn!(target: "update_offset", "Failed to load saved update offset: {e}");
+                0
+            }
+        };
+        OffsetStore { storage, saved }
+    }
+
+    pub fn offset(&self) -> i64 { self.saved }
+
+    /// Write the offset to file if changed
+    pub async fn save(&mut self, offset: i64) {
+        if offset == self.saved {
+            return;
+        }
+        if let Err(e) = self.storage.save::<UpdateOffsetDocument>(|| SavedOffset { offset }).await {
+            log::warn!(target: "update_offset", "Failed to save update offset: {e}");
+            return;
+        }
+        self.saved = offset;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn offset_is_imported_and_saved_in_the_store() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("update_offset.json"), r#"{"offset":42}"#).unwrap();
+
+        let storage = Arc::new(Storage::new(dir.path()));
+        let mut store = OffsetStore::load(storage.clone());
+        assert_eq!(store.offset(), 42);
+
+        store.save(43).await;
+        assert_eq!(OffsetStore::load(storage).offset(), 43);
+        assert!(!dir.path().join("update_offset.json").exists());
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use frankenstein::AsyncTelegramApi;
+use frankenstein::methods::GetUpdatesParams;
+use tokio::time::{sleep, Duration};
+
+use crate::config::BacklogMode;
+use crate::context::Context;
+use crate::updater::dispatcher::Dispatcher;
+use crate::updater::is_stale;
+use crate::updater::offset::OffsetStore;
+
+pub async fn polling_loop(ctx: Arc<Context>, dispatcher: Dispatcher) -> anyhow::Result<()> {
+    log::info!(target: "update_loop", "Receiving updates by long polling");
+
+    let mut offset_store = OffsetStore::load(ctx.storage.clone());
+    let mut update_id: i64 = offset_store.offset();
+
+    if ctx.config.telegram.backlog.mode == BacklogMode::Skip {
+        update_id = skip_backlog(&ctx).await.unwrap_or(update_id);
+    }
+    log::info!(target: "update_loop", "Resuming from update offset {update_id}");
+
+    'update_loop: loop {
+        let get_updates_param = GetUpdatesParams::builder()
+            .offset(update_id)
+            .timeout(ctx.config.telegram.polling_timeout)
+            .build();
+        let result = tokio::select! {
+            result = ctx.bot.get_updates(&get_updates_param) => result,
+            _ = ctx.shutdown.cancelled() => break 'update_loop,
+        };
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                log::error!(target: "update_loop", "Failed to get updates: {e}");
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(5)) => {},
+                    _ = ctx.shutdown.cancelled() => break 'update_loop,
+                };
+                continue 'update_loop;
+            }
+        }.result;
+        for update in result {
+            update_id = i64::max(update_id, update.update_id as i64 + 1);
+            if is_stale(&ctx, &update) {
+                log::debug!(target: "update_loop", "Ignoring stale update {}", update.update_id);
+                continue;
+            }
+            // Waits here if the queue is full, which holds back the polling
+            dispatcher.dispatch(update).await;
+        }
+        offset_store.save(update_id).await;
+    }
+
+    // Confirm the received updates, so they are not delivered again after restart
+    if update_id > 0 {
+        let confirm_param = GetUpdatesParams::builder()
+            .offset(update_id)
+            .timeout(0)
+            .limit(1)
+            .build();
+        if let Err(e) = ctx.bot.get_updates(&confirm_param).await {
+            log::warn!(target: "update_loop", "Failed to confirm received updates: {e}");
+        }
+    }
+    offset_store.save(update_id).await;
+
+    Ok(())
+}
+
+/// Returns the offset next to the latest pending update, so the earlier ones are dropped
+async fn skip_backlog(ctx: &Context) -> Option<i64> {
+    // Negative offset returns updates from the end of the queue
+    let param = GetUpdatesParams::builder()
+        .offset(-1)
+        .timeout(0)
+        .build();
+    match ctx.bot.get_updates(&param).await {
+        Ok(response) => {
+            let latest = response.result.last()?;
+            log::info!(target: "update_loop", "Skipping pending updates until {}", latest.update_id);
+            Some(latest.update_id as i64 + 1)
+        }
+        Err(e) => {
+            log::warn!(target: "update_loop", "Failed to skip pending updates: {e}");
+            None
+        }
+    }
+}
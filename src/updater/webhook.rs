@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use frankenstein::updates::Update;
+
+use crate::config::WebhookConfig;
+use crate::context::Context;
+use crate::updater::dispatcher::Dispatcher;
+use crate::updater::is_stale;
+
+const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+/// Where received updates go, the dispatcher outside tests
+pub trait UpdateSink: Clone + Send + Sync + 'static {
+    fn dispatch(&self, update: Update) -> impl Future<Output = ()> + Send;
+}
+
+impl UpdateSink for Dispatcher {
+    fn dispatch(&self, update: Update) -> impl Future<Output = ()> + Send {
+        Dispatcher::dispatch(self, update)
+    }
+}
+
+#[derive(Clone)]
+struct WebhookState<S: UpdateSink> {
+    ctx: Arc<Context>,
+    dispatcher: S,
+    secret_token: Option<String>,
+}
+
+pub async fn webhook_server(ctx: Arc<Context>, dispatcher: Dispatcher, config: WebhookConfig) -> anyhow::Result<()> {
+    let shutdown = ctx.shutdown.clone();
+    let state = WebhookState {
+        ctx,
+        dispatcher,
+        secret_token: config.secret_token.clone(),
+    };
+    let app = webhook_router(state, &config.path);
+
+    let listener = tokio::net::TcpListener::bind(&config.listen).await?;
+    log::info!(target: "webhook", "Receiving updates by webhook on {}{}", config.listen, config.path);
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await?;
+    Ok(())
+}
+
+fn webhook_router<S: UpdateSink>(state: WebhookState<S>, path: &str) -> Router {
+    Router::new()
+        .route(path, post(receive_update::<S>))
+        .with_state(state)
+}
+
+async fn receive_update<S: UpdateSink>(
+    State(state): State<WebhookState<S>>,
+    headers: HeaderMap,
+    body: String,
+) -> StatusCode {
+    if let Some(secret_token) = state.secret_token.as_ref() {
+        let received = headers.get(SECRET_TOKEN_HEADER).and_then(|value| value.to_str().ok());
+        if received != Some(secret_token.as_str()) {
+            log::warn!(target: "webhook", "Rejected update with invalid secret token");
+            return StatusCode::UNAUTHORIZED;
+        }
+    }
+
+    let update: Update = match serde_json::from_str(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            log::warn!(target: "webhook", "Failed to parse update: {e}");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    if is_stale(&state.ctx, &update) {
+        log::debug!(target: "webhook", "Ignoring stale update {}", update.update_id);
+        return StatusCode::OK;
+    }
+
+    // Respond once queued, the response is delayed if the queue is full
+    state.dispatcher.dispatch(update).await;
+
+    StatusCode::OK
+}
+
+#[cfg(test)]
+mod tests {
+    use frankenstein::client_reqwest::Bot;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::config::BotConfig;
+    use crate::helper::telegram_client::TelegramClient;
+
+    const SECRET_TOKEN: &str = "secret";
+
+    #[derive(Clone)]
+    struct ChannelSink(mpsc::UnboundedSender<Update>);
+
+    impl UpdateSink for ChannelSink {
+        async fn dispatch(&self, update: Update) {
+            let _ = self.0.send(update);
+        }
+    }
+
+    /// Serve the webhook on an ephemeral port, returns the URL and the dispatched updates
+    async fn serve() -> (String, mpsc::UnboundedReceiver<Update>, TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let config: BotConfig = serde_json::from_value(json!({
+            "telegram": { "token": "0:test", "bot_api_server": "http://127.0.0.1:9" },
+            "telegraph": { "access_token": "" },
+            "sticker": {},
+            "pixiv": {},
+            "kemono": {},
+        })).unwrap();
+        let bot = TelegramClient::new(Bot::new_url("http://127.0.0.1:9/bot0:test"), config.telegram.api_call.clone());
+        let ctx = Arc::new(Context::_new(bot, config, dir.path().join("temp"), dir.path().join("data")));
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let state = WebhookState { ctx, dispatcher: ChannelSink(sender), secret_token: Some(SECRET_TOKEN.to_string()) };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, webhook_router(state, "/webhook")).await });
+        (url, receiver, dir)
+    }
+
+    fn update_json() -> serde_json::Value {
+        json!({
+            "update_id": 42,
+            "message": {
+                "message_id": 1,
+                "date": 0,
+                "chat": { "id": 1, "type": "private", "first_name": "test" },
+                "from": { "id": 1, "is_bot": false, "first_name": "test" },
+                "text": "/help",
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_secret_token() {
+        let (url, mut updates, _dir) = serve().await;
+        let client = reqwest::Client::new();
+        for token in [None, Some("wrong")] {
+            let mut request = client.post(&url).json(&update_json());
+            if let Some(token) = token {
+                request = request.header(SECRET_TOKEN_HEADER, token);
+            }
+            let response = request.send().await.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        }
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn dispatches_valid_update() {
+        let (url, mut updates, _dir) = serve().await;
+        let response = reqwest::Client::new().post(&url)
+            .header(SECRET_TOKEN_HEADER, SECRET_TOKEN)
+            .json(&update_json())
+            .send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(updates.recv().await.unwrap().update_id, 42);
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_update() {
+        let (url, mut updates, _dir) = serve().await;
+        let response = reqwest::Client::new().post(&url)
+            .header(SECRET_TOKEN_HEADER, SECRET_TOKEN)
+            .body("{}")
+            .send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        assert!(updates.try_recv().is_err());
+    }
+}